@@ -20,20 +20,32 @@ pub enum Commands {
     },
     /// 启动指定JAR包
     Start { 
-        /// JAR文件名
+        /// JAR文件名、应用名或别名 (<jar>@<实例名> 表示实例)
         jar: String, 
-        /// Java启动参数
+        /// 实例名，PID、日志和启动参数按实例分开保存
+        #[arg(short, long)]
+        instance: Option<String>,
+        /// 扩缩到编号实例 1..N，停止编号超出的实例
+        #[arg(long, conflicts_with = "instance")]
+        scale: Option<usize>,
+        /// Java启动参数，可使用 ${INSTANCE}、${PORT_OFFSET}、${8080+PORT_OFFSET}
         args: Vec<String> 
     },
     /// 停止指定JAR包
     Stop { 
-        /// JAR文件名
-        jar: String 
+        /// JAR文件名、应用名或别名 (<jar>@<实例名> 表示实例)
+        jar: String,
+        /// 实例名
+        #[arg(short, long)]
+        instance: Option<String>,
     },
     /// 重启指定JAR包
     Restart { 
-        /// JAR文件名
+        /// JAR文件名、应用名或别名 (<jar>@<实例名> 表示实例)
         jar: String, 
+        /// 实例名
+        #[arg(short, long)]
+        instance: Option<String>,
         /// Java启动参数
         args: Vec<String> 
     },
@@ -43,30 +55,60 @@ pub enum Commands {
         jar: Option<String> 
     },
     /// 列出所有可用JAR文件
-    List,
+    List {
+        /// 显示清单中的标题和版本
+        #[arg(short, long)]
+        details: bool,
+    },
+    /// 检查JAR内容：清单、主类、Spring Boot 布局、依赖和所需的 Java 版本
+    Inspect {
+        /// JAR文件名、应用名或别名
+        jar: String,
+        /// 显示全部清单属性
+        #[arg(short, long)]
+        manifest: bool,
+    },
     /// 查看JAR日志
     Log { 
-        /// JAR文件名
+        /// JAR文件名、应用名或别名
         jar: String, 
         /// 显示行数
         #[arg(default_value = "20")] 
-        lines: u32 
+        lines: u32,
+        /// 只显示错误输出 (stderr)
+        #[arg(long)]
+        stderr: bool,
+        /// 查看指定编号的运行会话 (见 'logs sessions')
+        #[arg(long, conflicts_with = "previous")]
+        session: Option<usize>,
+        /// 查看上一次运行的会话
+        #[arg(long)]
+        previous: bool,
+        /// 持续跟踪新写入的日志 (类似 tail -f)
+        #[arg(short, long, conflicts_with_all = ["session", "previous"])]
+        follow: bool,
+        /// JSON 日志只显示指定字段，逗号分隔 (如 timestamp,level,mdc.traceId,message)
+        #[arg(long, value_delimiter = ',')]
+        fields: Vec<String>,
+        /// 按 JSON 字段过滤，可多次指定: key=value、key!=value、key~正则
+        #[arg(short = 'w', long = "where")]
+        filters: Vec<String>,
     },
     /// 强制杀死JAR进程
     Kill { 
-        /// JAR文件名
+        /// JAR文件名、应用名或别名
         jar: String 
     },
     /// 配置JAR参数
     Config { 
-        /// JAR文件名
+        /// JAR文件名、应用名或别名
         jar: String, 
         /// 启动参数
         args: Vec<String> 
     },
     /// 用已保存配置快速启动JAR
     Quick { 
-        /// JAR文件名
+        /// JAR文件名、应用名或别名
         jar: String 
     },
     /// 批量操作
@@ -103,6 +145,94 @@ pub enum Commands {
         #[command(subcommand)]
         action: DaemonAction 
     },
+    /// 线程转储分析：线程状态、死锁、锁等待链和最繁忙的线程
+    Threads {
+        /// JAR文件名、应用名或别名
+        jar: String,
+        /// 转储次数
+        #[arg(short = 'n', long, default_value = "1")]
+        count: u32,
+        /// 两次转储之间的间隔 (秒)
+        #[arg(short, long, default_value = "5")]
+        interval: u64,
+        /// 显示最繁忙的前N个线程
+        #[arg(short, long, default_value = "5")]
+        top: usize,
+        /// 每个线程显示的栈帧数
+        #[arg(short, long, default_value = "8")]
+        depth: usize,
+    },
+    /// 堆诊断：类直方图快照、对比和堆转储
+    Heap {
+        #[command(subcommand)]
+        action: HeapAction,
+    },
+    /// 汇总 GC 日志：暂停次数和时间分位数、堆占用、收集器
+    Gc {
+        /// JAR文件名、应用名或别名
+        jar: String,
+        /// 显示最长的前N次暂停
+        #[arg(short, long, default_value = "5")]
+        top: usize,
+    },
+    /// 部署新版本：保存到版本目录，替换JAR并重启，就绪检查失败时自动回滚
+    Deploy {
+        /// 应用名、别名或JAR文件名
+        app: String,
+        /// 新版本JAR文件
+        artifact: String,
+    },
+    /// 列出已部署的版本
+    Releases {
+        /// 应用名、别名或JAR文件名
+        app: String,
+    },
+    /// 回滚到指定版本 (版本标识、版本号或哈希前缀)，默认回滚到上一个版本
+    Rollback {
+        /// 应用名、别名或JAR文件名
+        app: String,
+        /// 目标版本
+        version: Option<String>,
+    },
+    /// 应用注册表：逻辑应用名对应JAR路径或通配符，以及别名
+    Apps {
+        #[command(subcommand)]
+        action: AppsAction,
+    },
+    /// 列出本机安装的 JDK (/usr/lib/jvm、JAVA_HOME、SDKMAN 等) 及版本和发行方
+    Jdks,
+    /// 列出监听中的 TCP 端口及所属的JAR (读取 /proc/net/tcp)
+    Ports {
+        /// 只显示受管理的JAR的端口
+        #[arg(short, long)]
+        managed: bool,
+    },
+    /// Java Flight Recorder 录制控制
+    Jfr {
+        #[command(subcommand)]
+        action: JfrAction,
+    },
+    /// 导出诊断包 (tar.gz)：日志、配置（已脱敏）、进程和系统信息
+    Bundle {
+        /// JAR文件名、应用名或别名
+        #[arg(required_unless_present = "all", conflicts_with = "all")]
+        jar: Option<String>,
+        /// 包含查找目录中的所有JAR
+        #[arg(long)]
+        all: bool,
+        /// 附带线程转储 (jcmd 或 SIGQUIT)
+        #[arg(long)]
+        threads: bool,
+        /// 每个活动日志保留的尾部行数
+        #[arg(long, default_value = "5000")]
+        lines: usize,
+        /// 每个活动日志附带的最近轮转代数量
+        #[arg(long, default_value = "3")]
+        rotated: usize,
+        /// 输出文件路径 (默认 .Manager_Jar/bundles/bundle-<名称>-<时间>.tar.gz)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
     /// 显示版本信息
     Version,
 }
@@ -114,6 +244,127 @@ pub enum LogsAction {
     List,
     /// 清理所有日志文件
     Clean,
+    /// 列出JAR日志中的运行会话
+    Sessions {
+        /// JAR文件名、应用名或别名
+        jar: String,
+    },
+    /// 在日志中搜索（含轮转和压缩的旧日志）
+    #[command(alias = "search")]
+    Grep {
+        /// JAR文件名、应用名或别名
+        jar: String,
+        /// 正则表达式
+        pattern: String,
+        /// 忽略大小写
+        #[arg(short, long)]
+        ignore_case: bool,
+        /// JSON 日志只显示指定字段，逗号分隔
+        #[arg(long, value_delimiter = ',')]
+        fields: Vec<String>,
+        /// 按 JSON 字段过滤，可多次指定: key=value、key!=value、key~正则
+        #[arg(short = 'w', long = "where")]
+        filters: Vec<String>,
+    },
+    /// 向 syslog 接收端发送测试消息
+    SyslogTest {
+        /// JAR文件名、应用名或别名
+        jar: String,
+    },
+    /// 汇总日志中的异常堆栈
+    Errors {
+        /// JAR文件名、应用名或别名
+        jar: String,
+        /// 显示出现次数最多的前N类异常
+        #[arg(short, long, default_value = "10")]
+        top: usize,
+    },
+}
+
+/// 堆诊断子命令
+#[derive(clap::Subcommand, Debug)]
+pub enum HeapAction {
+    /// 采集类直方图快照 (jcmd GC.class_histogram)
+    Histo {
+        /// JAR文件名、应用名或别名
+        jar: String,
+        /// 包含不可达对象 (不触发 Full GC)
+        #[arg(long)]
+        all: bool,
+        /// 显示占用最多的前N个类
+        #[arg(short, long, default_value = "20")]
+        top: usize,
+    },
+    /// 对比两次类直方图快照，显示增长最多的类
+    Diff {
+        /// JAR文件名、应用名或别名
+        jar: String,
+        /// 起始快照: 编号、时间戳、文件名、latest 或 previous
+        #[arg(default_value = "previous")]
+        from: String,
+        /// 结束快照
+        #[arg(default_value = "latest")]
+        to: String,
+        /// 显示增长最多的前N个类
+        #[arg(short, long, default_value = "20")]
+        top: usize,
+    },
+    /// 生成堆转储 (.hprof)，按配置保留最近的几个
+    Dump {
+        /// JAR文件名、应用名或别名
+        jar: String,
+        /// 包含不可达对象 (不触发 Full GC)
+        #[arg(long)]
+        all: bool,
+    },
+    /// 列出类直方图快照和堆转储
+    List {
+        /// JAR文件名、应用名或别名
+        jar: String,
+    },
+}
+
+/// JFR 子命令
+#[derive(clap::Subcommand, Debug)]
+pub enum JfrAction {
+    /// 开始录制
+    Start {
+        /// JAR文件名、应用名或别名
+        jar: String,
+        /// 录制名称
+        #[arg(short, long, default_value = "manager")]
+        name: String,
+        /// 录制时长 (如 60s、10m)，到期后自动保存；不指定时一直录制到 stop
+        #[arg(short, long)]
+        duration: Option<String>,
+        /// 设置: default (低开销)、profile (更详细) 或 .jfc 文件路径
+        #[arg(short, long, default_value = "default")]
+        settings: String,
+    },
+    /// 把录制的当前数据保存到文件，录制继续进行
+    Dump {
+        /// JAR文件名、应用名或别名
+        jar: String,
+        /// 录制名称，不指定时保存所有录制
+        #[arg(short, long)]
+        name: Option<String>,
+    },
+    /// 结束录制并保存到文件
+    Stop {
+        /// JAR文件名、应用名或别名
+        jar: String,
+        /// 录制名称
+        #[arg(short, long, default_value = "manager")]
+        name: String,
+        /// 不保存，直接丢弃录制数据
+        #[arg(long)]
+        discard: bool,
+    },
+    /// 列出进行中的录制和已保存的录制文件
+    List {
+        /// JAR文件名、应用名或别名
+        jar: String,
+    },
 }
 
 /// 配置管理子命令
@@ -123,14 +374,54 @@ pub enum ConfigsAction {
     List,
     /// 查看指定JAR的配置
     Show { 
-        /// JAR文件名
+        /// JAR文件名、应用名或别名
         jar: String 
     },
     /// 删除指定JAR的配置
     Delete { 
-        /// JAR文件名
+        /// JAR文件名、应用名或别名
         jar: String 
     },
+    /// 设置指定JAR的选项 (如 log_mode)
+    Set {
+        /// JAR文件名、应用名或别名
+        jar: String,
+        /// 选项名
+        key: String,
+        /// 选项值
+        value: String,
+    },
+}
+
+/// 应用注册表子命令
+#[derive(clap::Subcommand, Debug)]
+pub enum AppsAction {
+    /// 列出注册的应用和别名
+    List,
+    /// 注册应用 (已注册时更新)，如: apps add api 'api-*.jar'
+    Add {
+        /// 应用名
+        name: String,
+        /// JAR路径或通配符 (* 和 ?)，通配符匹配多个时使用版本最新的
+        jar: String,
+    },
+    /// 取消注册应用
+    Remove {
+        /// 应用名
+        name: String,
+    },
+    /// 定义别名
+    Alias {
+        /// 别名
+        alias: String,
+        /// 应用名或JAR文件名
+        app: String,
+    },
+    /// 删除别名
+    Unalias {
+        /// 别名
+        alias: String,
+    },
 }
 
 /// 全局配置管理子命令
@@ -156,6 +447,11 @@ pub enum GlobalConfigAction {
         /// 保存天数
         days: u32 
     },
+    /// 设置日志目录磁盘配额(MB)，0 表示不限制
+    SetLogQuota {
+        /// 配额(MB)
+        size_mb: u64
+    },
     /// 设置日志文件最大大小(MB)
     SetMaxLogSize { 
         /// 文件大小(MB)
@@ -164,8 +460,39 @@ pub enum GlobalConfigAction {
     /// 启用/禁用日志轮转
     SetLogRotation { 
         /// 是否启用
+        #[arg(action = clap::ArgAction::Set)]
         enable: bool 
     },
+    /// 启用/禁用日志管道（时间戳前缀和实时轮转）
+    SetLogPipeline {
+        /// 是否启用
+        #[arg(action = clap::ArgAction::Set)]
+        enable: bool
+    },
+    /// 设置查找JAR的目录 (list、batch、bundle --all)，如: set-search-dirs . /opt/apps -d 2
+    SetSearchDirs {
+        /// 目录列表
+        #[arg(required = true)]
+        dirs: Vec<String>,
+        /// 查找深度，1 表示不进入子目录
+        #[arg(short, long)]
+        depth: Option<usize>,
+    },
+    /// 设置自动分配端口的范围，如: set-port-range 20000-29999
+    SetPortRange {
+        /// 端口范围 <起点>-<终点>
+        range: String,
+    },
+    /// 设置默认 Java：安装目录或主版本号 (如 17)，off 表示使用 PATH 中的 java
+    SetJavaHome {
+        /// 安装目录、主版本号或 off
+        value: String
+    },
+    /// 设置全局 syslog 转发地址 (udp://主机:端口、tcp://主机:端口、unix:///dev/log)，off 表示禁用
+    SetSyslog {
+        /// 接收端地址或 off
+        address: String
+    },
 }
 
 /// 守护进程管理子命令
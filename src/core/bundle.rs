@@ -0,0 +1,277 @@
+// bundle.rs - 诊断包导出
+// 把日志、配置（敏感信息脱敏）、进程信息和系统信息打包成一个 tar.gz，排查问题时一次性提供
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use regex::{Captures, Regex};
+use crate::core::config::{GlobalConfig, JarOptions, GLOBAL_CONFIG_FILE};
+use crate::core::{jdk, logging, process, threads};
+use crate::utils::system;
+
+const BUNDLE_DIR: &str = ".Manager_Jar/bundles";
+const DAEMON_LOG_FILE: &str = ".Manager_Jar/logs/daemon.log";
+
+/// 诊断包选项
+pub struct BundleOptions {
+    /// 每个活动日志保留的尾部行数
+    pub lines: usize,
+    /// 每个活动日志附带的最近轮转代数量
+    pub rotated: usize,
+    /// 是否附带线程转储
+    pub threads: bool,
+    /// 输出文件路径，未指定时写入 .Manager_Jar/bundles
+    pub output: Option<String>,
+}
+
+/// 正在写入的诊断包
+struct Bundle {
+    builder: tar::Builder<GzEncoder<File>>,
+    root: String,
+    mtime: u64,
+    /// 未能收集的项目，写入 summary.txt
+    failures: Vec<String>,
+}
+
+impl Bundle {
+    fn add_text(&mut self, name: &str, content: &str) {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(self.mtime);
+        let path = format!("{}/{}", self.root, name);
+        if let Err(e) = self.builder.append_data(&mut header, &path, content.as_bytes()) {
+            self.failures.push(format!("{}: {}", name, e));
+        }
+    }
+
+    fn add_file(&mut self, name: &str, path: &Path) {
+        let archive_path = format!("{}/{}", self.root, name);
+        if let Err(e) = self.builder.append_path_with_name(path, &archive_path) {
+            self.failures.push(format!("{}: {}", name, e));
+        }
+    }
+
+    /// 读取文本文件并脱敏后加入诊断包，文件不存在时跳过
+    fn add_redacted_file(&mut self, name: &str, path: &Path) {
+        if !path.exists() {
+            return;
+        }
+        match fs::read_to_string(path) {
+            Ok(content) => self.add_text(name, &redact_secrets(&content)),
+            Err(e) => self.failures.push(format!("{}: {}", name, e)),
+        }
+    }
+
+    fn finish(self) -> Result<(), String> {
+        let encoder = self.builder.into_inner().map_err(|e| format!("写入诊断包失败: {}", e))?;
+        encoder.finish().map_err(|e| format!("写入诊断包失败: {}", e))?;
+        Ok(())
+    }
+}
+
+/// 为指定的JAR生成诊断包，返回诊断包路径
+pub fn create_bundle(jars: &[String], label: &str, options: &BundleOptions) -> Result<PathBuf, String> {
+    let now = chrono::Local::now();
+    let root = format!("bundle-{}-{}", label, now.format("%Y%m%d-%H%M%S"));
+    let output = match &options.output {
+        Some(path) => PathBuf::from(path),
+        None => PathBuf::from(BUNDLE_DIR).join(format!("{}.tar.gz", root)),
+    };
+    if let Some(parent) = output.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).map_err(|e| format!("创建目录失败 {}: {}", parent.display(), e))?;
+    }
+    let file = File::create(&output).map_err(|e| format!("创建诊断包失败 {}: {}", output.display(), e))?;
+
+    let mut bundle = Bundle {
+        builder: tar::Builder::new(GzEncoder::new(file, Compression::default())),
+        root,
+        mtime: now.timestamp().max(0) as u64,
+        failures: Vec::new(),
+    };
+
+    print_info!("收集系统信息...");
+    collect_system(&mut bundle);
+    let config = GlobalConfig::load();
+    for jar in jars {
+        print_info!("收集 {} ...", jar.bright_cyan());
+        collect_jar(&mut bundle, &config, jar, options);
+    }
+
+    let mut summary = vec![
+        format!("生成时间: {}", now.format("%Y-%m-%d %H:%M:%S %z")),
+        format!("主机: {}", system::local_hostname()),
+        format!("Manager_Jar: {} ({})", env!("CARGO_PKG_VERSION"), env!("GIT_HASH")),
+        format!("工作目录: {}", std::env::current_dir().map(|d| d.display().to_string()).unwrap_or_default()),
+        format!("JAR: {}", jars.join(", ")),
+        format!("日志尾部行数: {}，轮转代数: {}，线程转储: {}", options.lines, options.rotated, if options.threads { "是" } else { "否" }),
+        "配置文件中的密码、令牌等已脱敏；日志内容保持原样".to_string(),
+    ];
+    if !bundle.failures.is_empty() {
+        summary.push(String::new());
+        summary.push("未能收集的项目:".to_string());
+        summary.extend(bundle.failures.iter().map(|f| format!("  {}", f)));
+        for failure in &bundle.failures {
+            print_warn!("未能收集 {}", failure);
+        }
+    }
+    summary.push(String::new());
+    bundle.add_text("summary.txt", &summary.join("\n"));
+    bundle.finish()?;
+    Ok(output)
+}
+
+/// 系统、Java、全局配置和守护进程信息
+fn collect_system(bundle: &mut Bundle) {
+    let mut os_info = Vec::new();
+    if cfg!(target_os = "windows") {
+        os_info.push(capture("cmd", &["/C", "ver"]));
+        os_info.push(capture("systeminfo", &[]));
+    } else {
+        os_info.push(capture("uname", &["-a"]));
+        if let Ok(release) = fs::read_to_string("/etc/os-release") {
+            os_info.push(release);
+        }
+        os_info.push(capture("sh", &["-c", "ulimit -a"]));
+        os_info.push(capture("df", &["-h", "."]));
+        if let Ok(meminfo) = fs::read_to_string("/proc/meminfo") {
+            os_info.push(meminfo.lines().take(5).collect::<Vec<_>>().join("\n"));
+        }
+    }
+    bundle.add_text("system/os.txt", &os_info.join("\n\n"));
+    bundle.add_text("system/java_version.txt", &capture("java", &["-version"]));
+    bundle.add_text("system/manager_version.txt", &format!(
+        "Manager_Jar {}\ngit: {}\nbuild: {} ({})\nrustc: {}\n",
+        env!("CARGO_PKG_VERSION"), env!("GIT_HASH"), env!("LOCAL_BUILD_TIME"), env!("BUILD_MODE"), env!("RUSTC_VERSION"),
+    ));
+    bundle.add_redacted_file("config/global_config.toml", Path::new(GLOBAL_CONFIG_FILE));
+    bundle.add_text("daemon/status.txt", &capture_self(&["daemon", "status"]));
+    if Path::new(DAEMON_LOG_FILE).exists() {
+        add_log_tail(bundle, "daemon/daemon.log.tail", Path::new(DAEMON_LOG_FILE), 1000, encoding_rs::UTF_8);
+    }
+}
+
+/// 单个JAR的配置、进程信息和日志
+fn collect_jar(bundle: &mut Bundle, config: &GlobalConfig, jar: &str, options: &BundleOptions) {
+    let name = crate::core::registry::state_key(jar);
+    let dir = format!("jars/{}", name);
+
+    bundle.add_text(&format!("{}/status.txt", dir), &redact_secrets(&capture_self(&["status", jar])));
+    bundle.add_text(&format!("{}/sessions.txt", dir), &capture_self(&["logs", "sessions", jar]));
+    bundle.add_redacted_file(&format!("{}/{}.config", dir, name), &PathBuf::from(format!(".Manager_Jar/configs/{}.config", name)));
+    bundle.add_redacted_file(&format!("{}/{}.options.toml", dir, name), &JarOptions::file_path(jar));
+    // JAR可能配置了与系统默认不同的 Java
+    let java_version = match jdk::resolve_java(jar) {
+        Ok(runtime) => format!("java: {}\n来源: {}\n\n{}", runtime.java, runtime.source.as_deref().unwrap_or("PATH"), capture(&runtime.java, &["-version"])),
+        Err(e) => format!("无法确定 java: {}\n", e),
+    };
+    bundle.add_text(&format!("{}/java_version.txt", dir), &java_version);
+
+    // PID 记录和进程信息
+    let pid_file = PathBuf::from(format!(".Manager_Jar/data/{}.pid", name));
+    let recorded = fs::read_to_string(&pid_file).map(|c| c.trim().to_string()).unwrap_or_else(|_| "无".to_string());
+    let pid = process::get_pid(jar);
+    let state = match pid {
+        Some(pid) => format!("运行中 (PID {})", pid),
+        None => "未运行".to_string(),
+    };
+    bundle.add_text(&format!("{}/pid.txt", dir), &format!("PID文件: {}\n记录的PID: {}\n状态: {}\n", pid_file.display(), recorded, state));
+
+    if let Some(pid) = pid {
+        let pid_text = pid.to_string();
+        let process_info = if cfg!(target_os = "windows") {
+            capture("tasklist", &["/V", "/FI", &format!("PID eq {}", pid), "/FO", "LIST"])
+        } else {
+            capture("ps", &["-o", "pid,ppid,user,etime,rss,vsz,nlwp,args", "-p", &pid_text])
+        };
+        bundle.add_text(&format!("{}/process.txt", dir), &redact_secrets(&process_info));
+
+        if cfg!(target_os = "linux") {
+            for file in ["limits", "status"] {
+                match fs::read_to_string(format!("/proc/{}/{}", pid, file)) {
+                    Ok(content) => bundle.add_text(&format!("{}/proc_{}.txt", dir, file), &content),
+                    Err(e) => bundle.failures.push(format!("{} /proc/{}/{}: {}", jar, pid, file, e)),
+                }
+            }
+        }
+
+        if options.threads {
+            match threads::capture_thread_dump(jar, pid) {
+                Ok(dump) => bundle.add_text(&format!("{}/threads.txt", dir), &dump),
+                Err(e) => bundle.failures.push(format!("{} 线程转储: {}", jar, e)),
+            }
+        }
+    }
+
+    // 活动日志的尾部和最近的轮转代
+    let encoding = JarOptions::load(jar).encoding();
+    for log_file in config.get_jar_log_files(jar) {
+        let path = Path::new(&log_file);
+        let file_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        if path.exists() {
+            add_log_tail(bundle, &format!("{}/logs/{}.tail", dir, file_name), path, options.lines, encoding);
+        }
+        let generations = logging::list_log_generations(&log_file);
+        let skip = generations.len().saturating_sub(options.rotated);
+        for generation in &generations[skip..] {
+            let generation_name = generation.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            bundle.add_file(&format!("{}/logs/{}", dir, generation_name), generation);
+        }
+    }
+}
+
+fn add_log_tail(bundle: &mut Bundle, name: &str, path: &Path, lines: usize, encoding: &'static encoding_rs::Encoding) {
+    match logging::read_last_lines(path, lines, encoding, &|_| true) {
+        Ok(tail) => {
+            let mut content = tail.join("\n");
+            content.push('\n');
+            bundle.add_text(name, &content);
+        },
+        Err(e) => bundle.failures.push(format!("{}: {}", path.display(), e)),
+    }
+}
+
+/// 执行命令并返回输出（含 stderr），失败时返回错误说明
+fn capture(program: &str, args: &[&str]) -> String {
+    command_output(Command::new(program).args(args), &format!("{} {}", program, args.join(" ")))
+}
+
+/// 以子进程方式执行本程序的子命令（不带颜色）
+fn capture_self(args: &[&str]) -> String {
+    match std::env::current_exe() {
+        Ok(exe) => command_output(Command::new(exe).args(args).env("NO_COLOR", "1"), &args.join(" ")),
+        Err(e) => format!("无法定位程序路径: {}\n", e),
+    }
+}
+
+fn command_output(command: &mut Command, description: &str) -> String {
+    match command.output() {
+        Ok(output) => {
+            let mut text = String::from_utf8_lossy(&output.stdout).to_string();
+            text.push_str(&String::from_utf8_lossy(&output.stderr));
+            text
+        },
+        Err(e) => format!("执行 {} 失败: {}\n", description.trim(), e),
+    }
+}
+
+/// 脱敏：隐藏密码、令牌等键的值、命令行参数的值和 URL 中的用户信息
+pub fn redact_secrets(text: &str) -> String {
+    static PATTERNS: OnceLock<[Regex; 3]> = OnceLock::new();
+    let [key_value, flag_value, url_userinfo] = PATTERNS.get_or_init(|| [
+        Regex::new(r#"(?i)([\w.\-]*(?:password|passwd|pwd|secret|token|api[_\-]?key|credential|auth_header|webhook)[\w.\-]*)(\s*[=:]\s*)("[^"]*"|'[^']*'|[^\s,;&]+)"#).unwrap(),
+        Regex::new(r"(?i)(--?[\w.\-]*(?:password|passwd|secret|token)[\w.\-]*)(\s+)([^\s\-]\S*)").unwrap(),
+        Regex::new(r"(://)[^/\s:@]+:[^/\s@]+@").unwrap(),
+    ]);
+
+    let masked = |caps: &Captures| {
+        let value = &caps[3];
+        let quote = if value.starts_with('"') { "\"" } else if value.starts_with('\'') { "'" } else { "" };
+        format!("{}{}{}***{}", &caps[1], &caps[2], quote, quote)
+    };
+    let text = key_value.replace_all(text, masked);
+    let text = flag_value.replace_all(&text, masked);
+    url_userinfo.replace_all(&text, "${1}***@").to_string()
+}
@@ -1,12 +1,14 @@
 // core/config.rs - 配置管理模块（整合所有配置功能）
 
 // ============= 基础配置管理 =============
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::PathBuf;
 use colored::Colorize;
 
 // ============= 全局配置管理 =============
 use serde::{Deserialize, Serialize};
+use crate::core::registry::state_key;
 
 pub const GLOBAL_CONFIG_FILE: &str = ".Manager_Jar/configs/global_config.toml";
 
@@ -18,6 +20,30 @@ pub struct GlobalConfig {
     pub process: ProcessConfig,
     /// 系统配置
     pub system: SystemConfig,
+    /// syslog 转发配置
+    #[serde(default)]
+    pub syslog: SyslogConfig,
+    /// HTTP 日志投递配置
+    #[serde(default)]
+    pub shipper: ShipperConfig,
+    /// 堆诊断配置
+    #[serde(default)]
+    pub heap: HeapConfig,
+    /// JFR 录制配置
+    #[serde(default)]
+    pub jfr: JfrConfig,
+    /// 版本部署配置
+    #[serde(default)]
+    pub deploy: DeployConfig,
+    /// JAR发现配置
+    #[serde(default)]
+    pub discovery: DiscoveryConfig,
+    /// 端口分配配置
+    #[serde(default)]
+    pub ports: PortsConfig,
+    /// 日志告警规则
+    #[serde(default)]
+    pub alerts: Vec<AlertRule>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -34,6 +60,51 @@ pub struct LogConfig {
     pub timestamp_format: String,
     /// 是否启用日志压缩 (针对旧日志)
     pub enable_compression: bool,
+    /// 是否由 Manager_Jar 日志管道接管输出（添加时间戳并实时轮转）
+    #[serde(default = "default_true")]
+    pub enable_pipeline: bool,
+    /// 日志目录磁盘配额 (MB)，0 表示不限制
+    #[serde(default)]
+    pub quota_mb: u64,
+    /// 查看日志时的高亮规则，按顺序匹配
+    #[serde(default = "default_highlight_rules")]
+    pub highlight_rules: Vec<HighlightRule>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// 日志高亮规则
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HighlightRule {
+    /// 匹配日志行的正则表达式
+    pub pattern: String,
+    /// 颜色: red、green、yellow、blue、magenta、cyan、white、black、bright_red 等，或 #RRGGBB
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub color: String,
+    /// 样式: bold、italic、underline、dimmed，多个用逗号分隔
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub style: String,
+    /// 只给匹配到的文字着色，否则给整行着色
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub match_only: bool,
+}
+
+impl HighlightRule {
+    fn line(pattern: &str, color: &str) -> Self {
+        Self { pattern: pattern.to_string(), color: color.to_string(), style: String::new(), match_only: false }
+    }
+}
+
+/// 默认高亮规则，与早期版本的固定高亮一致
+fn default_highlight_rules() -> Vec<HighlightRule> {
+    vec![
+        HighlightRule::line("ERROR|Exception|Failed", "red"),
+        HighlightRule::line("WARN", "yellow"),
+        HighlightRule::line("INFO|Started|Success", "green"),
+        HighlightRule::line("DEBUG", "bright_black"),
+    ]
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -46,6 +117,18 @@ pub struct ProcessConfig {
     pub startup_timeout: u32,
     /// 停止超时时间（秒）
     pub shutdown_timeout: u32,
+    /// 默认 Java 安装目录或主版本号 (如 17)，为空时使用 PATH 中的 java
+    #[serde(default)]
+    pub java_home: String,
+    /// 默认 java 可执行文件，优先于 java_home
+    #[serde(default)]
+    pub java_bin: String,
+    /// 启动前预检 (java 版本、Main-Class、端口、日志目录)，有失败项时不启动
+    #[serde(default = "default_true")]
+    pub preflight: bool,
+    /// 从 .Manager_Jar/runtime/ 下按内容哈希命名的只读副本启动，覆盖原JAR不影响运行中的进程
+    #[serde(default)]
+    pub run_copy: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -60,14 +143,493 @@ pub struct SystemConfig {
     pub max_concurrent_operations: u32,
 }
 
+/// 日志告警规则，由系统级守护进程增量检查
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AlertRule {
+    /// 规则名称
+    pub name: String,
+    /// 监控的JAR文件名，"*" 表示所有JAR
+    pub jar: String,
+    /// 匹配日志行的正则表达式
+    pub pattern: String,
+    /// 时间窗口内匹配次数达到此值时触发
+    #[serde(default = "default_alert_threshold")]
+    pub threshold: u32,
+    /// 统计时间窗口（秒）
+    #[serde(default = "default_alert_window")]
+    pub window_secs: u64,
+    /// 触发后的冷却时间（秒），冷却期内不重复通知
+    #[serde(default = "default_alert_cooldown")]
+    pub cooldown_secs: u64,
+    /// 触发时执行的命令
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    /// 触发时 POST JSON 的 Webhook 地址
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook: Option<String>,
+}
+
+fn default_alert_threshold() -> u32 {
+    1
+}
+
+fn default_alert_window() -> u64 {
+    60
+}
+
+fn default_alert_cooldown() -> u64 {
+    300
+}
+
+impl AlertRule {
+    /// 规则是否适用于指定JAR：两边都按应用名比较，注册为应用之前按JAR文件名写的规则仍然有效
+    /// 规则未指定实例时也适用于JAR的所有实例
+    pub fn applies_to(&self, jar: &str) -> bool {
+        if self.jar == "*" {
+            return true;
+        }
+        let target = crate::core::registry::app_name(&self.jar);
+        let (_, instance) = crate::core::instance::split(&target);
+        let jar = crate::core::registry::app_name(jar);
+        if instance.is_some() { jar == target } else { crate::core::instance::base(&jar) == target }
+    }
+}
+
+/// syslog 转发配置，由日志管道将每行输出以 RFC 5424 格式发送到接收端
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct SyslogConfig {
+    /// 是否为所有JAR启用转发（单个JAR可通过 syslog 选项覆盖）
+    pub enabled: bool,
+    /// 接收端地址: udp://主机:端口、tcp://主机:端口 或 unix:///dev/log
+    pub address: String,
+    /// syslog facility: user、daemon、local0 ~ local7 等
+    pub facility: String,
+    /// 接收端不可用时最多缓存的行数，超出后丢弃最旧的
+    pub buffer_lines: usize,
+}
+
+impl Default for SyslogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            address: "udp://127.0.0.1:514".to_string(),
+            facility: "user".to_string(),
+            buffer_lines: 10000,
+        }
+    }
+}
+
+/// HTTP 日志投递配置，由系统级守护进程分批推送各JAR的新增日志
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct ShipperConfig {
+    /// 是否启用
+    pub enabled: bool,
+    /// 推送格式: loki / elasticsearch
+    pub format: String,
+    /// 推送地址 (Loki: .../loki/api/v1/push，Elasticsearch: .../_bulk)
+    pub url: String,
+    /// Elasticsearch 索引名
+    pub index: String,
+    /// Authorization 请求头，为空时不发送
+    pub auth_header: String,
+    /// 每批最多行数
+    pub batch_lines: usize,
+    /// 每批最多字节数
+    pub batch_bytes: usize,
+    /// 守护进程每轮最多发送的批数，其余留到下一轮
+    pub max_batches_per_pass: usize,
+    /// 请求超时（秒）
+    pub timeout_secs: u64,
+}
+
+impl Default for ShipperConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            format: "loki".to_string(),
+            url: "http://127.0.0.1:3100/loki/api/v1/push".to_string(),
+            index: "manager-jar".to_string(),
+            auth_header: String::new(),
+            batch_lines: 1000,
+            batch_bytes: 1024 * 1024,
+            max_batches_per_pass: 10,
+            timeout_secs: 10,
+        }
+    }
+}
+
+/// 堆诊断配置 (heap histo / heap dump)
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct HeapConfig {
+    /// 堆转储 (.hprof) 保存目录
+    pub dump_dir: String,
+    /// 每个JAR保留的堆转储数量，0 表示不限制
+    pub max_dumps: usize,
+    /// 每个JAR保留的类直方图快照数量，0 表示不限制
+    pub max_histograms: usize,
+}
+
+impl Default for HeapConfig {
+    fn default() -> Self {
+        Self {
+            dump_dir: ".Manager_Jar/data/heapdumps".to_string(),
+            max_dumps: 3,
+            max_histograms: 50,
+        }
+    }
+}
+
+/// JFR 录制配置 (jfr start/dump/stop 和持续录制)
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct JfrConfig {
+    /// 每个JAR保留的录制文件数量，0 表示不限制
+    pub max_recordings: usize,
+    /// 持续录制在磁盘上保留的最长时间 (JFR 时长格式，如 6h)
+    pub max_age: String,
+    /// 持续录制在磁盘上保留的最大大小 (如 250m)
+    pub max_size: String,
+}
+
+impl Default for JfrConfig {
+    fn default() -> Self {
+        Self {
+            max_recordings: 10,
+            max_age: "6h".to_string(),
+            max_size: "250m".to_string(),
+        }
+    }
+}
+
+/// 版本部署配置 (deploy / rollback)
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct DeployConfig {
+    /// 每个JAR保留的版本数量，当前版本不会被删除
+    pub keep_releases: usize,
+    /// 未配置就绪检查时，进程持续运行该秒数即视为就绪
+    pub settle_secs: u64,
+}
+
+impl Default for DeployConfig {
+    fn default() -> Self {
+        Self {
+            keep_releases: 5,
+            settle_secs: 10,
+        }
+    }
+}
+
+/// JAR发现配置 (list、batch、bundle --all)
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct DiscoveryConfig {
+    /// 查找JAR的目录，相对路径相对于当前目录
+    pub search_dirs: Vec<String>,
+    /// 查找深度，1 表示只查找目录本身，不进入子目录
+    pub max_depth: usize,
+}
+
+impl Default for DiscoveryConfig {
+    fn default() -> Self {
+        Self {
+            search_dirs: vec![".".to_string()],
+            max_depth: 1,
+        }
+    }
+}
+
+/// 端口分配配置 (JAR 选项 ports 中的 auto 端口)
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct PortsConfig {
+    /// 自动分配端口的范围起点
+    pub range_start: u16,
+    /// 自动分配端口的范围终点 (含)
+    pub range_end: u16,
+}
+
+impl Default for PortsConfig {
+    fn default() -> Self {
+        Self {
+            range_start: 20000,
+            range_end: 29999,
+        }
+    }
+}
+
 // ============= 原有的JAR配置管理功能 =============
 const CONFIG_DIR: &str = ".Manager_Jar/configs";
 
+// ============= 单个JAR的选项 =============
+
+/// 日志输出模式
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogMode {
+    /// stdout 和 stderr 写入同一个 <jar>.log
+    #[default]
+    Combined,
+    /// stdout 写入 <jar>.out.log，stderr 写入 <jar>.err.log
+    Split,
+}
+
+impl LogMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogMode::Combined => "combined",
+            LogMode::Split => "split",
+        }
+    }
+}
+
+/// 单个JAR的选项 (.Manager_Jar/configs/<jar>.options.toml)
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct JarOptions {
+    /// 日志输出模式
+    pub log_mode: LogMode,
+    /// 日志字符集，未设置时为 UTF-8
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_encoding: Option<String>,
+    /// syslog 转发地址，"off" 表示不转发，未设置时使用全局配置
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub syslog: Option<String>,
+    /// 该JAR日志的磁盘配额 (MB)，未设置时只受全局配额限制
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_quota_mb: Option<u64>,
+    /// 随JAR启动的持续 JFR 录制所用的设置 (default、profile 或 .jfc 文件)，未设置时不录制
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jfr: Option<String>,
+    /// 是否把 GC 日志写入日志目录 (<jar>.gc.log)
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub gc_logging: bool,
+    /// Java 安装目录或主版本号，未设置时使用全局配置
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub java_home: Option<String>,
+    /// java 可执行文件，优先于 java_home
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub java_bin: Option<String>,
+    /// 是否从运行副本启动，未设置时使用全局配置
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_copy: Option<bool>,
+    /// 就绪检查地址，返回 2xx 时视为就绪
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ready_url: Option<String>,
+    /// 就绪日志正则，本次会话的日志匹配时视为就绪
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ready_log: Option<String>,
+    /// 命名端口: 名称 -> 固定端口或 auto
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub ports: BTreeMap<String, String>,
+}
+
+/// 可通过 `configs set` 设置的选项及说明
+pub const JAR_OPTION_KEYS: &[(&str, &str)] = &[
+    ("log_mode", "日志输出模式: combined (合并) / split (stdout、stderr 分开)"),
+    ("log_encoding", "日志字符集: utf-8 (默认) / gbk / gb18030 / latin1 等"),
+    ("syslog", "syslog 转发地址: udp://主机:端口 / tcp://主机:端口 / unix:///dev/log / off / default (使用全局配置)"),
+    ("log_quota_mb", "日志磁盘配额 (MB)，超出时由守护进程删除最旧的轮转日志 / off (不限制)"),
+    ("jfr", "随启动的持续 JFR 录制: default / profile / .jfc 文件路径 / off"),
+    ("gc_logging", "GC 日志: on / off，按 Java 版本生成参数，写入 <jar>.gc.log 并由 JVM 轮转"),
+    ("java_home", "Java 安装目录或主版本号 (如 17，从 'jdks' 列出的 JDK 中选择) / off (使用全局配置)"),
+    ("java_bin", "java 可执行文件路径，优先于 java_home / off (使用全局配置)"),
+    ("run_copy", "从 .Manager_Jar/runtime/ 下的不可变副本启动: on / off / default (使用全局配置)"),
+    ("ready_url", "部署后的就绪检查地址，返回 2xx 视为就绪 (如 http://127.0.0.1:8080/actuator/health) / off"),
+    ("ready_log", "部署后的就绪日志正则 (如 'Started .* in') / off"),
+    ("ports", "命名端口: http,management=9090 (未给端口时自动分配)，参数中用 ${PORT_http} 引用 / off"),
+];
+
+impl JarOptions {
+    /// 选项文件路径，同一JAR的各实例共用
+    pub fn file_path(jar: &str) -> PathBuf {
+        PathBuf::from(CONFIG_DIR).join(format!("{}.options.toml", state_key(crate::core::instance::base(jar))))
+    }
+
+    /// 加载JAR选项，文件不存在时使用默认值
+    pub fn load(jar: &str) -> Self {
+        match fs::read_to_string(Self::file_path(jar)) {
+            Ok(content) => toml::from_str(&content).unwrap_or_else(|e| {
+                print_warn!("JAR选项解析失败，使用默认选项: {}", e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// 保存JAR选项
+    pub fn save(&self, jar: &str) -> Result<(), String> {
+        fs::create_dir_all(CONFIG_DIR).map_err(|e| format!("创建配置目录失败: {}", e))?;
+        let content = toml::to_string_pretty(self).map_err(|e| format!("序列化JAR选项失败: {}", e))?;
+        fs::write(Self::file_path(jar), content).map_err(|e| format!("保存JAR选项失败: {}", e))
+    }
+
+    /// 按名称设置单个选项
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "log_mode" => {
+                self.log_mode = match value {
+                    "combined" => LogMode::Combined,
+                    "split" => LogMode::Split,
+                    _ => return Err(format!("无效的日志模式: {} (可选: combined, split)", value)),
+                };
+            },
+            "log_encoding" => {
+                // 日志按字节 '\n' 分行，只支持兼容 ASCII 的字符集
+                let encoding = encoding_rs::Encoding::for_label(value.trim().as_bytes())
+                    .filter(|encoding| encoding.is_ascii_compatible())
+                    .ok_or_else(|| format!("不支持的字符集: {} (如 utf-8, gbk, gb18030, latin1)", value))?;
+                self.log_encoding = if encoding == encoding_rs::UTF_8 { None } else { Some(value.trim().to_lowercase()) };
+            },
+            "syslog" => {
+                self.syslog = match value.trim() {
+                    "default" | "" => None,
+                    "off" => Some("off".to_string()),
+                    address => {
+                        crate::core::syslog::SyslogTarget::parse(address)?;
+                        Some(address.to_string())
+                    },
+                };
+            },
+            "jfr" => {
+                self.jfr = match value.trim() {
+                    "off" | "" => None,
+                    settings => {
+                        // JVM 参数以逗号分隔子选项，设置中不能含逗号
+                        if settings.contains(',') || !(matches!(settings, "default" | "profile") || settings.ends_with(".jfc")) {
+                            return Err(format!("无效的 JFR 设置: {} (可选: default, profile, .jfc 文件路径, off)", settings));
+                        }
+                        Some(settings.to_string())
+                    },
+                };
+            },
+            "gc_logging" => {
+                self.gc_logging = match value.trim() {
+                    "on" | "true" => true,
+                    "off" | "false" => false,
+                    _ => return Err(format!("无效的值: {} (可选: on, off)", value)),
+                };
+            },
+            "java_home" => {
+                self.java_home = match value.trim() {
+                    "off" | "default" | "" => None,
+                    home => {
+                        crate::core::jdk::resolve_home(home)?;
+                        Some(home.to_string())
+                    },
+                };
+            },
+            "java_bin" => {
+                self.java_bin = match value.trim() {
+                    "off" | "default" | "" => None,
+                    bin => {
+                        crate::core::jdk::validate_bin(bin)?;
+                        Some(bin.to_string())
+                    },
+                };
+            },
+            "run_copy" => {
+                self.run_copy = match value.trim() {
+                    "on" | "true" => Some(true),
+                    "off" | "false" => Some(false),
+                    "default" | "" => None,
+                    _ => return Err(format!("无效的值: {} (可选: on, off, default)", value)),
+                };
+            },
+            "ready_url" => {
+                self.ready_url = match value.trim() {
+                    "off" | "" => None,
+                    url if url.starts_with("http://") || url.starts_with("https://") => Some(url.to_string()),
+                    url => return Err(format!("无效的地址: {} (需以 http:// 或 https:// 开头)", url)),
+                };
+            },
+            "ready_log" => {
+                self.ready_log = match value {
+                    "off" | "" => None,
+                    pattern => {
+                        regex::Regex::new(pattern).map_err(|e| format!("无效的正则表达式: {}", e))?;
+                        Some(pattern.to_string())
+                    },
+                };
+            },
+            "ports" => {
+                self.ports = match value.trim() {
+                    "off" | "" => BTreeMap::new(),
+                    spec => crate::core::ports::parse_spec(spec)?,
+                };
+            },
+            "log_quota_mb" => {
+                self.log_quota_mb = match value.trim() {
+                    "off" | "0" | "" => None,
+                    size => Some(size.parse::<u64>().map_err(|_| format!("无效的配额: {} (单位 MB，off 表示不限制)", size))?),
+                };
+            },
+            _ => {
+                let keys: Vec<&str> = JAR_OPTION_KEYS.iter().map(|(k, _)| *k).collect();
+                return Err(format!("未知选项: {} (可用: {})", key, keys.join(", ")));
+            },
+        }
+        Ok(())
+    }
+
+    /// 日志字符集
+    pub fn encoding(&self) -> &'static encoding_rs::Encoding {
+        self.log_encoding.as_deref()
+            .and_then(|label| encoding_rs::Encoding::for_label(label.as_bytes()))
+            .unwrap_or(encoding_rs::UTF_8)
+    }
+
+    /// 显示选项
+    pub fn display(&self) {
+        println!("  日志模式: {}", self.log_mode.as_str().yellow());
+        println!("  日志字符集: {}", self.log_encoding.as_deref().unwrap_or("utf-8").yellow());
+        println!("  syslog 转发: {}", self.syslog.as_deref().unwrap_or("使用全局配置").yellow());
+        match self.log_quota_mb {
+            Some(quota) => println!("  日志配额: {} MB", quota.to_string().yellow()),
+            None => println!("  日志配额: {}", "不限制".yellow()),
+        }
+        println!("  持续 JFR: {}", self.jfr.as_deref().unwrap_or("off").yellow());
+        println!("  GC 日志: {}", if self.gc_logging { "on".green() } else { "off".yellow() });
+        println!("  Java: {}", self.java_bin.as_deref().or(self.java_home.as_deref()).unwrap_or("使用全局配置").yellow());
+        println!("  运行副本: {}", match self.run_copy {
+            Some(true) => "on".green(),
+            Some(false) => "off".yellow(),
+            None => "使用全局配置".yellow(),
+        });
+        if let Some(url) = &self.ready_url {
+            println!("  就绪地址: {}", url.yellow());
+        }
+        if let Some(pattern) = &self.ready_log {
+            println!("  就绪日志: {}", pattern.yellow());
+        }
+        if !self.ports.is_empty() {
+            let ports: Vec<String> = self.ports.iter().map(|(name, port)| format!("{}={}", name, port)).collect();
+            println!("  命名端口: {}", ports.join(", ").yellow());
+        }
+    }
+}
+
+// 设置JAR选项
+pub fn set_jar_option(jar: &str, key: &str, value: &str) -> Result<(), String> {
+    let mut options = JarOptions::load(jar);
+    options.set(key, value)?;
+    options.save(jar)?;
+    print_success!("已设置 {} 的选项: {} = {}", jar.bright_cyan(), key.cyan(), value.yellow());
+    // 字符集只影响查看日志，立即生效
+    if key != "log_encoding" && crate::core::process::is_jar_running(jar) {
+        println!("💡 选项将在下次启动时生效");
+    }
+    Ok(())
+}
+
 // 保存JAR配置参数
 pub fn save_jar_config(jar: &str, args: &[String]) -> Result<(), String> {
     fs::create_dir_all(CONFIG_DIR).map_err(|e| format!("创建配置目录失败: {}", e))?;
     
-    let config_file = PathBuf::from(CONFIG_DIR).join(format!("{}.config", jar.replace(".jar", "")));
+    let config_file = PathBuf::from(CONFIG_DIR).join(format!("{}.config", state_key(jar)));
     let config_args = args.join(" ");
     
     fs::write(&config_file, &config_args).map_err(|e| format!("保存配置失败: {}", e))?;
@@ -85,7 +647,7 @@ pub fn save_jar_config(jar: &str, args: &[String]) -> Result<(), String> {
 
 // 使用已保存配置快速启动JAR
 pub fn quick_start_jar(jar: &str) -> Result<(), String> {
-    let config_file = PathBuf::from(CONFIG_DIR).join(format!("{}.config", jar.replace(".jar", "")));
+    let config_file = PathBuf::from(CONFIG_DIR).join(format!("{}.config", state_key(jar)));
     
     let config_args = if config_file.exists() {
         match fs::read_to_string(&config_file) {
@@ -115,9 +677,17 @@ pub fn quick_start_jar(jar: &str) -> Result<(), String> {
     crate::core::process::start_jar(jar, &args)
 }
 
+// 读取保存的启动参数，未保存时为空
+pub fn saved_jar_args(jar: &str) -> Vec<String> {
+    let config_file = PathBuf::from(CONFIG_DIR).join(format!("{}.config", state_key(jar)));
+    fs::read_to_string(&config_file)
+        .map(|content| content.split_whitespace().map(|s| s.to_string()).collect())
+        .unwrap_or_default()
+}
+
 // 显示JAR配置
 pub fn show_config(jar: &str) -> Result<(), String> {
-    let config_file = PathBuf::from(CONFIG_DIR).join(format!("{}.config", jar.replace(".jar", "")));
+    let config_file = PathBuf::from(CONFIG_DIR).join(format!("{}.config", state_key(jar)));
     
     if config_file.exists() {
         match fs::read_to_string(&config_file) {
@@ -128,6 +698,7 @@ pub fn show_config(jar: &str) -> Result<(), String> {
                 } else {
                     println!("  参数: {}", content.trim().yellow());
                 }
+                JarOptions::load(jar).display();
                 Ok(())
             },
             Err(e) => {
@@ -138,13 +709,16 @@ pub fn show_config(jar: &str) -> Result<(), String> {
         }
     } else {
         print_warn!("配置文件不存在: {}", jar);
+        if JarOptions::file_path(jar).exists() {
+            JarOptions::load(jar).display();
+        }
         Ok(())
     }
 }
 
 // 删除JAR配置
 pub fn delete_config(jar: &str) -> Result<(), String> {
-    let config_file = PathBuf::from(CONFIG_DIR).join(format!("{}.config", jar.replace(".jar", "")));
+    let config_file = PathBuf::from(CONFIG_DIR).join(format!("{}.config", state_key(jar)));
     
     if config_file.exists() {
         fs::remove_file(&config_file).map_err(|e| format!("删除配置失败: {}", e))?;
@@ -165,7 +739,7 @@ pub fn list_configs() {
             let path = entry.path();
             if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("config") {
                 if let Some(name) = path.file_stem().and_then(|n| n.to_str()) {
-                    let jar_name = format!("{}.jar", name);
+                    let jar_name = crate::core::registry::app_for_key(name);
                     if let Ok(content) = fs::read_to_string(&path) {
                         configs.push((jar_name, content));
                     }
@@ -202,12 +776,19 @@ impl Default for GlobalConfig {
                 enable_rotation: true,
                 timestamp_format: "%Y-%m-%d %H:%M:%S".to_string(),
                 enable_compression: false,
+                enable_pipeline: true,
+                quota_mb: 0,
+                highlight_rules: default_highlight_rules(),
             },
             process: ProcessConfig {
                 default_java_args: vec!["-Xmx512m".to_string()],
                 health_check_interval: 30,
                 startup_timeout: 60,
                 shutdown_timeout: 30,
+                java_home: String::new(),
+                java_bin: String::new(),
+                preflight: true,
+                run_copy: false,
             },
             system: SystemConfig {
                 enable_color: true,
@@ -215,10 +796,23 @@ impl Default for GlobalConfig {
                 auto_cleanup_pid: true,
                 max_concurrent_operations: 5,
             },
+            syslog: SyslogConfig::default(),
+            shipper: ShipperConfig::default(),
+            heap: HeapConfig::default(),
+            jfr: JfrConfig::default(),
+            deploy: DeployConfig::default(),
+            discovery: DiscoveryConfig::default(),
+            ports: PortsConfig::default(),
+            alerts: Vec::new(),
         }
     }
 }
 
+/// 配置模板中写在 "..." 里的字符串值，转义反斜杠和引号
+fn toml_escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
 impl GlobalConfig {
     /// 加载配置文件
     pub fn load() -> Self {
@@ -263,6 +857,11 @@ impl GlobalConfig {
             .map(|s| format!("\"{}\"", s))
             .collect::<Vec<_>>()
             .join(", ");
+        let search_dirs_str = self.discovery.search_dirs
+            .iter()
+            .map(|s| format!("\"{}\"", s.replace('\\', "\\\\")))
+            .collect::<Vec<_>>()
+            .join(", ");
 
         format!(r#"# Manager_Jar 全局配置文件
 # 此文件控制 Manager_Jar 的各种行为和设置
@@ -287,9 +886,25 @@ enable_rotation = {}
 # 日志时间戳格式 (strftime格式)
 timestamp_format = "{}"
 
-# 是否启用日志压缩 (实验性功能)
+# 是否压缩轮转后的旧日志 (gzip，由轮转和守护进程在后台完成)
 enable_compression = {}
 
+# 是否由 Manager_Jar 日志管道接管 JAR 输出
+# 启用后每行日志带时间戳和流标记 (out/err)，并在写入时实时按大小轮转
+enable_pipeline = {}
+
+# 日志目录磁盘配额 (MB)，0 表示不限制
+# 超出时守护进程从最旧的轮转日志 (含压缩文件) 开始删除，不删除正在写入的活动日志
+# 单个 JAR 的配额可通过 'configs set <jar> log_quota_mb <MB>' 设置
+quota_mb = {}
+
+# 查看日志时的高亮规则，每条规则一个 [[log.highlight_rules]] 段，按顺序匹配:
+#   pattern     正则表达式
+#   color       颜色: red、green、yellow、blue、magenta、cyan、white、black、bright_red 等，或 #RRGGBB (需加引号)
+#   style       样式: bold、italic、underline、dimmed，多个用逗号分隔
+#   match_only  true 时只给匹配到的文字着色，否则整行着色 (取第一条匹配的整行规则)
+{}
+
 # ========================================
 # 进程配置 - 控制 JAR 进程的启动和管理
 # ========================================
@@ -306,6 +921,20 @@ startup_timeout = {}
 # JAR 停止超时时间 (秒)
 shutdown_timeout = {}
 
+# 默认 Java: 安装目录或主版本号 (如 "17"，从 jdks 命令列出的 JDK 中选择)，为空时使用 PATH 中的 java
+java_home = "{}"
+
+# 默认 java 可执行文件路径，优先于 java_home
+java_bin = "{}"
+
+# 启动前预检: java 版本是否满足 class 文件版本、Main-Class、端口是否空闲、日志目录是否可写
+# 有失败项时不启动
+preflight = {}
+
+# 从 .Manager_Jar/runtime/<sha256>.jar 副本启动，运行中覆盖原JAR不会影响进程，status 会提示需要重启
+# 可在 JAR 选项中单独设置 (configs set <jar> run_copy on/off)
+run_copy = {}
+
 # ========================================
 # 系统配置 - 控制工具本身的行为
 # ========================================
@@ -321,30 +950,241 @@ auto_cleanup_pid = {}
 
 # 最大并发操作数
 max_concurrent_operations = {}
-"#,
+
+# ========================================
+# syslog 转发 - 由日志管道将 JAR 输出以 RFC 5424 格式转发
+# ========================================
+[syslog]
+# 是否为所有 JAR 启用 (单个 JAR 可用 'configs set <jar> syslog <地址|off>' 覆盖)
+enabled = {}
+
+# 接收端地址: udp://主机:端口、tcp://主机:端口 或 unix:///dev/log
+address = "{}"
+
+# syslog facility: kern、user、daemon、local0 ~ local7 等
+facility = "{}"
+
+# 接收端不可用时最多缓存的行数，超出后丢弃最旧的
+buffer_lines = {}
+
+# ========================================
+# 日志投递 - 由系统级守护进程将 JAR 日志分批推送到 HTTP 端点
+# ========================================
+[shipper]
+# 是否启用
+enabled = {}
+
+# 推送格式: loki (Loki push API) 或 elasticsearch (_bulk)
+format = "{}"
+
+# 推送地址，如 http://loki:3100/loki/api/v1/push 或 http://es:9200/_bulk
+url = "{}"
+
+# Elasticsearch 索引名
+index = "{}"
+
+# Authorization 请求头 (如 "Basic xxx"、"Bearer xxx")，为空时不发送
+auth_header = "{}"
+
+# 每批最多行数和字节数
+batch_lines = {}
+batch_bytes = {}
+
+# 守护进程每轮最多发送的批数，超出的日志留到下一轮 (端点变慢时限制压力)
+max_batches_per_pass = {}
+
+# 请求超时时间 (秒)
+timeout_secs = {}
+
+# ========================================
+# 堆诊断 - heap histo / heap dump 的保存和保留数量
+# ========================================
+[heap]
+# 堆转储 (.hprof) 保存目录，文件可能很大，建议放在空间充足的磁盘
+dump_dir = "{}"
+
+# 每个 JAR 保留的堆转储数量，超出时删除最旧的 (0 表示不限制)
+max_dumps = {}
+
+# 每个 JAR 保留的类直方图快照数量 (0 表示不限制)
+max_histograms = {}
+
+# ========================================
+# JFR 录制 - jfr start/dump/stop 和随 JAR 启动的持续录制
+# ========================================
+[jfr]
+# 每个 JAR 保留的录制文件数量，超出时删除最旧的 (0 表示不限制)
+max_recordings = {}
+
+# 持续录制 (configs set <jar> jfr <default|profile>) 在磁盘上保留的最长时间和最大大小
+max_age = "{}"
+max_size = "{}"
+
+# ========================================
+# 版本部署 - deploy / releases / rollback
+# ========================================
+[deploy]
+# 每个 JAR 保留的版本数量，超出时删除最旧的 (当前版本不会被删除)
+keep_releases = {}
+
+# 部署后的就绪检查: 优先使用 JAR 选项 ready_url (HTTP 2xx) 或 ready_log (日志正则)，
+# 都未设置时进程持续运行 settle_secs 秒即视为就绪；等待上限为 startup_timeout
+settle_secs = {}
+
+# ========================================
+# JAR发现 - list、batch 和 bundle --all 查找JAR的位置
+# ========================================
+[discovery]
+# 查找JAR的目录 (相对路径相对于当前目录)；当前目录以外的JAR以完整路径标识
+search_dirs = [{}]
+
+# 查找深度: 1 表示只查找目录本身，2 表示包括一级子目录，依此类推 (跳过隐藏目录)
+max_depth = {}
+
+# ========================================
+# 端口分配 - JAR 选项 ports 声明的命名端口 (configs set <jar> ports http,management=9090)
+# ========================================
+[ports]
+# auto 端口从此范围中选择空闲端口，优先沿用上次启动时的端口
+range_start = {}
+range_end = {}
+
+# ========================================
+# 告警规则 - 由系统级守护进程检查 JAR 日志
+# ========================================
+# 每条规则一个 [[alerts]] 段，字段说明:
+#   name          规则名称
+#   jar           JAR 文件名，"*" 表示所有 JAR
+#   pattern       匹配日志行的正则表达式
+#   threshold     window_secs 秒内匹配次数达到此值时触发 (默认 1)
+#   window_secs   统计时间窗口 (秒，默认 60)
+#   cooldown_secs 触发后的冷却时间 (秒，默认 300)，冷却期内不重复通知
+#   command       触发时执行的命令，可使用环境变量 MANAGER_JAR_ALERT_* 获取告警信息
+#   webhook       触发时 POST JSON 的地址
+{}"#,
             self.log.log_dir,
             self.log.retention_days,
             self.log.max_file_size_mb,
             self.log.enable_rotation,
             self.log.timestamp_format,
             self.log.enable_compression,
+            self.log.enable_pipeline,
+            self.log.quota_mb,
+            self.format_highlight_rules(),
             default_args_str,
             self.process.health_check_interval,
             self.process.startup_timeout,
             self.process.shutdown_timeout,
+            self.process.java_home.replace('\\', "\\\\"),
+            self.process.java_bin.replace('\\', "\\\\"),
+            self.process.preflight,
+            self.process.run_copy,
             self.system.enable_color,
             self.system.verbose,
             self.system.auto_cleanup_pid,
             self.system.max_concurrent_operations,
+            self.syslog.enabled,
+            toml_escape(&self.syslog.address),
+            toml_escape(&self.syslog.facility),
+            self.syslog.buffer_lines,
+            self.shipper.enabled,
+            toml_escape(&self.shipper.format),
+            toml_escape(&self.shipper.url),
+            toml_escape(&self.shipper.index),
+            toml_escape(&self.shipper.auth_header),
+            self.shipper.batch_lines,
+            self.shipper.batch_bytes,
+            self.shipper.max_batches_per_pass,
+            self.shipper.timeout_secs,
+            toml_escape(&self.heap.dump_dir),
+            self.heap.max_dumps,
+            self.heap.max_histograms,
+            self.jfr.max_recordings,
+            self.jfr.max_age,
+            self.jfr.max_size,
+            self.deploy.keep_releases,
+            self.deploy.settle_secs,
+            search_dirs_str,
+            self.discovery.max_depth,
+            self.ports.range_start,
+            self.ports.range_end,
+            self.format_alert_rules(),
         )
     }
 
+    /// 生成高亮规则段
+    fn format_highlight_rules(&self) -> String {
+        if self.log.highlight_rules.is_empty() {
+            return "highlight_rules = []\n".to_string();
+        }
+        self.log.highlight_rules
+            .iter()
+            .map(|rule| format!("[[log.highlight_rules]]\n{}\n", toml::to_string(rule).unwrap_or_default().trim_end()))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// 生成告警规则段，没有规则时输出注释示例
+    fn format_alert_rules(&self) -> String {
+        if self.alerts.is_empty() {
+            return r#"#
+# [[alerts]]
+# name = "oom"
+# jar = "*"
+# pattern = "OutOfMemoryError"
+# command = "echo 内存溢出 >> alert.txt"
+#
+# [[alerts]]
+# name = "db-down"
+# jar = "app.jar"
+# pattern = "Connection refused"
+# threshold = 5
+# window_secs = 60
+# webhook = "https://example.com/hooks/alert"
+"#.to_string();
+        }
+
+        self.alerts
+            .iter()
+            .map(|rule| format!("\n[[alerts]]\n{}", toml::to_string(rule).unwrap_or_default()))
+            .collect()
+    }
+
     /// 获取日志文件路径
     pub fn get_log_file_path(&self, jar_name: &str) -> String {
-        let log_name = format!("{}.log", jar_name.replace(".jar", ""));
+        let log_name = format!("{}.log", state_key(jar_name));
         format!("{}/{}", self.log.log_dir, log_name)
     }
 
+    /// 获取分流模式下指定输出流的日志文件路径 (<jar>.out.log / <jar>.err.log)
+    pub fn get_stream_log_path(&self, jar_name: &str, stream: &str) -> String {
+        let log_name = format!("{}.{}.log", state_key(jar_name), stream);
+        format!("{}/{}", self.log.log_dir, log_name)
+    }
+
+    /// 获取JAR在当前日志模式下的全部活动日志文件
+    pub fn get_jar_log_files(&self, jar_name: &str) -> Vec<String> {
+        match JarOptions::load(jar_name).log_mode {
+            LogMode::Combined => vec![self.get_log_file_path(jar_name)],
+            LogMode::Split => vec![
+                self.get_stream_log_path(jar_name, "out"),
+                self.get_stream_log_path(jar_name, "err"),
+            ],
+        }
+    }
+
+    /// 轮转JAR所有超过大小限制的活动日志，返回轮转的文件数
+    pub fn rotate_jar_logs(&self, jar_name: &str) -> Result<u32, String> {
+        let mut rotated = 0;
+        for log_path in self.get_jar_log_files(jar_name) {
+            if self.should_rotate_log(&log_path) {
+                self.rotate_log(jar_name, &log_path)?;
+                rotated += 1;
+            }
+        }
+        Ok(rotated)
+    }
+
     /// 检查是否需要轮转日志
     pub fn should_rotate_log(&self, log_path: &str) -> bool {
         if !self.log.enable_rotation {
@@ -360,28 +1200,67 @@ max_concurrent_operations = {}
     }
 
     /// 轮转日志文件
-    pub fn rotate_log(&self, jar_name: &str) -> Result<(), String> {
-        let log_path = self.get_log_file_path(jar_name);
-        
-        if !PathBuf::from(&log_path).exists() {
+    pub fn rotate_log(&self, jar_name: &str, log_path: &str) -> Result<(), String> {
+        if !PathBuf::from(log_path).exists() {
             return Ok(());
         }
 
-        // 生成轮转后的文件名
-        let rotated_path = format!("{}.1", log_path);
+        // 生成轮转后的文件名 (带时间戳，不覆盖之前的轮转代)
+        let rotated_path = crate::core::logging::next_generation_path(log_path);
         
         // 移动当前日志文件
-        fs::rename(&log_path, &rotated_path)
+        fs::rename(log_path, &rotated_path)
             .map_err(|e| format!("轮转日志文件失败: {}", e))?;
 
         print_success!("日志文件已轮转: {} -> {}", 
-                      PathBuf::from(&log_path).file_name().unwrap().to_string_lossy(),
-                      PathBuf::from(&rotated_path).file_name().unwrap().to_string_lossy());
+                      PathBuf::from(log_path).file_name().unwrap().to_string_lossy(),
+                      rotated_path.file_name().unwrap().to_string_lossy());
+
+        // 运行中的进程仍在写入被轮转的文件，留给守护进程在其静默后压缩
+        if self.log.enable_compression && !crate::core::process::is_jar_running(jar_name) {
+            crate::core::logging::compress_in_background(rotated_path);
+        }
 
         Ok(())
     }
 
-    /// 清理过期日志文件
+    /// 压缩所有已静默的轮转旧日志，返回压缩的文件数
+    pub fn compress_rotated_logs(&self) -> Result<u32, String> {
+        if !self.log.enable_compression {
+            return Ok(0);
+        }
+
+        let entries = fs::read_dir(&self.log.log_dir).map_err(|e| format!("无法访问日志目录: {}", e))?;
+
+        let mut compressed_count = 0;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !path.is_file() || crate::core::logging::is_active_log_name(&name) || name.ends_with(".gz") {
+                continue;
+            }
+            // 清理中断的压缩留下的临时文件
+            if name.ends_with(".gz.tmp") {
+                if crate::core::logging::is_generation_quiet(&path) {
+                    fs::remove_file(&path).ok();
+                }
+                continue;
+            }
+            if crate::core::logging::is_managed_log_file(&name)
+                && !crate::core::logging::is_gc_log_file(&name)
+                && crate::core::logging::is_generation_quiet(&path)
+            {
+                match crate::core::logging::compress_log_file(&path) {
+                    Ok(_) => compressed_count += 1,
+                    Err(e) => print_warn!("{}", e),
+                }
+            }
+        }
+
+        Ok(compressed_count)
+    }
+
+    /// 清理过期日志文件（含轮转和压缩的旧日志）
     pub fn cleanup_old_logs(&self) -> Result<u32, String> {
         if self.log.retention_days == 0 {
             return Ok(0); // 永不清理
@@ -400,7 +1279,9 @@ max_concurrent_operations = {}
         if let Ok(entries) = fs::read_dir(&log_dir) {
             for entry in entries.flatten() {
                 let path = entry.path();
-                if path.is_file() && path.extension().map_or(false, |ext| ext == "log") {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let is_log = crate::core::logging::is_managed_log_file(&name) && !crate::core::logging::is_gc_log_file(&name);
+                if path.is_file() && is_log {
                     if let Ok(metadata) = entry.metadata() {
                         if let Ok(modified) = metadata.modified() {
                             if modified < cutoff_time {
@@ -422,6 +1303,72 @@ max_concurrent_operations = {}
         Ok(cleaned_count)
     }
 
+    /// 日志目录中受管理的日志文件
+    pub fn log_dir_files(&self) -> Vec<LogFileInfo> {
+        let mut files = Vec::new();
+        if let Ok(entries) = fs::read_dir(&self.log.log_dir) {
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if crate::core::logging::is_managed_log_file(&name) {
+                    files.extend(LogFileInfo::read(entry.path()));
+                }
+            }
+        }
+        files
+    }
+
+    /// JAR的全部日志文件：各日志模式下的活动日志及其轮转代
+    pub fn jar_log_files_all(&self, jar_name: &str) -> Vec<LogFileInfo> {
+        let mut files = Vec::new();
+        let active_logs = [
+            self.get_log_file_path(jar_name),
+            self.get_stream_log_path(jar_name, "out"),
+            self.get_stream_log_path(jar_name, "err"),
+        ];
+        for log_path in active_logs {
+            files.extend(LogFileInfo::read(PathBuf::from(&log_path)));
+            files.extend(crate::core::logging::list_log_generations(&log_path).into_iter().filter_map(LogFileInfo::read));
+        }
+        files
+    }
+
+    /// 设置了日志配额的JAR及其配额 (MB)
+    pub fn jar_log_quotas(&self) -> Vec<(String, u64)> {
+        let mut quotas = Vec::new();
+        if let Ok(entries) = fs::read_dir(CONFIG_DIR) {
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if let Some(stem) = name.strip_suffix(".options.toml") {
+                    let jar = crate::core::registry::app_for_key(stem);
+                    if let Some(quota) = JarOptions::load(&jar).log_quota_mb {
+                        quotas.push((jar, quota));
+                    }
+                }
+            }
+        }
+        quotas.sort();
+        quotas
+    }
+
+    /// 按磁盘配额清理日志：先检查各JAR的配额，再检查全局配额
+    /// 从最旧的轮转代（含压缩文件）开始删除，活动日志和 unshipped 中尚未投递完的轮转代不会被删除，返回删除的文件数
+    pub fn enforce_log_quota(&self, unshipped: &std::collections::HashSet<String>) -> Result<u32, String> {
+        let mut removed = 0;
+        for (jar, quota) in self.jar_log_quotas() {
+            removed += trim_to_quota(self.jar_log_files_all(&jar), quota.saturating_mul(1024 * 1024), &jar, unshipped);
+        }
+        if self.log.quota_mb > 0 {
+            if !PathBuf::from(&self.log.log_dir).exists() {
+                return Ok(removed);
+            }
+            removed += trim_to_quota(self.log_dir_files(), self.log.quota_mb.saturating_mul(1024 * 1024), "日志目录", unshipped);
+        }
+        if removed > 0 {
+            print_success!("按磁盘配额删除了 {} 个旧日志文件", removed);
+        }
+        Ok(removed)
+    }
+
     /// 获取配置文件路径
     pub fn config_file_path() -> String {
         GLOBAL_CONFIG_FILE.to_string()
@@ -438,6 +1385,13 @@ max_concurrent_operations = {}
         println!("  日志轮转: {}", if self.log.enable_rotation { "启用".green() } else { "禁用".red() });
         println!("  时间格式: {}", self.log.timestamp_format.cyan());
         println!("  日志压缩: {}", if self.log.enable_compression { "启用".green() } else { "禁用".red() });
+        println!("  日志管道: {}", if self.log.enable_pipeline { "启用".green() } else { "禁用".red() });
+        if self.log.quota_mb > 0 {
+            println!("  磁盘配额: {} MB", self.log.quota_mb.to_string().yellow());
+        } else {
+            println!("  磁盘配额: {}", "不限制".yellow());
+        }
+        println!("  高亮规则: {} 条", self.log.highlight_rules.len().to_string().yellow());
 
         println!("\n{}:", "进程配置".bright_green());
         if self.process.default_java_args.is_empty() {
@@ -448,12 +1402,69 @@ max_concurrent_operations = {}
         println!("  健康检查: 每 {} 秒", self.process.health_check_interval.to_string().yellow());
         println!("  启动超时: {} 秒", self.process.startup_timeout.to_string().yellow());
         println!("  停止超时: {} 秒", self.process.shutdown_timeout.to_string().yellow());
+        match (self.process.java_bin.as_str(), self.process.java_home.as_str()) {
+            ("", "") => println!("  Java: {}", "PATH 中的 java".bright_black()),
+            ("", home) => println!("  Java: {} (java_home)", home.yellow()),
+            (bin, _) => println!("  Java: {} (java_bin)", bin.yellow()),
+        }
+        println!("  启动预检: {}", if self.process.preflight { "启用".green() } else { "禁用".red() });
+        println!("  运行副本: {}", if self.process.run_copy { "启用".green() } else { "禁用".red() });
 
         println!("\n{}:", "系统配置".bright_green());
         println!("  彩色输出: {}", if self.system.enable_color { "启用".green() } else { "禁用".red() });
         println!("  详细输出: {}", if self.system.verbose { "启用".green() } else { "禁用".red() });
         println!("  自动清理: {}", if self.system.auto_cleanup_pid { "启用".green() } else { "禁用".red() });
         println!("  并发数量: {}", self.system.max_concurrent_operations.to_string().yellow());
+
+        println!("\n{}:", "syslog 转发".bright_green());
+        println!("  全局转发: {}", if self.syslog.enabled { "启用".green() } else { "禁用".red() });
+        println!("  接收地址: {}", self.syslog.address.cyan());
+        println!("  Facility: {}", self.syslog.facility.yellow());
+        println!("  缓存行数: {}", self.syslog.buffer_lines.to_string().yellow());
+
+        println!("\n{}:", "日志投递".bright_green());
+        println!("  投递状态: {}", if self.shipper.enabled { "启用".green() } else { "禁用".red() });
+        println!("  推送格式: {}", self.shipper.format.yellow());
+        println!("  推送地址: {}", self.shipper.url.cyan());
+        println!("  批量大小: {} 行 / {} 字节, 每轮最多 {} 批",
+                 self.shipper.batch_lines, self.shipper.batch_bytes, self.shipper.max_batches_per_pass);
+
+        println!("\n{}:", "堆诊断".bright_green());
+        println!("  转储目录: {}", self.heap.dump_dir.cyan());
+        println!("  保留数量: 堆转储 {} 个, 直方图 {} 个", self.heap.max_dumps.to_string().yellow(), self.heap.max_histograms.to_string().yellow());
+
+        println!("\n{}:", "JFR 录制".bright_green());
+        println!("  保留数量: {} 个", self.jfr.max_recordings.to_string().yellow());
+        println!("  持续录制: 最长 {}, 最大 {}", self.jfr.max_age.yellow(), self.jfr.max_size.yellow());
+
+        println!("\n{}:", "版本部署".bright_green());
+        println!("  保留版本: {} 个", self.deploy.keep_releases.to_string().yellow());
+        println!("  就绪判定: 未配置检查时持续运行 {} 秒", self.deploy.settle_secs.to_string().yellow());
+
+        println!("\n{}:", "JAR发现".bright_green());
+        println!("  查找目录: {}", self.discovery.search_dirs.join(", ").cyan());
+        println!("  查找深度: {}", self.discovery.max_depth.to_string().yellow());
+
+        println!("\n{}:", "端口分配".bright_green());
+        println!("  自动端口范围: {}", format!("{}-{}", self.ports.range_start, self.ports.range_end).yellow());
+
+        println!("\n{}:", "告警规则".bright_green());
+        if self.alerts.is_empty() {
+            println!("  {}", "无".bright_black());
+        }
+        for rule in &self.alerts {
+            let mut actions = Vec::new();
+            if rule.command.is_some() {
+                actions.push("命令");
+            }
+            if rule.webhook.is_some() {
+                actions.push("Webhook");
+            }
+            println!("  {} [{}] /{}/ {} 秒内 ≥{} 次, 冷却 {} 秒, 通知: {}",
+                     rule.name.cyan(), rule.jar, rule.pattern.yellow(),
+                     rule.window_secs, rule.threshold, rule.cooldown_secs,
+                     if actions.is_empty() { "仅记录".to_string() } else { actions.join(" + ") });
+        }
         
         println!("\n配置文件: {}", GLOBAL_CONFIG_FILE.cyan());
     }
@@ -483,6 +1494,61 @@ max_concurrent_operations = {}
     }
 }
 
+/// 日志文件的大小和修改时间，用于配额统计
+pub struct LogFileInfo {
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: std::time::SystemTime,
+    /// 正在写入的活动日志 (*.log) 或由 JVM 轮转的 GC 日志，计入配额但不删除
+    pub active: bool,
+}
+
+impl LogFileInfo {
+    fn read(path: PathBuf) -> Option<Self> {
+        let metadata = fs::metadata(&path).ok().filter(|m| m.is_file())?;
+        let active = path.file_name().map(|name| name.to_string_lossy()).is_some_and(|name| {
+            crate::core::logging::is_active_log_name(&name) || crate::core::logging::is_gc_log_file(&name)
+        });
+        Some(Self {
+            size: metadata.len(),
+            modified: metadata.modified().unwrap_or(std::time::UNIX_EPOCH),
+            active,
+            path,
+        })
+    }
+}
+
+/// 删除最旧的轮转代直到总大小不超过配额，返回删除的文件数；unshipped 中的轮转代尚未投递，不删除
+fn trim_to_quota(files: Vec<LogFileInfo>, quota_bytes: u64, scope: &str, unshipped: &std::collections::HashSet<String>) -> u32 {
+    let mut total: u64 = files.iter().map(|f| f.size).sum();
+    if total <= quota_bytes {
+        return 0;
+    }
+
+    let is_unshipped = |file: &LogFileInfo| file.path.file_name().is_some_and(|name| unshipped.contains(name.to_string_lossy().as_ref()));
+    let kept = files.iter().filter(|f| !f.active && is_unshipped(f)).count();
+    let mut candidates: Vec<LogFileInfo> = files.into_iter().filter(|f| !f.active && !is_unshipped(f)).collect();
+    candidates.sort_by(|a, b| a.modified.cmp(&b.modified).then_with(|| a.path.cmp(&b.path)));
+
+    let mut removed = 0;
+    for file in candidates {
+        if total <= quota_bytes {
+            break;
+        }
+        if fs::remove_file(&file.path).is_ok() {
+            total = total.saturating_sub(file.size);
+            removed += 1;
+            print_info!("超出 {} 的磁盘配额，删除旧日志: {}", scope, file.path.file_name().unwrap_or_default().to_string_lossy());
+        }
+    }
+    if total > quota_bytes && kept > 0 {
+        print_warn!("{} 超出磁盘配额，{} 个尚未投递的轮转日志暂不删除", scope, kept);
+    } else if total > quota_bytes {
+        print_warn!("{} 的活动日志已超出磁盘配额，无更多可删除的轮转日志", scope);
+    }
+    removed
+}
+
 /// 全局配置实例 (线程安全)
 #[allow(dead_code)]
 static GLOBAL_CONFIG: std::sync::OnceLock<std::sync::Arc<std::sync::Mutex<GlobalConfig>>> = std::sync::OnceLock::new();
@@ -494,3 +1560,47 @@ pub fn get_global_config() -> &'static std::sync::Arc<std::sync::Mutex<GlobalCon
         std::sync::Arc::new(std::sync::Mutex::new(GlobalConfig::load()))
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 生成的配置文件重新解析后字符串值不变
+    fn round_trip(config: &GlobalConfig) -> GlobalConfig {
+        toml::from_str(&config.generate_config_with_comments()).unwrap()
+    }
+
+    #[test]
+    fn escapes_string_values_in_template() {
+        let mut config = GlobalConfig::default();
+        config.syslog.address = r#"unix://C:\logs\"syslog".sock"#.to_string();
+        config.shipper.url = r#"http://logs/push?q="a\b""#.to_string();
+        config.shipper.auth_header = r#"Basic "dXNlcg==""#.to_string();
+        config.heap.dump_dir = r"D:\dumps".to_string();
+        let parsed = round_trip(&config);
+        assert_eq!(parsed.syslog.address, config.syslog.address);
+        assert_eq!(parsed.shipper.url, config.shipper.url);
+        assert_eq!(parsed.shipper.auth_header, config.shipper.auth_header);
+        assert_eq!(parsed.heap.dump_dir, r"D:\dumps");
+    }
+
+    #[test]
+    fn alert_rules_match_instances() {
+        let rule = |jar: &str| AlertRule {
+            name: "errors".to_string(),
+            jar: jar.to_string(),
+            pattern: "ERROR".to_string(),
+            threshold: 1,
+            window_secs: 60,
+            cooldown_secs: 300,
+            command: None,
+            webhook: None,
+        };
+        assert!(rule("*").applies_to("api.jar@2"));
+        assert!(rule("api.jar").applies_to("api.jar"));
+        assert!(rule("api.jar").applies_to("api.jar@2"));
+        assert!(rule("api.jar@2").applies_to("api.jar@2"));
+        assert!(!rule("api.jar@2").applies_to("api.jar@3"));
+        assert!(!rule("api.jar").applies_to("other.jar"));
+    }
+}
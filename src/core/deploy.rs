@@ -0,0 +1,358 @@
+// deploy.rs - 版本部署和回滚
+// 每个版本按 <版本号>-<哈希前缀> 保存在 .Manager_Jar/releases/<jar>/，原子替换当前JAR后重启并检查就绪，失败时自动回滚
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+use colored::Colorize;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use crate::core::config::{GlobalConfig, JarOptions};
+use crate::core::runcopy::sha256_file;
+use crate::core::{inspect, instance, logging, preflight, process, registry};
+use crate::utils::files::format_file_size;
+
+const RELEASES_DIR: &str = ".Manager_Jar/releases";
+
+/// 一个已部署的版本
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Release {
+    /// 版本标识: <版本号>-<哈希前缀>
+    pub id: String,
+    /// 清单中的版本号
+    pub version: String,
+    /// JAR内容的 SHA-256
+    pub sha256: String,
+    /// 版本目录中的文件名
+    pub file: String,
+    /// 部署时间
+    pub deployed_at: String,
+    /// 部署来源文件
+    pub source: String,
+    /// 部署后就绪检查失败
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub failed: bool,
+}
+
+/// JAR的版本列表，按部署顺序排列
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct ReleaseIndex {
+    /// 当前版本的标识
+    active: Option<String>,
+    #[serde(default)]
+    releases: Vec<Release>,
+}
+
+fn release_dir(jar: &str) -> PathBuf {
+    PathBuf::from(RELEASES_DIR).join(registry::state_key(jar))
+}
+
+impl ReleaseIndex {
+    fn file_path(jar: &str) -> PathBuf {
+        release_dir(jar).join("releases.toml")
+    }
+
+    fn load(jar: &str) -> Self {
+        fs::read_to_string(Self::file_path(jar)).ok()
+            .and_then(|content| toml::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, jar: &str) -> Result<(), String> {
+        fs::create_dir_all(release_dir(jar)).map_err(|e| format!("创建版本目录失败: {}", e))?;
+        let content = toml::to_string_pretty(self).map_err(|e| format!("序列化版本列表失败: {}", e))?;
+        fs::write(Self::file_path(jar), content).map_err(|e| format!("保存版本列表失败: {}", e))
+    }
+
+    fn active(&self) -> Option<&Release> {
+        let active = self.active.as_deref()?;
+        self.releases.iter().find(|release| release.id == active)
+    }
+
+    /// 按版本标识、版本号或哈希前缀查找，版本号相同时取最近部署的
+    fn find(&self, target: &str) -> Option<&Release> {
+        self.releases.iter().rev().find(|release| release.id == target)
+            .or_else(|| self.releases.iter().rev().find(|release| release.version == target))
+            .or_else(|| self.releases.iter().rev().find(|release| target.len() >= 6 && release.sha256.starts_with(target)))
+    }
+
+    /// 当前版本之前最近一个未失败的版本
+    fn previous(&self) -> Option<&Release> {
+        let active = self.active.as_deref();
+        let position = self.releases.iter().position(|release| Some(release.id.as_str()) == active).unwrap_or(self.releases.len());
+        self.releases[..position].iter().rev().find(|release| !release.failed)
+    }
+}
+
+/// 文件名中只保留字母、数字和 . _ -
+fn sanitize(version: &str) -> String {
+    version.chars().map(|c| if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') { c } else { '_' }).collect()
+}
+
+/// 把JAR保存为一个版本，内容相同的版本只保存一次
+fn store_release(jar: &str, index: &mut ReleaseIndex, source: &Path) -> Result<Release, String> {
+    let info = inspect::read_jar_info(source)?;
+    if info.attribute("Main-Class").is_none() {
+        return Err(format!("{} 的清单中没有 Main-Class，不是可执行JAR", source.display()));
+    }
+    let sha256 = sha256_file(source).map_err(|e| format!("计算 {} 的哈希失败: {}", source.display(), e))?;
+    let version = info.attribute("Implementation-Version")
+        .or(info.attribute("Bundle-Version"))
+        .unwrap_or("unknown")
+        .to_string();
+    let id = format!("{}-{}", sanitize(&version), &sha256[..8]);
+    let file = format!("{}.jar", id);
+
+    let dir = release_dir(jar);
+    fs::create_dir_all(&dir).map_err(|e| format!("创建版本目录失败: {}", e))?;
+    let target = dir.join(&file);
+    if !target.exists() {
+        let temp = dir.join(format!("{}.tmp", file));
+        fs::copy(source, &temp).map_err(|e| format!("保存版本失败: {}", e))?;
+        fs::rename(&temp, &target).map_err(|e| format!("保存版本失败: {}", e))?;
+    }
+
+    // 重新部署已有版本时移到列表末尾
+    index.releases.retain(|release| release.id != id);
+    let release = Release {
+        id,
+        version,
+        sha256,
+        file,
+        deployed_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        source: source.display().to_string(),
+        failed: false,
+    };
+    index.releases.push(release.clone());
+    Ok(release)
+}
+
+/// 原子替换当前JAR：先复制到同目录的临时文件再改名，运行中的进程仍持有旧文件
+fn activate(jar: &str, index: &mut ReleaseIndex, release: &Release) -> Result<(), String> {
+    let target = registry::jar_path(jar);
+    let temp = PathBuf::from(format!("{}.deploying", target.display()));
+    fs::copy(release_dir(jar).join(&release.file), &temp).map_err(|e| {
+        let _ = fs::remove_file(&temp);
+        format!("复制版本 {} 失败: {}", release.id, e)
+    })?;
+    fs::rename(&temp, &target).map_err(|e| {
+        let _ = fs::remove_file(&temp);
+        format!("替换 {} 失败: {}", jar, e)
+    })?;
+    index.active = Some(release.id.clone());
+    index.save(jar)
+}
+
+/// 等待JAR就绪：ready_url 返回 2xx、ready_log 匹配本次会话日志，或都未配置时持续运行 settle_secs 秒
+pub fn wait_ready(jar: &str) -> Result<(), String> {
+    let config = GlobalConfig::load();
+    let options = JarOptions::load(jar);
+    let timeout = Duration::from_secs(config.process.startup_timeout as u64);
+    let settle = Duration::from_secs(config.deploy.settle_secs);
+    let pattern = match &options.ready_log {
+        Some(pattern) => Some(Regex::new(pattern).map_err(|e| format!("无效的 ready_log: {}", e))?),
+        None => None,
+    };
+    let log_files = config.get_jar_log_files(jar);
+    let encoding = options.encoding();
+
+    let check = match (&options.ready_url, &options.ready_log) {
+        (Some(url), _) => format!("等待 {} 返回 2xx", url),
+        (None, Some(pattern)) => format!("等待日志匹配 '{}'", pattern),
+        (None, None) => format!("等待进程持续运行 {} 秒", settle.as_secs()),
+    };
+    print_info!("检查就绪: {} (最长 {} 秒)", check, timeout.as_secs());
+
+    let started = Instant::now();
+    loop {
+        if process::get_pid(jar).is_none() {
+            let reason = preflight::diagnose_exit(jar, &log_files).unwrap_or_else(|| "进程已退出".to_string());
+            return Err(format!("{} 未就绪: {}", jar, reason));
+        }
+        let ready = match (&options.ready_url, &pattern) {
+            (Some(url), _) => ureq::get(url).timeout(Duration::from_secs(2)).call().is_ok(),
+            (None, Some(pattern)) => logging::current_session_lines(&log_files, 500, encoding).iter().any(|line| pattern.is_match(line)),
+            (None, None) => started.elapsed() >= settle,
+        };
+        if ready {
+            print_success!("{} 已就绪 ({:.1} 秒)", jar.bright_cyan(), started.elapsed().as_secs_f64());
+            return Ok(());
+        }
+        if started.elapsed() >= timeout {
+            return Err(format!("{} 在 {} 秒内未就绪 ({})", jar, timeout.as_secs(), check));
+        }
+        thread::sleep(Duration::from_secs(1));
+    }
+}
+
+/// 使用保存的启动参数重启并等待就绪
+/// 旧进程退出后才启动新进程，避免就绪检查连到仍在运行的旧JVM
+fn restart_and_verify(jar: &str) -> Result<(), String> {
+    let args = crate::core::config::saved_jar_args(jar);
+    if let Some(pid) = process::get_pid(jar) {
+        process::stop_jar(jar)?;
+        wait_exit(jar, pid)?;
+    }
+    process::start_jar(jar, &args)?;
+    wait_ready(jar)
+}
+
+/// 等待旧进程退出，最长 shutdown_timeout 秒
+fn wait_exit(jar: &str, pid: u32) -> Result<(), String> {
+    let timeout = Duration::from_secs(GlobalConfig::load().process.shutdown_timeout as u64);
+    let started = Instant::now();
+    while process::is_process_running(pid) {
+        if started.elapsed() >= timeout {
+            return Err(format!("{} 的旧进程 (PID: {}) 在 {} 秒内未退出", jar, pid, timeout.as_secs()));
+        }
+        thread::sleep(Duration::from_millis(200));
+    }
+    Ok(())
+}
+
+/// 部署和回滚只支持固定路径的JAR：通配符应用的JAR名随版本变化，替换最新匹配的文件会改写旧版本文件
+/// 运行中的实例也不支持，重启后各实例的版本会不一致
+fn check_target(jar: &str) -> Result<(), String> {
+    if registry::is_pattern_app(jar) {
+        return Err(format!("应用 {} 以通配符注册，无法确定部署位置；请用 'apps add' 把它注册为固定的JAR路径", jar));
+    }
+    let instances = instance::running_instances(jar);
+    if !instances.is_empty() {
+        let names: Vec<&str> = instances.iter().map(|(id, _)| id.as_str()).collect();
+        return Err(format!("{} 有运行中的实例 ({})，请先用 'stop' 停止后再部署或回滚", jar, names.join(", ")));
+    }
+    Ok(())
+}
+
+/// 首次部署时把现有的JAR登记为一个版本，以便回滚
+fn adopt_current(jar: &str, index: &mut ReleaseIndex) {
+    let path = registry::jar_path(jar);
+    if index.active.is_some() || !path.is_file() {
+        return;
+    }
+    match store_release(jar, index, &path) {
+        Ok(release) => {
+            print_info!("已把现有的 {} 登记为版本 {}", jar, release.id.cyan());
+            index.active = Some(release.id);
+        },
+        Err(e) => print_warn!("无法登记现有的 {}，部署失败时无法回滚: {}", jar, e),
+    }
+}
+
+/// 删除超出保留数量的旧版本，当前版本不删除
+fn prune(jar: &str, index: &mut ReleaseIndex) {
+    let keep = GlobalConfig::load().deploy.keep_releases.max(1);
+    while index.releases.len() > keep {
+        let Some(position) = index.releases.iter().position(|release| Some(&release.id) != index.active.as_ref()) else { break };
+        let release = index.releases.remove(position);
+        if fs::remove_file(release_dir(jar).join(&release.file)).is_ok() {
+            print_info!("删除旧版本: {}", release.id);
+        }
+    }
+}
+
+/// 部署新版本：保存、替换、重启并检查就绪，失败时回滚到之前的版本
+pub fn deploy(jar: &str, artifact: &str) -> Result<(), String> {
+    if !jar.ends_with(".jar") && !registry::is_registered(jar) {
+        return Err(format!("应用名需要是JAR文件名或注册的应用名: {}", jar));
+    }
+    check_target(jar)?;
+    let artifact_path = Path::new(artifact);
+    if !artifact_path.is_file() {
+        return Err(format!("文件不存在: {}", artifact));
+    }
+    let mut index = ReleaseIndex::load(jar);
+    adopt_current(jar, &mut index);
+    let previous = index.active().cloned();
+
+    let release = store_release(jar, &mut index, artifact_path)?;
+    index.save(jar)?;
+    let on_disk = sha256_file(&registry::jar_path(jar)).ok();
+    if previous.as_ref().is_some_and(|p| p.id == release.id) && on_disk.as_deref() == Some(release.sha256.as_str()) && process::is_jar_running(jar) {
+        print_warn!("{} 已是当前版本，未重启", release.id.cyan());
+        return Ok(());
+    }
+
+    print_success!("正在部署 {} 版本 {} (sha256 {})", jar.bright_cyan(), release.version.yellow(), release.sha256[..12].bright_black());
+    activate(jar, &mut index, &release)?;
+    match restart_and_verify(jar) {
+        Ok(()) => {
+            prune(jar, &mut index);
+            index.save(jar)?;
+            print_success!("✅ 部署完成: {} → {}", jar.bright_cyan(), release.id.bright_green());
+            Ok(())
+        },
+        Err(e) => {
+            print_error!("{}", e);
+            if let Some(failed) = index.releases.iter_mut().find(|r| r.id == release.id) {
+                failed.failed = true;
+            }
+            index.save(jar)?;
+            let Some(previous) = previous.filter(|p| p.id != release.id) else {
+                return Err(format!("部署 {} 失败，没有可回滚的版本", release.id));
+            };
+            print_warn!("自动回滚到 {}", previous.id.cyan());
+            activate(jar, &mut index, &previous)?;
+            match restart_and_verify(jar) {
+                Ok(()) => Err(format!("部署 {} 失败，已回滚到 {}", release.id, previous.id)),
+                Err(rollback_error) => Err(format!("部署 {} 失败，回滚到 {} 后仍未就绪: {}", release.id, previous.id, rollback_error)),
+            }
+        },
+    }
+}
+
+/// 回滚到指定版本，未指定时回滚到当前版本之前的版本
+pub fn rollback(jar: &str, target: Option<&str>) -> Result<(), String> {
+    check_target(jar)?;
+    let mut index = ReleaseIndex::load(jar);
+    if index.releases.is_empty() {
+        return Err(format!("{} 没有部署记录", jar));
+    }
+    let release = match target {
+        Some(target) => index.find(target).cloned().ok_or_else(|| format!("未找到版本: {} (见 'releases {}')", target, jar))?,
+        None => index.previous().cloned().ok_or_else(|| format!("{} 没有更早的可用版本", jar))?,
+    };
+    if index.active.as_deref() == Some(release.id.as_str()) && target.is_none() {
+        return Err(format!("{} 已是当前版本", release.id));
+    }
+    print_success!("正在回滚 {} 到 {}", jar.bright_cyan(), release.id.yellow());
+    activate(jar, &mut index, &release)?;
+    restart_and_verify(jar)?;
+    if let Some(restored) = index.releases.iter_mut().find(|r| r.id == release.id) {
+        restored.failed = false;
+    }
+    index.save(jar)?;
+    print_success!("✅ 已回滚: {} → {}", jar.bright_cyan(), release.id.bright_green());
+    Ok(())
+}
+
+/// 显示JAR的版本列表
+pub fn list_releases(jar: &str) {
+    let index = ReleaseIndex::load(jar);
+    println!("\n{}", format!("=== {} 版本 ===", jar).bright_blue().bold());
+    if index.releases.is_empty() {
+        println!("  {}", "无部署记录".bright_black());
+        println!("💡 使用 {} 部署新版本", format!("deploy {} <新JAR>", jar).cyan());
+        return;
+    }
+    let on_disk = sha256_file(&registry::jar_path(jar)).ok();
+    for release in index.releases.iter().rev() {
+        let active = index.active.as_deref() == Some(release.id.as_str());
+        let marker = if active { "*".bright_green().bold() } else { " ".normal() };
+        let state = if release.failed { "失败".red() } else if active { "当前".green() } else { "".normal() };
+        let size = fs::metadata(release_dir(jar).join(&release.file)).map(|m| format_file_size(m.len())).unwrap_or_else(|_| "文件缺失".to_string());
+        println!("{} {} {} {} {} {}",
+            marker,
+            format!("{:<28}", release.id).cyan(),
+            format!("{:<12}", release.sha256[..12].to_string()).bright_black(),
+            release.deployed_at.bright_black(),
+            format!("{:>9}", size).yellow(),
+            state);
+    }
+    if let (Some(active), Some(on_disk)) = (index.active(), on_disk) {
+        if active.sha256 != on_disk {
+            print_warn!("{} 在部署后被直接修改，与当前版本 {} 不一致", jar, active.id);
+        }
+    }
+    println!("💡 {} 回滚到上一个版本", format!("rollback {}", jar).cyan());
+}
@@ -0,0 +1,359 @@
+// exceptions.rs - Java 异常堆栈聚合分析
+// 从日志中解析异常堆栈（含 Caused by 链和 "... N more"），按异常类型和栈顶帧分组统计
+use std::collections::HashMap;
+use colored::Colorize;
+use regex::Regex;
+use crate::core::config::{GlobalConfig, JarOptions};
+use crate::core::logging;
+
+/// 参与分组的栈顶帧数
+const GROUP_FRAMES: usize = 3;
+
+/// 样例堆栈最多显示的行数
+const SAMPLE_LINES: usize = 30;
+
+/// 从日志中解析出的一条异常堆栈
+#[derive(Debug, Clone)]
+pub struct StackTrace {
+    /// 最外层异常类型
+    pub exception: String,
+    /// 最外层异常消息
+    pub message: String,
+    /// 最外层异常的栈帧
+    pub frames: Vec<String>,
+    /// Caused by 链上的异常类型（由外到内）
+    pub causes: Vec<String>,
+    /// 堆栈原始内容
+    pub lines: Vec<String>,
+    /// 出现时间
+    pub timestamp: Option<String>,
+}
+
+impl StackTrace {
+    /// 最内层的根因异常类型
+    pub fn root_cause(&self) -> &str {
+        self.causes.last().unwrap_or(&self.exception)
+    }
+}
+
+/// 按异常类型和栈顶帧聚合的一组异常
+pub struct ExceptionGroup {
+    pub exception: String,
+    pub top_frames: Vec<String>,
+    pub count: usize,
+    pub first_seen: Option<String>,
+    pub last_seen: Option<String>,
+    /// 最近一次出现的堆栈，作为样例
+    pub sample: StackTrace,
+}
+
+/// 逐行解析日志中的异常堆栈
+pub struct StackTraceParser {
+    header: Regex,
+    frame: Regex,
+    more: Regex,
+    nested: Regex,
+    timestamp: Regex,
+    /// 可能是异常头的上一行，遇到栈帧时才确认
+    candidate: Option<(String, String, String)>,
+    current: Option<StackTrace>,
+    /// 当前处于 Caused by / Suppressed 段内，栈帧不再计入最外层异常
+    in_nested: bool,
+    last_timestamp: Option<String>,
+    traces: Vec<StackTrace>,
+}
+
+impl Default for StackTraceParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StackTraceParser {
+    pub fn new() -> Self {
+        const CLASS: &str = r"(?:[A-Za-z_$][\w$]*\.)+[A-Za-z_$][\w$]*";
+        Self {
+            header: Regex::new(&format!(r#"^(?:Exception in thread "[^"]*" )?({})(?::\s?(.*))?$"#, CLASS)).unwrap(),
+            frame: Regex::new(r"^\s+at\s+(\S+?\([^)]*\))").unwrap(),
+            more: Regex::new(r"^\s+\.\.\. \d+ (?:more|common frames omitted)\s*$").unwrap(),
+            nested: Regex::new(&format!(r"^\s*(Caused by|Suppressed): ({})(?::\s?(.*))?$", CLASS)).unwrap(),
+            timestamp: Regex::new(r"^\[?(\d{4}-\d{2}-\d{2}[ T]\d{2}:\d{2}:\d{2})").unwrap(),
+            candidate: None,
+            current: None,
+            in_nested: false,
+            last_timestamp: None,
+            traces: Vec::new(),
+        }
+    }
+
+    /// 输入一行日志（可带日志管道前缀）
+    pub fn feed(&mut self, raw: &str) {
+        let body = logging::strip_line_prefix(raw);
+        let timestamp = logging::line_timestamp(raw)
+            .map(|ts| ts.to_string())
+            .or_else(|| self.timestamp.captures(body).map(|c| c[1].to_string()));
+
+        if let Some(frame) = self.frame.captures(body).map(|c| c[1].to_string()) {
+            self.push_frame(body, frame);
+            return;
+        }
+
+        if self.current.is_some() {
+            if self.more.is_match(body) {
+                self.push_line(body);
+                return;
+            }
+            if let Some(caps) = self.nested.captures(body) {
+                if &caps[1] == "Caused by" {
+                    let cause = caps[2].to_string();
+                    if let Some(trace) = self.current.as_mut() {
+                        trace.causes.push(cause);
+                    }
+                }
+                self.in_nested = true;
+                self.push_line(body);
+                return;
+            }
+            self.finish_current();
+        }
+
+        if timestamp.is_some() {
+            self.last_timestamp = timestamp;
+        }
+        self.candidate = self.header.captures(body).map(|caps| {
+            let message = caps.get(2).map_or("", |m| m.as_str()).to_string();
+            (caps[1].to_string(), message, body.to_string())
+        });
+    }
+
+    /// 结束解析，返回所有堆栈
+    pub fn finish(mut self) -> Vec<StackTrace> {
+        self.finish_current();
+        self.traces
+    }
+
+    fn push_frame(&mut self, body: &str, frame: String) {
+        if self.current.is_none() {
+            // 栈帧前必须是异常头，否则不是完整的堆栈
+            let (exception, message, header) = match self.candidate.take() {
+                Some(candidate) => candidate,
+                None => return,
+            };
+            self.current = Some(StackTrace {
+                exception,
+                message,
+                frames: Vec::new(),
+                causes: Vec::new(),
+                lines: vec![header],
+                timestamp: self.last_timestamp.clone(),
+            });
+            self.in_nested = false;
+        }
+        if !self.in_nested {
+            if let Some(trace) = self.current.as_mut() {
+                trace.frames.push(frame);
+            }
+        }
+        self.push_line(body);
+    }
+
+    fn push_line(&mut self, body: &str) {
+        if let Some(trace) = self.current.as_mut() {
+            trace.lines.push(body.to_string());
+        }
+    }
+
+    fn finish_current(&mut self) {
+        if let Some(trace) = self.current.take() {
+            self.traces.push(trace);
+        }
+        self.candidate = None;
+        self.in_nested = false;
+    }
+}
+
+/// 按异常类型和栈顶帧分组，按出现次数降序排列
+pub fn group_traces(traces: Vec<StackTrace>) -> Vec<ExceptionGroup> {
+    let mut groups: Vec<ExceptionGroup> = Vec::new();
+    let mut index: HashMap<(String, Vec<String>), usize> = HashMap::new();
+
+    for trace in traces {
+        let top_frames: Vec<String> = trace.frames.iter().take(GROUP_FRAMES).cloned().collect();
+        let key = (trace.exception.clone(), top_frames.clone());
+        match index.get(&key) {
+            Some(&i) => {
+                let group = &mut groups[i];
+                group.count += 1;
+                if group.first_seen.is_none() {
+                    group.first_seen = trace.timestamp.clone();
+                }
+                if trace.timestamp.is_some() {
+                    group.last_seen = trace.timestamp.clone();
+                }
+                group.sample = trace;
+            },
+            None => {
+                index.insert(key, groups.len());
+                groups.push(ExceptionGroup {
+                    exception: trace.exception.clone(),
+                    top_frames,
+                    count: 1,
+                    first_seen: trace.timestamp.clone(),
+                    last_seen: trace.timestamp.clone(),
+                    sample: trace,
+                });
+            },
+        }
+    }
+
+    groups.sort_by_key(|group| std::cmp::Reverse(group.count));
+    groups
+}
+
+// 显示JAR日志中的异常聚合报告
+pub fn show_error_report(jar: &str, top: usize) -> Result<(), String> {
+    let config = GlobalConfig::load();
+    let encoding = JarOptions::load(jar).encoding();
+    let mut traces = Vec::new();
+    let mut scanned = 0;
+
+    for log_file in config.get_jar_log_files(jar) {
+        let files = logging::log_files_in_order(&log_file);
+        scanned += files.len();
+        // 堆栈不会跨越不同的输出流，每个日志单独解析
+        let mut parser = StackTraceParser::new();
+        logging::for_each_log_line(&files, encoding, |_, line| parser.feed(line))
+            .map_err(|e| format!("读取日志失败: {}", e))?;
+        traces.extend(parser.finish());
+    }
+
+    if scanned == 0 {
+        print_warn!("{} 没有日志文件", jar);
+        return Ok(());
+    }
+    if traces.is_empty() {
+        print_success!("在 {} 个日志文件中未发现异常堆栈", scanned);
+        return Ok(());
+    }
+
+    let total = traces.len();
+    let groups = group_traces(traces);
+    println!("\n{}", format!("=== {} 异常报告 ===", jar).bright_blue().bold());
+    println!("扫描 {} 个日志文件，发现 {} 个异常堆栈，归为 {} 类\n",
+             scanned, total.to_string().bright_red(), groups.len().to_string().yellow());
+
+    for (i, group) in groups.iter().take(top).enumerate() {
+        println!("{} {} {}",
+                 format!("#{}", i + 1).bright_green().bold(),
+                 group.exception.red().bold(),
+                 format!("×{}", group.count).yellow().bold());
+        let unknown = "未知".to_string();
+        println!("  首次出现: {}", group.first_seen.as_ref().unwrap_or(&unknown).cyan());
+        println!("  最近出现: {}", group.last_seen.as_ref().unwrap_or(&unknown).cyan());
+        if !group.sample.message.is_empty() {
+            println!("  异常消息: {}", group.sample.message);
+        }
+        if !group.sample.causes.is_empty() {
+            println!("  Caused by: {}", group.sample.causes.join(" → ").yellow());
+            println!("  根本原因: {}", group.sample.root_cause().bright_red());
+        }
+        println!("  栈顶帧:");
+        for frame in &group.top_frames {
+            println!("    at {}", frame.bright_black());
+        }
+        println!("  样例堆栈:");
+        for line in group.sample.lines.iter().take(SAMPLE_LINES) {
+            println!("    {}", line.bright_black());
+        }
+        if group.sample.lines.len() > SAMPLE_LINES {
+            println!("    {}", format!("... (共 {} 行)", group.sample.lines.len()).bright_black());
+        }
+        println!();
+    }
+
+    if groups.len() > top {
+        println!("💡 还有 {} 类异常未显示，使用 {} 查看更多", groups.len() - top, "--top N".cyan());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(lines: &[&str]) -> Vec<StackTrace> {
+        let mut parser = StackTraceParser::new();
+        for line in lines {
+            parser.feed(line);
+        }
+        parser.finish()
+    }
+
+    #[test]
+    fn parses_trace_with_cause_chain() {
+        let traces = parse(&[
+            "2024-05-01 10:00:00 ERROR request failed",
+            "java.lang.IllegalStateException: boom",
+            "\tat com.example.Service.run(Service.java:10)",
+            "\tat com.example.Main.main(Main.java:5)",
+            "Caused by: java.io.IOException: disk full",
+            "\tat com.example.Store.write(Store.java:42)",
+            "\t... 2 more",
+            "2024-05-01 10:00:01 INFO next request",
+        ]);
+        assert_eq!(traces.len(), 1);
+        let trace = &traces[0];
+        assert_eq!(trace.exception, "java.lang.IllegalStateException");
+        assert_eq!(trace.message, "boom");
+        assert_eq!(trace.frames, ["com.example.Service.run(Service.java:10)", "com.example.Main.main(Main.java:5)"]);
+        assert_eq!(trace.causes, ["java.io.IOException"]);
+        assert_eq!(trace.root_cause(), "java.io.IOException");
+        assert_eq!(trace.timestamp.as_deref(), Some("2024-05-01 10:00:00"));
+        assert_eq!(trace.lines.len(), 6);
+    }
+
+    #[test]
+    fn parses_uncaught_exception_header() {
+        let traces = parse(&[
+            "Exception in thread \"main\" java.lang.NullPointerException",
+            "\tat com.example.Main.main(Main.java:7)",
+        ]);
+        assert_eq!(traces.len(), 1);
+        assert_eq!(traces[0].exception, "java.lang.NullPointerException");
+        assert_eq!(traces[0].message, "");
+        assert_eq!(traces[0].root_cause(), "java.lang.NullPointerException");
+    }
+
+    #[test]
+    fn ignores_frames_without_header() {
+        let traces = parse(&[
+            "some text",
+            "\tat com.example.Main.main(Main.java:7)",
+            "com.example.NotAnException happened here",
+        ]);
+        assert!(traces.is_empty());
+    }
+
+    #[test]
+    fn groups_by_exception_and_top_frames() {
+        let trace = |timestamp: &str, frame: &str| vec![
+            format!("{} ERROR failed", timestamp),
+            "java.lang.RuntimeException: x".to_string(),
+            format!("\tat {}", frame),
+        ];
+        let mut lines = Vec::new();
+        lines.extend(trace("2024-05-01 10:00:00", "a.B.c(B.java:1)"));
+        lines.extend(trace("2024-05-01 10:00:01", "a.B.d(B.java:2)"));
+        lines.extend(trace("2024-05-01 10:00:02", "a.B.d(B.java:2)"));
+        let traces = parse(&lines.iter().map(String::as_str).collect::<Vec<_>>());
+        assert_eq!(traces.len(), 3);
+
+        let groups = group_traces(traces);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].count, 2);
+        assert_eq!(groups[0].top_frames, ["a.B.d(B.java:2)"]);
+        assert_eq!(groups[0].first_seen.as_deref(), Some("2024-05-01 10:00:01"));
+        assert_eq!(groups[0].last_seen.as_deref(), Some("2024-05-01 10:00:02"));
+        assert_eq!(groups[1].count, 1);
+    }
+}
@@ -0,0 +1,348 @@
+// gc.rs - GC 日志
+// 按 Java 版本生成 GC 日志参数（JDK 9+ 统一日志 -Xlog:gc*，Java 8 的 -XX:+PrintGCDetails），
+// 日志写入受管理的日志目录并由 JVM 自行轮转；`gc <jar>` 解析日志汇总暂停次数、暂停时间分位数和堆占用
+use std::fs;
+use std::io::BufRead;
+use std::path::PathBuf;
+use colored::Colorize;
+use regex::Regex;
+use crate::core::config::{GlobalConfig, JarOptions};
+use crate::core::{logging, process};
+
+/// JVM 轮转 GC 日志时保留的文件数
+const GC_LOG_FILES: u32 = 5;
+
+/// 从 `java -version` 的第一行解析主版本号，1.8 返回 8
+pub fn java_major_version(version_line: &str) -> Option<u32> {
+    let caps = Regex::new(r#"version "(\d+)(?:\.(\d+))?"#).unwrap().captures(version_line)?;
+    let first: u32 = caps[1].parse().ok()?;
+    if first == 1 {
+        caps.get(2)?.as_str().parse().ok()
+    } else {
+        Some(first)
+    }
+}
+
+/// JAR的 GC 日志路径: <日志目录>/<jar>.gc.log
+pub fn gc_log_path(config: &GlobalConfig, jar: &str) -> String {
+    format!("{}/{}.gc.log", config.log.log_dir, crate::core::registry::state_key(jar))
+}
+
+/// 启用了 gc_logging 的JAR的 JVM 参数
+pub fn gc_logging_jvm_args(jar: &str, java: &str) -> Vec<String> {
+    if !JarOptions::load(jar).gc_logging {
+        return Vec::new();
+    }
+    let config = GlobalConfig::load();
+    let path = gc_log_path(&config, jar);
+    let size_mb = config.log.max_file_size_mb.max(1);
+    let major = process::java_version(java).as_deref().and_then(java_major_version);
+
+    match major {
+        Some(version) if version <= 8 => vec![
+            "-XX:+PrintGCDetails".to_string(),
+            "-XX:+PrintGCDateStamps".to_string(),
+            format!("-Xloggc:{}", path),
+            "-XX:+UseGCLogFileRotation".to_string(),
+            format!("-XX:NumberOfGCLogFiles={}", GC_LOG_FILES),
+            format!("-XX:GCLogFileSize={}M", size_mb),
+        ],
+        _ => {
+            // 路径含冒号 (如 Windows 盘符) 时需要加引号，否则会被当作 -Xlog 的分隔符
+            let file = if path.contains(':') { format!("\"{}\"", path) } else { path };
+            vec![format!("-Xlog:gc*:file={}:time,uptime,level,tags:filecount={},filesize={}M", file, GC_LOG_FILES, size_mb)]
+        },
+    }
+}
+
+/// 一次 GC 暂停
+struct Pause {
+    kind: String,
+    millis: f64,
+    /// JVM 启动后的秒数
+    uptime: Option<f64>,
+    timestamp: Option<String>,
+    /// GC 前后的堆占用和堆容量 (KB)
+    heap: Option<(u64, u64, u64)>,
+}
+
+/// GC 日志解析器，同时支持统一日志和 Java 8 格式
+struct GcLogParser {
+    unified: Regex,
+    legacy_heap: Regex,
+    legacy_pause: Regex,
+    decorations: Regex,
+    legacy_stamp: Regex,
+    collector: Option<String>,
+    pauses: Vec<Pause>,
+}
+
+impl GcLogParser {
+    fn new() -> Self {
+        Self {
+            // [..][gc] GC(3) Pause Young (Normal) (G1 Evacuation Pause) 24M->3M(256M) 3.456ms
+            // [..][gc,phases] GC(0) Pause Mark Start 0.012ms (ZGC)
+            unified: Regex::new(r"GC\(\d+\) (Pause .*?)\s*(?:(\d+)([KMG])->(\d+)([KMG])\((\d+)([KMG])\)\s+)?([\d.]+)ms\s*$").unwrap(),
+            // 2026-01-01T10:00:00.000+0800: 1.234: [GC (Allocation Failure) [PSYoungGen: ...] 65536K->10728K(251392K), 0.0123456 secs]
+            legacy_heap: Regex::new(r"\[(Full GC|GC)\s*(\([^)]*\))?.*\s(\d+)K->(\d+)K\((\d+)K\),(?:\s*\[Metaspace: [^\]]*\],)?\s+([\d.]+) secs\]").unwrap(),
+            // Java 8 G1: [GC pause (G1 Evacuation Pause) (young), 0.0123 secs]
+            legacy_pause: Regex::new(r"\[(GC pause|GC remark|GC cleanup|Full GC)([^,\]]*), ([\d.]+) secs\]").unwrap(),
+            decorations: Regex::new(r"^\[([^\]]+)\]\[([\d.]+)s\]").unwrap(),
+            legacy_stamp: Regex::new(r"^(\d{4}-\d\d-\d\dT[\d:.]+[+-]\d{4}: )?([\d.]+): \[").unwrap(),
+            collector: None,
+            pauses: Vec::new(),
+        }
+    }
+
+    fn feed(&mut self, line: &str) {
+        if self.collector.is_none() {
+            self.detect_collector(line);
+        }
+
+        if let Some(caps) = self.unified.captures(line) {
+            let heap = match (caps.get(2), caps.get(4), caps.get(6)) {
+                (Some(before), Some(after), Some(capacity)) => Some((
+                    to_kb(before.as_str(), &caps[3]),
+                    to_kb(after.as_str(), &caps[5]),
+                    to_kb(capacity.as_str(), &caps[7]),
+                )),
+                _ => None,
+            };
+            let decorations = self.decorations.captures(line);
+            self.pauses.push(Pause {
+                kind: caps[1].trim().to_string(),
+                millis: caps[8].parse().unwrap_or(0.0),
+                uptime: decorations.as_ref().and_then(|d| d[2].parse().ok()),
+                timestamp: decorations.map(|d| d[1].to_string()),
+                heap,
+            });
+            return;
+        }
+
+        let stamp = self.legacy_stamp.captures(line);
+        let uptime = stamp.as_ref().and_then(|s| s[2].parse().ok());
+        let timestamp = stamp.as_ref().and_then(|s| s.get(1)).map(|t| t.as_str().trim_end_matches(": ").to_string());
+        if let Some(caps) = self.legacy_heap.captures(line) {
+            let cause = caps.get(2).map_or("", |c| c.as_str());
+            self.pauses.push(Pause {
+                kind: format!("{} {}", &caps[1], cause).trim().to_string(),
+                millis: caps[6].parse::<f64>().unwrap_or(0.0) * 1000.0,
+                uptime,
+                timestamp,
+                heap: Some((caps[3].parse().unwrap_or(0), caps[4].parse().unwrap_or(0), caps[5].parse().unwrap_or(0))),
+            });
+        } else if let Some(caps) = self.legacy_pause.captures(line) {
+            self.pauses.push(Pause {
+                kind: format!("{}{}", &caps[1], &caps[2]).trim().to_string(),
+                millis: caps[3].parse::<f64>().unwrap_or(0.0) * 1000.0,
+                uptime,
+                timestamp,
+                heap: None,
+            });
+        }
+    }
+
+    fn detect_collector(&mut self, line: &str) {
+        if let Some(pos) = line.find("] Using ") {
+            self.collector = Some(line[pos + 8..].trim().to_string());
+            return;
+        }
+        let legacy = [("PSYoungGen", "Parallel"), ("ParNew", "CMS (ParNew)"), ("DefNew", "Serial"), ("G1 ", "G1")];
+        if let Some((_, name)) = legacy.iter().find(|(marker, _)| line.contains(marker)) {
+            self.collector = Some(name.to_string());
+        }
+    }
+}
+
+fn to_kb(value: &str, unit: &str) -> u64 {
+    let value: u64 = value.parse().unwrap_or(0);
+    match unit {
+        "G" => value * 1024 * 1024,
+        "M" => value * 1024,
+        _ => value,
+    }
+}
+
+fn format_kb(kb: u64) -> String {
+    crate::utils::files::format_file_size(kb * 1024)
+}
+
+fn percentile(sorted: &[f64], q: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    sorted[((sorted.len() - 1) as f64 * q).round() as usize]
+}
+
+/// JAR的 GC 日志文件（含 JVM 轮转的旧文件和压缩文件），按修改时间从旧到新
+fn gc_log_files(config: &GlobalConfig, jar: &str) -> Vec<PathBuf> {
+    let prefix = format!("{}.gc.log", crate::core::registry::state_key(jar));
+    let mut files: Vec<(std::time::SystemTime, PathBuf)> = fs::read_dir(&config.log.log_dir)
+        .map(|entries| entries.flatten()
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with(&prefix))
+            .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+            .collect())
+        .unwrap_or_default();
+    files.sort();
+    files.into_iter().map(|(_, path)| path).collect()
+}
+
+/// 解析JAR的 GC 日志并显示汇总
+pub fn show_gc_summary(jar: &str, top: usize) -> Result<(), String> {
+    let config = GlobalConfig::load();
+    let files = gc_log_files(&config, jar);
+    if files.is_empty() {
+        if !JarOptions::load(jar).gc_logging {
+            println!("💡 使用 {} 启用 GC 日志，下次启动时生效", format!("configs set {} gc_logging on", jar).cyan());
+        }
+        return Err(format!("没有找到 {} 的 GC 日志", jar));
+    }
+
+    let mut parser = GcLogParser::new();
+    for path in &files {
+        let reader = logging::open_log_reader(path).map_err(|e| format!("读取 {} 失败: {}", path.display(), e))?;
+        for line in reader.split(b'\n').map_while(Result::ok) {
+            parser.feed(String::from_utf8_lossy(&line).trim_end());
+        }
+    }
+
+    println!("\n{}", format!("=== {} GC 汇总 ===", jar).bright_blue().bold());
+    println!("  日志文件: {} 个 ({})", files.len(), gc_log_path(&config, jar).cyan());
+    println!("  收集器: {}", parser.collector.as_deref().unwrap_or("未知").yellow());
+    let pauses = &parser.pauses;
+    if pauses.is_empty() {
+        print_warn!("日志中没有 GC 暂停记录");
+        return Ok(());
+    }
+
+    let total_ms: f64 = pauses.iter().map(|p| p.millis).sum();
+    let span = match (pauses.first().and_then(|p| p.uptime), pauses.last().and_then(|p| p.uptime)) {
+        (Some(first), Some(last)) if last > first => Some(last - first),
+        _ => None,
+    };
+    match span {
+        Some(span) => println!("  暂停总计: {} 次, {:.1} ms, 占运行时间 {:.2}% (统计 {:.0} 秒)",
+                               pauses.len(), total_ms, total_ms / 10.0 / span, span),
+        None => println!("  暂停总计: {} 次, {:.1} ms", pauses.len(), total_ms),
+    }
+
+    // 按暂停类型统计
+    let mut kinds: Vec<(&str, Vec<f64>)> = Vec::new();
+    for pause in pauses {
+        match kinds.iter_mut().find(|(kind, _)| *kind == pause.kind) {
+            Some((_, times)) => times.push(pause.millis),
+            None => kinds.push((&pause.kind, vec![pause.millis])),
+        }
+    }
+    let mut all: Vec<f64> = pauses.iter().map(|p| p.millis).collect();
+    all.sort_by(f64::total_cmp);
+    kinds.iter_mut().for_each(|(_, times)| times.sort_by(f64::total_cmp));
+    kinds.sort_by_key(|(_, times)| std::cmp::Reverse(times.len()));
+
+    println!("\n  {:>8} {:>10} {:>10} {:>10} {:>10}  类型", "次数", "p50(ms)", "p99(ms)", "max(ms)", "总计(ms)");
+    let row = |kind: String, times: &[f64]| {
+        println!("  {:>8} {:>10.2} {:>10.2} {:>10.2} {:>10.1}  {}",
+                 times.len(), percentile(times, 0.5), percentile(times, 0.99),
+                 times.last().copied().unwrap_or(0.0), times.iter().sum::<f64>(), kind);
+    };
+    for (kind, times) in &kinds {
+        row(kind.cyan().to_string(), times);
+    }
+    row("全部".bold().to_string(), &all);
+
+    // 堆占用
+    let heaps: Vec<(u64, u64, u64)> = pauses.iter().filter_map(|p| p.heap).collect();
+    if let Some(&(_, last_after, capacity)) = heaps.last() {
+        let average = |pick: fn(&(u64, u64, u64)) -> u64| heaps.iter().map(pick).sum::<u64>() / heaps.len() as u64;
+        let max_after = heaps.iter().map(|h| h.1).max().unwrap_or(0);
+        println!("\n  堆占用: GC 前平均 {}, GC 后平均 {}, GC 后最大 {}",
+                 format_kb(average(|h| h.0)).yellow(), format_kb(average(|h| h.1)).yellow(), format_kb(max_after).yellow());
+        println!("  最近一次 GC 后: {} / 容量 {}", format_kb(last_after).bright_green(), format_kb(capacity));
+    }
+
+    // 最长的几次暂停
+    let mut longest: Vec<&Pause> = pauses.iter().collect();
+    longest.sort_by(|a, b| b.millis.total_cmp(&a.millis));
+    println!("\n  最长的 {} 次暂停:", top.min(longest.len()));
+    for pause in longest.iter().take(top) {
+        let when = pause.timestamp.clone()
+            .or_else(|| pause.uptime.map(|u| format!("{:.3}s", u)))
+            .unwrap_or_default();
+        let heap = pause.heap.map(|(before, after, capacity)| format!(" {}->{}({})", format_kb(before), format_kb(after), format_kb(capacity)))
+            .unwrap_or_default();
+        let millis = format!("{:.2}ms", pause.millis);
+        let millis = if pause.millis >= 200.0 { millis.red().bold() } else { millis.yellow() };
+        println!("    {} {} {}{}", when.bright_black(), millis, pause.kind, heap.bright_black());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(lines: &[&str]) -> GcLogParser {
+        let mut parser = GcLogParser::new();
+        for line in lines {
+            parser.feed(line);
+        }
+        parser
+    }
+
+    #[test]
+    fn parses_java_version() {
+        assert_eq!(java_major_version(r#"openjdk version "17.0.15" 2025-04-15"#), Some(17));
+        assert_eq!(java_major_version(r#"java version "1.8.0_402""#), Some(8));
+        assert_eq!(java_major_version(r#"openjdk version "21" 2023-09-19"#), Some(21));
+        assert_eq!(java_major_version("unknown"), None);
+    }
+
+    #[test]
+    fn parses_unified_log() {
+        let parser = parse(&[
+            "[2024-05-01T10:00:00.000+0800][0.012s][info][gc] Using G1",
+            "[2024-05-01T10:00:01.000+0800][1.500s][info][gc] GC(0) Pause Young (Normal) (G1 Evacuation Pause) 24M->3M(256M) 3.456ms",
+            "[2024-05-01T10:00:02.000+0800][2.500s][info][gc,phases] GC(1) Pause Mark Start 0.012ms",
+            "[2024-05-01T10:00:03.000+0800][3.500s][info][gc,heap] GC(1) Eden regions: 12->0(150)",
+        ]);
+        assert_eq!(parser.collector.as_deref(), Some("G1"));
+        assert_eq!(parser.pauses.len(), 2);
+        let young = &parser.pauses[0];
+        assert_eq!(young.kind, "Pause Young (Normal) (G1 Evacuation Pause)");
+        assert_eq!(young.millis, 3.456);
+        assert_eq!(young.uptime, Some(1.5));
+        assert_eq!(young.timestamp.as_deref(), Some("2024-05-01T10:00:01.000+0800"));
+        assert_eq!(young.heap, Some((24 * 1024, 3 * 1024, 256 * 1024)));
+        assert_eq!(parser.pauses[1].kind, "Pause Mark Start");
+        assert_eq!(parser.pauses[1].heap, None);
+    }
+
+    #[test]
+    fn parses_java8_log() {
+        let parser = parse(&[
+            "2024-05-01T10:00:00.000+0800: 1.234: [GC (Allocation Failure) [PSYoungGen: 65536K->10728K(76288K)] 65536K->10736K(251392K), 0.0123456 secs] [Times: user=0.03 sys=0.01, real=0.01 secs]",
+            "2024-05-01T10:00:05.000+0800: 6.000: [Full GC (Ergonomics) [PSYoungGen: 10728K->0K(76288K)] [ParOldGen: 8K->10500K(175104K)] 10736K->10500K(251392K), [Metaspace: 3000K->3000K(1056768K)], 0.0500000 secs]",
+            "7.000: [GC pause (G1 Evacuation Pause) (young), 0.0040000 secs]",
+        ]);
+        assert_eq!(parser.collector.as_deref(), Some("Parallel"));
+        assert_eq!(parser.pauses.len(), 3);
+        let minor = &parser.pauses[0];
+        assert_eq!(minor.kind, "GC (Allocation Failure)");
+        assert!((minor.millis - 12.3456).abs() < 1e-9);
+        assert_eq!(minor.uptime, Some(1.234));
+        assert_eq!(minor.timestamp.as_deref(), Some("2024-05-01T10:00:00.000+0800"));
+        assert_eq!(minor.heap, Some((65536, 10736, 251392)));
+        assert_eq!(parser.pauses[1].kind, "Full GC (Ergonomics)");
+        assert_eq!(parser.pauses[1].heap, Some((10736, 10500, 251392)));
+        assert_eq!(parser.pauses[2].kind, "GC pause (G1 Evacuation Pause) (young)");
+        assert_eq!(parser.pauses[2].timestamp, None);
+    }
+
+    #[test]
+    fn computes_percentiles() {
+        let sorted = [1.0, 2.0, 3.0, 4.0, 100.0];
+        assert_eq!(percentile(&sorted, 0.5), 3.0);
+        assert_eq!(percentile(&sorted, 0.99), 100.0);
+        assert_eq!(percentile(&[], 0.5), 0.0);
+    }
+}
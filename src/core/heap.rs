@@ -0,0 +1,334 @@
+// heap.rs - 堆诊断
+// 通过 jcmd 采集类直方图快照、对比两次快照找出增长最多的类（排查内存泄漏），以及生成堆转储 (.hprof)
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use colored::Colorize;
+use regex::Regex;
+use crate::core::config::GlobalConfig;
+use crate::core::process;
+use crate::utils::files::format_file_size;
+
+const HISTOGRAM_DIR: &str = ".Manager_Jar/data/histograms";
+const STAMP_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+/// 直方图中一个类的实例数和字节数
+#[derive(Clone, Copy, Default)]
+struct ClassUsage {
+    instances: u64,
+    bytes: u64,
+}
+
+/// 解析 GC.class_histogram 输出，按原顺序返回 (类名, 用量)
+/// 行格式: `   1:         12345        1234567  [B (java.base@17.0.2)`，类名后的模块信息会被去掉
+fn parse_histogram(text: &str) -> Vec<(String, ClassUsage)> {
+    let line_re = Regex::new(r"^\s*\d+:\s+(\d+)\s+(\d+)\s+(\S+)").unwrap();
+    text.lines()
+        .filter_map(|line| {
+            let caps = line_re.captures(line)?;
+            Some((caps[3].to_string(), ClassUsage {
+                instances: caps[1].parse().ok()?,
+                bytes: caps[2].parse().ok()?,
+            }))
+        })
+        .collect()
+}
+
+fn jar_dir(base: &str, jar: &str) -> PathBuf {
+    PathBuf::from(base).join(crate::core::registry::state_key(jar))
+}
+
+/// 目录中指定扩展名的文件，按文件名中的时间戳和同一秒内的序号从旧到新排序
+fn list_files(dir: &Path, extension: &str) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| entries.flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == extension))
+            .collect())
+        .unwrap_or_default();
+    files.sort_by_key(|path| {
+        let stem = path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+        let (stamp, seq) = split_stamp(&stem);
+        (stamp.to_string(), seq)
+    });
+    files
+}
+
+/// 拆分文件名中的时间戳和序号: 20240501-120000-2 为 (20240501-120000, 2)，没有序号时为 0
+fn split_stamp(stem: &str) -> (&str, u32) {
+    match stem.rsplit_once('-') {
+        Some((stamp, seq)) if stamp.contains('-') => match seq.parse() {
+            Ok(seq) => (stamp, seq),
+            Err(_) => (stem, 0),
+        },
+        _ => (stem, 0),
+    }
+}
+
+/// 只保留最新的 keep 个文件，0 表示不限制
+fn prune(dir: &Path, extension: &str, keep: usize, kind: &str) {
+    if keep == 0 {
+        return;
+    }
+    let files = list_files(dir, extension);
+    let excess = files.len().saturating_sub(keep);
+    for path in &files[..excess] {
+        if fs::remove_file(path).is_ok() {
+            print_info!("删除旧的{}: {}", kind, path.display());
+        }
+    }
+}
+
+/// 从文件名中的时间戳得到快照时间
+fn snapshot_time(path: &Path) -> Option<chrono::NaiveDateTime> {
+    let stem = path.file_stem()?.to_str()?;
+    chrono::NaiveDateTime::parse_from_str(split_stamp(stem).0, STAMP_FORMAT).ok()
+}
+
+fn new_snapshot_path(dir: &Path, extension: &str) -> PathBuf {
+    let stamp = chrono::Local::now().format(STAMP_FORMAT).to_string();
+    let mut path = dir.join(format!("{}.{}", stamp, extension));
+    let mut seq = 1;
+    while path.exists() {
+        path = dir.join(format!("{}-{}.{}", stamp, seq, extension));
+        seq += 1;
+    }
+    path
+}
+
+/// 采集类直方图快照并显示占用最多的类
+/// all 为 true 时包含不可达对象（不触发 Full GC）
+pub fn capture_histogram(jar: &str, all: bool, top: usize) -> Result<(), String> {
+    let pid = process::get_pid(jar).ok_or_else(|| format!("{} 未在运行", jar))?;
+    let args: &[&str] = if all { &["GC.class_histogram", "-all"] } else { &["GC.class_histogram"] };
+    let output = process::jcmd(jar, pid, args)?;
+    let classes = parse_histogram(&output);
+    if classes.is_empty() {
+        return Err(format!("无法解析类直方图: {}", output.lines().next().unwrap_or("").trim()));
+    }
+
+    let dir = jar_dir(HISTOGRAM_DIR, jar);
+    fs::create_dir_all(&dir).map_err(|e| format!("创建目录失败 {}: {}", dir.display(), e))?;
+    let path = new_snapshot_path(&dir, "histo");
+    fs::write(&path, &output).map_err(|e| format!("保存类直方图失败: {}", e))?;
+    prune(&dir, "histo", GlobalConfig::load().heap.max_histograms, "类直方图");
+
+    let total_instances: u64 = classes.iter().map(|(_, usage)| usage.instances).sum();
+    let total_bytes: u64 = classes.iter().map(|(_, usage)| usage.bytes).sum();
+    print_success!("类直方图已保存: {}", path.display().to_string().cyan());
+    println!("  {} 个类, {} 个实例, {}{}", classes.len(), total_instances, format_file_size(total_bytes).bright_green(),
+             if all { "" } else { " (仅存活对象)" });
+
+    println!("\n{}", format!("=== 占用最多的 {} 个类 ===", top.min(classes.len())).bright_blue().bold());
+    println!("  {:>12} {:>12}  类名", "实例数", "大小");
+    for (class, usage) in classes.iter().take(top) {
+        println!("  {:>12} {:>12}  {}", usage.instances, format_file_size(usage.bytes), class.cyan());
+    }
+    println!("\n💡 再次采集后可用 {} 对比增长", format!("heap diff {}", jar).cyan());
+    Ok(())
+}
+
+/// 按编号、latest/previous、时间戳、文件名或路径查找快照
+fn resolve_snapshot(jar: &str, spec: &str) -> Result<PathBuf, String> {
+    let snapshots = list_files(&jar_dir(HISTOGRAM_DIR, jar), "histo");
+    let by_index = |index: usize| snapshots.get(index).cloned();
+    let found = match spec {
+        "latest" | "last" => snapshots.len().checked_sub(1).and_then(by_index),
+        "previous" | "prev" => snapshots.len().checked_sub(2).and_then(by_index),
+        _ => match spec.parse::<usize>() {
+            Ok(n) if n >= 1 && n <= snapshots.len() => by_index(n - 1),
+            _ if Path::new(spec).is_file() => Some(PathBuf::from(spec)),
+            _ => snapshots.iter().find(|path| {
+                path.file_name().and_then(|n| n.to_str()).is_some_and(|name| name == spec || name.starts_with(spec))
+            }).cloned(),
+        },
+    };
+    found.ok_or_else(|| format!("找不到类直方图快照: {} (共 {} 个，见 'heap list {}')", spec, snapshots.len(), jar))
+}
+
+/// 对比两次类直方图快照，显示实例数和字节数增长最多的类
+pub fn diff_histograms(jar: &str, from: &str, to: &str, top: usize) -> Result<(), String> {
+    let from_path = resolve_snapshot(jar, from)?;
+    let to_path = resolve_snapshot(jar, to)?;
+    let read = |path: &Path| -> Result<HashMap<String, ClassUsage>, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("读取快照失败 {}: {}", path.display(), e))?;
+        Ok(parse_histogram(&text).into_iter().collect())
+    };
+    let before = read(&from_path)?;
+    let after = read(&to_path)?;
+    let changes = histogram_changes(&before, &after);
+
+    let total = |map: &HashMap<String, ClassUsage>| map.values().map(|u| u.bytes).sum::<u64>() as i64;
+    println!("\n{}", format!("=== {} 类直方图对比 ===", jar).bright_blue().bold());
+    println!("  从: {}", from_path.display().to_string().cyan());
+    println!("  到: {}", to_path.display().to_string().cyan());
+    if let (Some(start), Some(end)) = (snapshot_time(&from_path), snapshot_time(&to_path)) {
+        let seconds = (end - start).num_seconds();
+        if seconds.abs() < 120 {
+            println!("  间隔: {} 秒", seconds);
+        } else {
+            println!("  间隔: {:.1} 分钟", seconds as f64 / 60.0);
+        }
+    }
+    println!("  堆总量: {} → {} ({})",
+             format_file_size(total(&before) as u64), format_file_size(total(&after) as u64),
+             signed_size(total(&after) - total(&before)));
+
+    let grown: Vec<_> = changes.iter().filter(|(_, instances, bytes, _)| *bytes > 0 || *instances > 0).collect();
+    if grown.is_empty() {
+        print_success!("没有类的实例数或大小增长");
+        return Ok(());
+    }
+    println!("\n{}", format!("=== 增长最多的 {} 个类 (共 {} 个类增长) ===", top.min(grown.len()), grown.len()).bright_blue().bold());
+    println!("  {:>12} {:>12} {:>12} {:>12}  类名", "实例增长", "大小增长", "当前实例", "当前大小");
+    for (class, instances, bytes, current) in grown.iter().take(top) {
+        let new_class = if before.contains_key(*class) { String::new() } else { format!(" {}", "[新增]".yellow()) };
+        println!("  {} {} {:>12} {:>12}  {}{}",
+                 format!("{:>+12}", instances).red(), format!("{:>12}", signed_size(*bytes)).red(),
+                 current.instances, format_file_size(current.bytes), class.cyan(), new_class);
+    }
+    Ok(())
+}
+
+/// 每个类的 (类名, 实例数变化, 字节数变化, 当前用量)，按字节数增长从多到少排序；消失的类当前用量为 0
+fn histogram_changes<'a>(before: &'a HashMap<String, ClassUsage>, after: &'a HashMap<String, ClassUsage>) -> Vec<(&'a str, i64, i64, ClassUsage)> {
+    let mut changes: Vec<(&str, i64, i64, ClassUsage)> = after.iter()
+        .map(|(class, usage)| {
+            let old = before.get(class).copied().unwrap_or_default();
+            (class.as_str(), usage.instances as i64 - old.instances as i64, usage.bytes as i64 - old.bytes as i64, *usage)
+        })
+        .chain(before.iter().filter(|(class, _)| !after.contains_key(*class)).map(|(class, usage)| {
+            (class.as_str(), -(usage.instances as i64), -(usage.bytes as i64), ClassUsage::default())
+        }))
+        .collect();
+    changes.sort_by(|a, b| b.2.cmp(&a.2).then(b.1.cmp(&a.1)).then(a.0.cmp(b.0)));
+    changes
+}
+
+fn signed_size(bytes: i64) -> String {
+    let sign = if bytes < 0 { "-" } else { "+" };
+    format!("{}{}", sign, format_file_size(bytes.unsigned_abs()))
+}
+
+/// 生成堆转储 (.hprof) 并按配置保留最近的几个
+/// all 为 true 时包含不可达对象（不触发 Full GC）
+pub fn dump_heap(jar: &str, all: bool) -> Result<(), String> {
+    let pid = process::get_pid(jar).ok_or_else(|| format!("{} 未在运行", jar))?;
+    let config = GlobalConfig::load();
+    let dir = jar_dir(&config.heap.dump_dir, jar);
+    fs::create_dir_all(&dir).map_err(|e| format!("创建目录失败 {}: {}", dir.display(), e))?;
+    // jcmd 在目标进程中解析路径，必须使用绝对路径
+    let dir = fs::canonicalize(&dir).map_err(|e| format!("无法解析目录 {}: {}", dir.display(), e))?;
+    let path = new_snapshot_path(&dir, "hprof");
+    let path_text = path.to_string_lossy().to_string();
+
+    print_info!("正在生成堆转储 (进程 {})，期间应用会暂停...", pid);
+    let mut args = vec!["GC.heap_dump"];
+    if all {
+        args.push("-all");
+    }
+    args.push(&path_text);
+    let output = process::jcmd(jar, pid, &args)?;
+
+    let size = fs::metadata(&path).map(|m| m.len())
+        .map_err(|_| format!("堆转储未生成: {}", output.lines().last().unwrap_or("").trim()))?;
+    print_success!("堆转储已保存: {} ({})", path_text.cyan(), format_file_size(size).bright_green());
+    prune(&dir, "hprof", config.heap.max_dumps, "堆转储");
+    println!("💡 可用 Eclipse MAT、VisualVM 等工具分析");
+    Ok(())
+}
+
+/// 列出JAR的类直方图快照和堆转储
+pub fn list_snapshots(jar: &str) {
+    let config = GlobalConfig::load();
+    let histograms = list_files(&jar_dir(HISTOGRAM_DIR, jar), "histo");
+    let dumps = list_files(&jar_dir(&config.heap.dump_dir, jar), "hprof");
+
+    println!("\n{}", format!("=== {} 类直方图快照 ===", jar).bright_blue().bold());
+    if histograms.is_empty() {
+        println!("  {}", "无".bright_black());
+    }
+    for (i, path) in histograms.iter().enumerate() {
+        let total: u64 = fs::read_to_string(path)
+            .map(|text| parse_histogram(&text).iter().map(|(_, usage)| usage.bytes).sum())
+            .unwrap_or(0);
+        println!("  {:>3}. {}  堆 {}", i + 1, path.file_name().unwrap_or_default().to_string_lossy().cyan(), format_file_size(total).bright_green());
+    }
+
+    println!("\n{}", format!("=== {} 堆转储 ===", jar).bright_blue().bold());
+    if dumps.is_empty() {
+        println!("  {}", "无".bright_black());
+    }
+    for path in &dumps {
+        let size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        println!("  {} ({})", path.display().to_string().cyan(), format_file_size(size).bright_green());
+    }
+    if histograms.len() >= 2 {
+        println!("\n💡 对比快照: {} (默认对比最近两次)", format!("heap diff {} [快照A] [快照B]", jar).cyan());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BEFORE: &str = "12345:
+ num     #instances         #bytes  class name (module)
+-------------------------------------------------------
+   1:         52014        6470624  [B (java.base@17.0.9)
+   2:         49626        1191024  java.lang.String (java.base@17.0.9)
+   3:         11360        1001544  java.lang.Class (java.base@17.0.9)
+   4:          5243         559920  [Ljava.lang.Object; (java.base@17.0.9)
+   5:           300           9600  com.example.OldTask
+Total        118543        9232712
+";
+
+    // JDK 8 jmap -histo 的格式，没有模块信息
+    const AFTER: &str = " num     #instances         #bytes  class name
+----------------------------------------------
+   1:         60014        8470624  [B
+   2:         49626        1191024  java.lang.String
+   3:         11000         990000  java.lang.Class
+   4:          5243         559920  [Ljava.lang.Object;
+   5:          1024          16384  com.example.Cache$Entry
+Total        126907       11227952
+";
+
+    #[test]
+    fn parses_class_histogram() {
+        let classes = parse_histogram(BEFORE);
+        assert_eq!(classes.len(), 5);
+        assert_eq!(classes[0].0, "[B");
+        assert_eq!((classes[0].1.instances, classes[0].1.bytes), (52014, 6470624));
+        assert_eq!(classes[3].0, "[Ljava.lang.Object;");
+        assert_eq!(parse_histogram(AFTER)[4].0, "com.example.Cache$Entry");
+    }
+
+    #[test]
+    fn diffs_histograms_by_growth() {
+        let before: HashMap<String, ClassUsage> = parse_histogram(BEFORE).into_iter().collect();
+        let after: HashMap<String, ClassUsage> = parse_histogram(AFTER).into_iter().collect();
+        let changes = histogram_changes(&before, &after);
+        let summary: Vec<(&str, i64, i64)> = changes.iter().map(|(class, instances, bytes, _)| (*class, *instances, *bytes)).collect();
+        assert_eq!(summary[0], ("[B", 8000, 2000000));
+        assert_eq!(summary[1], ("com.example.Cache$Entry", 1024, 16384));
+        assert_eq!(summary.last(), Some(&("java.lang.Class", -360, -11544)));
+        let removed = changes.iter().find(|(class, ..)| *class == "com.example.OldTask").unwrap();
+        assert_eq!((removed.1, removed.2, removed.3.bytes), (-300, -9600, 0));
+    }
+
+    #[test]
+    fn orders_snapshots_taken_in_the_same_second() {
+        let dir = std::env::temp_dir().join(format!("manager_jar_heap_test_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for name in ["20240501-120000-1.histo", "20240501-120000-10.histo", "20240501-120000.histo", "20240501-115959.histo", "20240501-120000-2.histo"] {
+            fs::write(dir.join(name), "").unwrap();
+        }
+        let names: Vec<String> = list_files(&dir, "histo").iter().map(|path| path.file_name().unwrap().to_string_lossy().to_string()).collect();
+        assert_eq!(names, ["20240501-115959.histo", "20240501-120000.histo", "20240501-120000-1.histo", "20240501-120000-2.histo", "20240501-120000-10.histo"]);
+        assert_eq!(snapshot_time(&dir.join("20240501-120000-2.histo")), snapshot_time(&dir.join("20240501-120000.histo")));
+        assert!(snapshot_time(&dir.join("20240501-120000.histo")).is_some());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,143 @@
+// highlight.rs - 日志高亮
+// 按全局配置中的 highlight_rules 给日志行着色：整行规则取第一条匹配的，只着色匹配文字的规则可叠加
+use std::sync::OnceLock;
+use colored::{Color, ColoredString, Colorize};
+use regex::Regex;
+use crate::core::config::{GlobalConfig, HighlightRule};
+
+/// 颜色和样式
+#[derive(Default)]
+struct Style {
+    color: Option<Color>,
+    bold: bool,
+    italic: bool,
+    underline: bool,
+    dimmed: bool,
+}
+
+impl Style {
+    fn apply(&self, text: &str) -> String {
+        let mut styled: ColoredString = text.normal();
+        if let Some(color) = self.color {
+            styled = styled.color(color);
+        }
+        if self.bold {
+            styled = styled.bold();
+        }
+        if self.italic {
+            styled = styled.italic();
+        }
+        if self.underline {
+            styled = styled.underline();
+        }
+        if self.dimmed {
+            styled = styled.dimmed();
+        }
+        styled.to_string()
+    }
+}
+
+/// 编译后的高亮规则
+pub struct Highlighter {
+    line_rules: Vec<(Regex, Style)>,
+    match_rules: Vec<(Regex, Style)>,
+}
+
+static HIGHLIGHTER: OnceLock<Highlighter> = OnceLock::new();
+
+impl Highlighter {
+    /// 编译规则，无效的规则给出警告后跳过
+    pub fn new(rules: &[HighlightRule]) -> Self {
+        let mut highlighter = Self { line_rules: Vec::new(), match_rules: Vec::new() };
+        for rule in rules {
+            let regex = match Regex::new(&rule.pattern) {
+                Ok(regex) => regex,
+                Err(e) => {
+                    print_warn!("忽略无效的高亮规则 {}: {}", rule.pattern, e);
+                    continue;
+                }
+            };
+            let style = match parse_style(rule) {
+                Ok(style) => style,
+                Err(e) => {
+                    print_warn!("忽略无效的高亮规则 {}: {}", rule.pattern, e);
+                    continue;
+                }
+            };
+            if rule.match_only {
+                highlighter.match_rules.push((regex, style));
+            } else {
+                highlighter.line_rules.push((regex, style));
+            }
+        }
+        highlighter
+    }
+
+    /// 按规则给一行日志着色
+    pub fn paint(&self, line: &str) -> String {
+        let line_style = self.line_rules.iter().find(|(regex, _)| regex.is_match(line)).map(|(_, style)| style);
+
+        // 收集只着色匹配文字的区间，重叠时先定义的规则优先
+        let mut spans: Vec<(usize, usize, &Style)> = Vec::new();
+        for (regex, style) in &self.match_rules {
+            for m in regex.find_iter(line).filter(|m| !m.is_empty()) {
+                if !spans.iter().any(|(start, end, _)| m.start() < *end && *start < m.end()) {
+                    spans.push((m.start(), m.end(), style));
+                }
+            }
+        }
+        if spans.is_empty() {
+            return line_style.map_or_else(|| line.to_string(), |style| style.apply(line));
+        }
+        spans.sort_by_key(|(start, _, _)| *start);
+
+        let plain = |text: &str| line_style.map_or_else(|| text.to_string(), |style| style.apply(text));
+        let mut painted = String::new();
+        let mut position = 0;
+        for (start, end, style) in spans {
+            if start > position {
+                painted.push_str(&plain(&line[position..start]));
+            }
+            painted.push_str(&style.apply(&line[start..end]));
+            position = end;
+        }
+        if position < line.len() {
+            painted.push_str(&plain(&line[position..]));
+        }
+        painted
+    }
+}
+
+/// 使用全局配置中的规则给日志行着色
+pub fn paint(line: &str) -> String {
+    HIGHLIGHTER.get_or_init(|| Highlighter::new(&GlobalConfig::load().log.highlight_rules)).paint(line)
+}
+
+fn parse_style(rule: &HighlightRule) -> Result<Style, String> {
+    let mut style = Style::default();
+    if !rule.color.is_empty() {
+        style.color = Some(parse_color(&rule.color)?);
+    }
+    for name in rule.style.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        match name.to_lowercase().as_str() {
+            "bold" => style.bold = true,
+            "italic" => style.italic = true,
+            "underline" => style.underline = true,
+            "dimmed" | "dim" => style.dimmed = true,
+            _ => return Err(format!("未知样式: {}", name)),
+        }
+    }
+    Ok(style)
+}
+
+/// 解析颜色名（bright_red 与 "bright red" 等价）或 #RRGGBB
+fn parse_color(name: &str) -> Result<Color, String> {
+    if let Some(hex) = name.strip_prefix('#') {
+        let channel = |i: usize| hex.get(i..i + 2).and_then(|c| u8::from_str_radix(c, 16).ok());
+        return match (hex.len(), channel(0), channel(2), channel(4)) {
+            (6, Some(r), Some(g), Some(b)) => Ok(Color::TrueColor { r, g, b }),
+            _ => Err(format!("无效的颜色: {}", name)),
+        };
+    }
+    name.replace('_', " ").parse::<Color>().map_err(|_| format!("未知颜色: {}", name))
+}
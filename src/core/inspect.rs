@@ -0,0 +1,350 @@
+// inspect.rs - JAR 内容检查
+// 以 zip 方式读取JAR：MANIFEST.MF、Spring Boot 布局、BOOT-INF/lib 依赖，以及 class 文件版本所要求的最低 Java 版本
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+use colored::Colorize;
+use regex::Regex;
+use zip::{CompressionMethod, ZipArchive};
+use crate::utils::files::format_file_size;
+
+/// JAR中的一个依赖包
+#[derive(Debug, Clone)]
+pub struct Dependency {
+    /// 文件名
+    pub file: String,
+    /// 从文件名解析的名称
+    pub name: String,
+    /// 从文件名解析的版本，无法解析时为空
+    pub version: String,
+    /// 依赖中 class 文件的最高版本 (class 文件主版本号)
+    pub class_version: Option<u16>,
+}
+
+/// JAR检查结果
+#[derive(Debug, Default)]
+pub struct JarInfo {
+    /// MANIFEST.MF 的全部属性，保持原有顺序
+    pub manifest: Vec<(String, String)>,
+    /// 是否为 Spring Boot 可执行JAR/WAR
+    pub spring_boot: bool,
+    /// 内嵌依赖 (BOOT-INF/lib、WEB-INF/lib)
+    pub dependencies: Vec<Dependency>,
+    /// 应用自身 class 文件的数量
+    pub class_count: usize,
+    /// 应用自身 class 文件的最高版本
+    pub class_version: Option<u16>,
+}
+
+impl JarInfo {
+    /// 读取清单属性
+    pub fn attribute(&self, key: &str) -> Option<&str> {
+        self.manifest.iter().find(|(k, _)| k.eq_ignore_ascii_case(key)).map(|(_, v)| v.as_str())
+    }
+
+    /// 运行JAR所需的最低 Java 主版本号 (应用和依赖中最高的 class 文件版本)
+    pub fn required_java(&self) -> Option<u32> {
+        self.dependencies.iter().filter_map(|dep| dep.class_version)
+            .chain(self.class_version)
+            .max()
+            .map(java_for_class_version)
+    }
+}
+
+/// class 文件主版本号对应的 Java 版本: 52 -> 8，61 -> 17
+pub fn java_for_class_version(major: u16) -> u32 {
+    (major as u32).saturating_sub(44)
+}
+
+/// 解析 MANIFEST.MF：每行 "名称: 值"，以空格开头的行是上一行的续行
+pub fn parse_manifest(text: &str) -> Vec<(String, String)> {
+    let mut attributes: Vec<(String, String)> = Vec::new();
+    for line in text.lines() {
+        if let Some(continuation) = line.strip_prefix(' ') {
+            if let Some((_, value)) = attributes.last_mut() {
+                value.push_str(continuation);
+            }
+        } else if let Some((key, value)) = line.split_once(':') {
+            attributes.push((key.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    attributes
+}
+
+/// 从依赖文件名解析名称和版本: spring-core-6.1.2.jar -> (spring-core, 6.1.2)
+fn split_dependency_name(file: &str) -> (String, String) {
+    let stem = file.trim_end_matches(".jar");
+    match Regex::new(r"^(.+?)-(\d[\w.\-]*)$").unwrap().captures(stem) {
+        Some(caps) => (caps[1].to_string(), caps[2].to_string()),
+        None => (stem.to_string(), String::new()),
+    }
+}
+
+/// class 文件头: 0xCAFEBABE 加次版本号、主版本号
+fn class_version(header: &[u8]) -> Option<u16> {
+    if header.len() < 8 || header[..4] != [0xCA, 0xFE, 0xBA, 0xBE] {
+        return None;
+    }
+    Some(u16::from_be_bytes([header[6], header[7]]))
+}
+
+/// 读取条目开头的 8 个字节
+fn read_header(entry: &mut impl Read) -> Vec<u8> {
+    let mut header = Vec::with_capacity(8);
+    let _ = entry.take(8).read_to_end(&mut header);
+    header
+}
+
+/// 扫描归档中的 class 文件，返回 (数量, 最高版本)
+/// Multi-Release JAR 中 META-INF/versions/ 下的类只在更高版本的 Java 上使用，module-info.class 是模块描述，都不计入
+fn scan_classes<R: Read + Seek>(archive: &mut ZipArchive<R>, prefix: &str) -> (usize, Option<u16>) {
+    let mut count = 0;
+    let mut highest = None;
+    for index in 0..archive.len() {
+        let Ok(mut entry) = archive.by_index(index) else { continue };
+        let name = entry.name().to_string();
+        let Some(relative) = name.strip_prefix(prefix) else { continue };
+        if !relative.ends_with(".class") || relative == "module-info.class" || name.starts_with("META-INF/versions/") {
+            continue;
+        }
+        count += 1;
+        if let Some(version) = class_version(&read_header(&mut entry)) {
+            highest = highest.max(Some(version));
+        }
+    }
+    (count, highest)
+}
+
+/// 文件中的一段区域，用于直接读取以不压缩方式存储的内嵌JAR
+struct FileRegion {
+    file: File,
+    start: u64,
+    len: u64,
+    position: u64,
+}
+
+impl Read for FileRegion {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.len.saturating_sub(self.position);
+        let limit = buf.len().min(remaining as usize);
+        let read = self.file.read(&mut buf[..limit])?;
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+impl Seek for FileRegion {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.len.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        }.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "seek 超出范围"))?;
+        self.file.seek(SeekFrom::Start(self.start + target))?;
+        self.position = target;
+        Ok(target)
+    }
+}
+
+/// 内嵌JAR中 class 文件的最高版本，只读取各 class 文件的文件头
+/// Spring Boot 要求 BOOT-INF/lib 不压缩存储，直接在外层文件中读取；压缩存储的 (如 WAR 的 WEB-INF/lib) 先解压到临时文件
+fn nested_class_version<R: Read + Seek>(archive: &mut ZipArchive<R>, path: &Path, name: &str) -> Option<u16> {
+    let mut entry = archive.by_name(name).ok()?;
+    if entry.compression() == CompressionMethod::Stored {
+        let (start, len) = (entry.data_start(), entry.compressed_size());
+        drop(entry);
+        let mut file = File::open(path).ok()?;
+        file.seek(SeekFrom::Start(start)).ok()?;
+        let region = FileRegion { file, start, len, position: 0 };
+        return scan_classes(&mut ZipArchive::new(region).ok()?, "").1;
+    }
+
+    let temp = std::env::temp_dir().join(format!("manager_jar_inspect_{}.jar", std::process::id()));
+    let copied = File::create(&temp).and_then(|mut file| io::copy(&mut entry, &mut file));
+    let version = copied.ok()
+        .and_then(|_| File::open(&temp).ok())
+        .and_then(|file| ZipArchive::new(file).ok())
+        .and_then(|mut nested| scan_classes(&mut nested, "").1);
+    let _ = fs::remove_file(&temp);
+    version
+}
+
+fn read_entry<R: Read + Seek>(archive: &mut ZipArchive<R>, name: &str) -> Option<Vec<u8>> {
+    let mut entry = archive.by_name(name).ok()?;
+    let mut data = Vec::new();
+    entry.read_to_end(&mut data).ok()?;
+    Some(data)
+}
+
+/// 只读取清单 (用于列表等需要快速显示的场景)
+pub fn read_manifest(path: &Path) -> Result<Vec<(String, String)>, String> {
+    let file = File::open(path).map_err(|e| format!("无法打开 {}: {}", path.display(), e))?;
+    let mut archive = ZipArchive::new(file).map_err(|e| format!("不是有效的JAR文件 {}: {}", path.display(), e))?;
+    Ok(read_entry(&mut archive, "META-INF/MANIFEST.MF")
+        .map(|data| parse_manifest(&String::from_utf8_lossy(&data)))
+        .unwrap_or_default())
+}
+
+/// 读取JAR的清单、布局、依赖和 class 文件版本
+pub fn read_jar_info(path: &Path) -> Result<JarInfo, String> {
+    let file = File::open(path).map_err(|e| format!("无法打开 {}: {}", path.display(), e))?;
+    let mut archive = ZipArchive::new(file).map_err(|e| format!("不是有效的JAR文件 {}: {}", path.display(), e))?;
+    let mut info = JarInfo {
+        manifest: read_entry(&mut archive, "META-INF/MANIFEST.MF")
+            .map(|data| parse_manifest(&String::from_utf8_lossy(&data)))
+            .unwrap_or_default(),
+        ..Default::default()
+    };
+
+    let names: Vec<String> = archive.file_names().map(str::to_string).collect();
+    info.spring_boot = info.attribute("Spring-Boot-Version").is_some()
+        || names.iter().any(|name| name.starts_with("BOOT-INF/") || name.starts_with("org/springframework/boot/loader/"));
+
+    // Spring Boot 的应用类位于 BOOT-INF/classes (WAR 为 WEB-INF/classes)，其余为 Spring Boot 加载器
+    let class_prefix = if names.iter().any(|name| name.starts_with("BOOT-INF/classes/")) {
+        "BOOT-INF/classes/"
+    } else if names.iter().any(|name| name.starts_with("WEB-INF/classes/")) {
+        "WEB-INF/classes/"
+    } else {
+        ""
+    };
+    (info.class_count, info.class_version) = scan_classes(&mut archive, class_prefix);
+
+    let mut lib_names: Vec<&String> = names.iter()
+        .filter(|name| (name.starts_with("BOOT-INF/lib/") || name.starts_with("WEB-INF/lib/") || name.starts_with("WEB-INF/lib-provided/")) && name.ends_with(".jar"))
+        .collect();
+    lib_names.sort();
+    for lib in lib_names {
+        let file = lib.rsplit('/').next().unwrap_or(lib).to_string();
+        let (name, version) = split_dependency_name(&file);
+        let class_version = nested_class_version(&mut archive, path, lib);
+        info.dependencies.push(Dependency { file, name, version, class_version });
+    }
+    Ok(info)
+}
+
+/// 显示JAR检查结果
+pub fn inspect_jar(jar: &str, show_manifest: bool) -> Result<(), String> {
+    let path = &crate::core::registry::jar_path(jar);
+    let info = read_jar_info(path)?;
+    let metadata = fs::metadata(path).map_err(|e| format!("无法读取 {}: {}", jar, e))?;
+    let modified = metadata.modified().ok()
+        .map(|time| chrono::DateTime::<chrono::Local>::from(time).format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default();
+
+    println!("\n{}", format!("=== {} ===", jar).bright_blue().bold());
+    println!("  文件大小: {}  修改时间: {}", format_file_size(metadata.len()).bright_green(), modified.bright_black());
+
+    println!("\n{}:", "清单 (META-INF/MANIFEST.MF)".bright_green());
+    if info.manifest.is_empty() {
+        println!("  {}", "无清单".bright_black());
+    }
+    for key in ["Main-Class", "Start-Class", "Implementation-Title", "Implementation-Version", "Implementation-Vendor", "Build-Jdk-Spec", "Build-Jdk", "Created-By", "Spring-Boot-Version", "Multi-Release"] {
+        if let Some(value) = info.attribute(key) {
+            println!("  {:<24}{}", format!("{}:", key), value.yellow());
+        }
+    }
+    if info.attribute("Main-Class").is_none() && !info.manifest.is_empty() {
+        print_warn!("清单中没有 Main-Class，无法用 java -jar 启动");
+    }
+    if show_manifest {
+        println!("\n{}:", "全部清单属性".bright_green());
+        for (key, value) in &info.manifest {
+            println!("  {}: {}", key.cyan(), value);
+        }
+    }
+
+    println!("\n{}:", "类型".bright_green());
+    if info.spring_boot {
+        let version = info.attribute("Spring-Boot-Version").map(|v| format!(" {}", v)).unwrap_or_default();
+        println!("  Spring Boot{} 可执行JAR (应用类 {} 个，依赖 {} 个)",
+            version.yellow(), info.class_count.to_string().yellow(), info.dependencies.len().to_string().yellow());
+    } else {
+        println!("  普通JAR (类 {} 个)", info.class_count.to_string().yellow());
+    }
+
+    println!("\n{}:", "Java 版本".bright_green());
+    match info.class_version {
+        Some(version) => println!("  应用类: Java {} (class 文件版本 {})",
+            java_for_class_version(version).to_string().yellow(), version),
+        None => println!("  应用类: {}", "无 class 文件".bright_black()),
+    }
+    if let Some(dep) = info.dependencies.iter().filter(|dep| dep.class_version.is_some()).max_by_key(|dep| dep.class_version) {
+        let version = dep.class_version.unwrap_or_default();
+        println!("  依赖中最高: Java {} ({})", java_for_class_version(version).to_string().yellow(), dep.file.cyan());
+    }
+    if let Some(required) = info.required_java() {
+        println!("  最低要求: {}", format!("Java {}", required).bright_green().bold());
+        check_configured_java(jar, required);
+    }
+
+    if !info.dependencies.is_empty() {
+        println!("\n{}:", format!("依赖 ({} 个)", info.dependencies.len()).bright_green());
+        let width = info.dependencies.iter().map(|dep| dep.name.len()).max().unwrap_or(0).min(48);
+        for dep in &info.dependencies {
+            let java = dep.class_version.map(|v| format!("Java {}", java_for_class_version(v))).unwrap_or_default();
+            println!("  {} {} {}",
+                format!("{:<width$}", dep.name, width = width).cyan(),
+                format!("{:<20}", if dep.version.is_empty() { "-" } else { &dep.version }).yellow(),
+                java.bright_black());
+        }
+    }
+    Ok(())
+}
+
+/// 与JAR配置使用的 java 比较，版本过低时提示
+fn check_configured_java(jar: &str, required: u32) {
+    let Ok(runtime) = crate::core::jdk::resolve_java(jar) else { return };
+    let Some(major) = crate::core::process::java_version(&runtime.java).and_then(|line| crate::core::gc::java_major_version(&line)) else { return };
+    if major < required {
+        print_warn!("配置使用的 Java {} ({}) 低于要求的 Java {}，启动会失败 (UnsupportedClassVersionError)", major, runtime.java, required);
+        println!("💡 使用 {} 选择合适的 Java", format!("configs set {} java_home {}", jar, required).cyan());
+    } else {
+        println!("  配置使用: Java {} ({})", major.to_string().green(), runtime.java.bright_black());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+
+    fn class(major: u8) -> Vec<u8> {
+        vec![0xCA, 0xFE, 0xBA, 0xBE, 0, 0, 0, major, 0, 0]
+    }
+
+    #[test]
+    fn parses_manifest_with_continuation_lines() {
+        let manifest = parse_manifest("Manifest-Version: 1.0\r\nMain-Class: org.springframework.boot.loader.launch.JarLaun\r\n cher\r\nStart-Class: com.example.App\r\n\r\n");
+        assert_eq!(manifest, [
+            ("Manifest-Version".to_string(), "1.0".to_string()),
+            ("Main-Class".to_string(), "org.springframework.boot.loader.launch.JarLauncher".to_string()),
+            ("Start-Class".to_string(), "com.example.App".to_string()),
+        ]);
+        let info = JarInfo { manifest, ..Default::default() };
+        assert_eq!(info.attribute("start-class"), Some("com.example.App"));
+        assert_eq!(info.attribute("Spring-Boot-Version"), None);
+    }
+
+    #[test]
+    fn parses_dependency_names_and_class_versions() {
+        assert_eq!(split_dependency_name("spring-core-6.1.2.jar"), ("spring-core".to_string(), "6.1.2".to_string()));
+        assert_eq!(split_dependency_name("jakarta.annotation-api-2.1.1.jar"), ("jakarta.annotation-api".to_string(), "2.1.1".to_string()));
+        assert_eq!(split_dependency_name("tools.jar"), ("tools".to_string(), String::new()));
+        assert_eq!(class_version(&class(61)), Some(61));
+        assert_eq!(class_version(b"PK\x03\x04\0\0\0\0"), None);
+        assert_eq!(java_for_class_version(52), 8);
+        assert_eq!(java_for_class_version(65), 21);
+    }
+
+    #[test]
+    fn skips_module_info_and_versioned_classes() {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, major) in [("module-info.class", 65), ("a/B.class", 52), ("a/C.class", 55), ("META-INF/versions/21/a/B.class", 65)] {
+            writer.start_file(name, zip::write::SimpleFileOptions::default()).unwrap();
+            writer.write_all(&class(major)).unwrap();
+        }
+        let mut archive = ZipArchive::new(writer.finish().unwrap()).unwrap();
+        assert_eq!(scan_classes(&mut archive, ""), (2, Some(55)));
+    }
+}
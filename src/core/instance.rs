@@ -0,0 +1,144 @@
+// instance.rs - 同一JAR的多个命名实例
+// 实例标识为 "<JAR或应用>@<实例名>"，PID、日志、启动参数和运行记录按实例分开保存，JAR 选项由各实例共用
+// 启动参数中的 ${INSTANCE}、${PORT_OFFSET}、${8080+PORT_OFFSET} 在启动时按实例替换
+use colored::Colorize;
+use regex::Regex;
+use crate::core::process;
+
+/// JAR与实例名之间的分隔符
+pub const SEPARATOR: char = '@';
+/// 未指定实例名时 ${INSTANCE} 的值
+const DEFAULT_INSTANCE: &str = "default";
+
+/// 拆分为 (JAR或应用, 实例名)；"@" 之后含路径分隔符或以 .jar 结尾时视为文件名的一部分 (如 svc@2.jar)
+pub fn split(jar: &str) -> (&str, Option<&str>) {
+    match jar.rsplit_once(SEPARATOR) {
+        Some((base, instance)) if !base.is_empty() && !instance.contains(['/', '\\']) && !instance.ends_with(".jar") => (base, Some(instance)),
+        _ => (jar, None),
+    }
+}
+
+/// 去掉实例名后的JAR或应用
+pub fn base(jar: &str) -> &str {
+    split(jar).0
+}
+
+pub fn instance_id(jar: &str, instance: &str) -> String {
+    format!("{}{}{}", base(jar), SEPARATOR, instance)
+}
+
+pub fn validate_name(instance: &str) -> Result<(), String> {
+    if Regex::new(r"^[A-Za-z0-9][A-Za-z0-9_.\-]*$").unwrap().is_match(instance) && !instance.ends_with(".jar") {
+        Ok(())
+    } else {
+        Err(format!("无效的实例名: {} (只能包含字母、数字、. _ -，且不能以 .jar 结尾)", instance))
+    }
+}
+
+/// 实例的端口偏移：实例名末尾的数字 (w2 为 2，3 为 3)，默认实例和不带数字的实例为 0
+pub fn port_offset(jar: &str) -> u32 {
+    let Some(instance) = split(jar).1 else { return 0 };
+    Regex::new(r"(\d+)$").unwrap().captures(instance)
+        .and_then(|caps| caps[1].parse().ok())
+        .unwrap_or(0)
+}
+
+/// 替换启动参数中的实例变量
+pub fn expand_args(jar: &str, args: &[String]) -> Vec<String> {
+    let instance = split(jar).1.unwrap_or(DEFAULT_INSTANCE);
+    let offset = port_offset(jar);
+    let sum = Regex::new(r"\$\{(\d+)\+PORT_OFFSET\}").unwrap();
+    args.iter()
+        .map(|arg| {
+            let arg = sum.replace_all(arg, |caps: &regex::Captures| (caps[1].parse::<u32>().unwrap_or(0) + offset).to_string());
+            arg.replace("${INSTANCE}", instance).replace("${PORT_OFFSET}", &offset.to_string())
+        })
+        .collect()
+}
+
+/// JAR运行中的实例 (不含默认实例)，按端口偏移和实例名排序
+pub fn running_instances(jar: &str) -> Vec<(String, u32)> {
+    let mut instances: Vec<(String, u32)> = process::get_running_jars().into_iter()
+        .filter(|(id, _)| matches!(split(id), (b, Some(_)) if b == base(jar)))
+        .collect();
+    instances.sort_by_key(|(id, _)| (port_offset(id), id.clone()));
+    instances
+}
+
+/// 扩缩到编号实例 1..=count：启动缺少的实例，停止编号超出的实例
+pub fn scale(jar: &str, count: usize, args: &[String]) -> Result<(), String> {
+    let mut failures = Vec::new();
+    for index in 1..=count {
+        let id = instance_id(jar, &index.to_string());
+        if process::is_jar_running(&id) {
+            println!("  {} 已在运行", id.bright_cyan());
+        } else if let Err(e) = process::start_jar(&id, args) {
+            failures.push(format!("{}: {}", id, e));
+        }
+    }
+    for (id, _) in running_instances(jar) {
+        let numbered = split(&id).1.and_then(|instance| instance.parse::<usize>().ok());
+        if numbered.is_some_and(|index| index > count) {
+            if let Err(e) = process::stop_jar(&id) {
+                failures.push(format!("{}: {}", id, e));
+            }
+        }
+    }
+    if failures.is_empty() {
+        print_success!("{} 已扩缩到 {} 个实例", base(jar).bright_cyan(), count.to_string().yellow());
+        Ok(())
+    } else {
+        Err(format!("部分实例操作失败:\n  {}", failures.join("\n  ")))
+    }
+}
+
+/// 在状态信息中列出JAR的其他实例
+pub fn print_instances(jar: &str) {
+    if split(jar).1.is_some() {
+        return;
+    }
+    let instances = running_instances(jar);
+    if instances.is_empty() {
+        return;
+    }
+    println!("\n{} ({} 个运行中):", "实例".bright_green(), instances.len());
+    for (id, pid) in instances {
+        let name = split(&id).1.unwrap_or_default();
+        println!("  {} (PID: {}) 端口偏移 {}", format!("{}{}", SEPARATOR, name).bright_cyan(), pid.to_string().bright_green(), port_offset(&id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_instance_names() {
+        assert_eq!(split("api@w2"), ("api", Some("w2")));
+        assert_eq!(split("app.jar@3"), ("app.jar", Some("3")));
+        assert_eq!(split("svc@2.jar"), ("svc@2.jar", None));
+        assert_eq!(split("dir@x/app.jar"), ("dir@x/app.jar", None));
+        assert_eq!(split("@w1"), ("@w1", None));
+        assert_eq!(base("api@w2"), "api");
+        assert_eq!(instance_id("api@w2", "w3"), "api@w3");
+        assert!(validate_name("w-1.blue").is_ok());
+        assert!(validate_name("2.jar").is_err());
+        assert!(validate_name("-w").is_err());
+    }
+
+    #[test]
+    fn port_offset_from_trailing_number() {
+        assert_eq!(port_offset("api@w3"), 3);
+        assert_eq!(port_offset("api@12"), 12);
+        assert_eq!(port_offset("api@blue"), 0);
+        assert_eq!(port_offset("api"), 0);
+    }
+
+    #[test]
+    fn expands_instance_variables() {
+        let args: Vec<String> = ["--server.port=${8080+PORT_OFFSET}", "-Dname=${INSTANCE}", "--offset=${PORT_OFFSET}"]
+            .iter().map(|arg| arg.to_string()).collect();
+        assert_eq!(expand_args("api@w3", &args), ["--server.port=8083", "-Dname=w3", "--offset=3"]);
+        assert_eq!(expand_args("api", &args), ["--server.port=8080", "-Dname=default", "--offset=0"]);
+    }
+}
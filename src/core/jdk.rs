@@ -0,0 +1,276 @@
+// jdk.rs - Java 运行时选择
+// 发现本机安装的 JDK (/usr/lib/jvm、JAVA_HOME、SDKMAN 等)，并按 JAR 选项和全局配置中的 java_bin、java_home 确定启动JAR所用的 java
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use colored::Colorize;
+use crate::core::config::{GlobalConfig, JarOptions};
+
+/// 一个已安装的 JDK/JRE
+#[derive(Debug, Clone)]
+pub struct Jdk {
+    /// 安装目录 (JAVA_HOME)
+    pub home: PathBuf,
+    /// 完整版本号，如 17.0.15、1.8.0_392
+    pub version: String,
+    /// 主版本号，如 17、8
+    pub major: u32,
+    /// 发行方，如 Eclipse Adoptium
+    pub vendor: String,
+    /// 是否带有 jcmd 等诊断工具 (JDK 而非 JRE)
+    pub has_tools: bool,
+}
+
+/// 启动JAR所用的 java 及其来源
+#[derive(Debug, Clone)]
+pub struct JavaRuntime {
+    /// java 可执行文件
+    pub java: String,
+    /// 配置来源说明，未配置时为 None (使用 PATH 中的 java)
+    pub source: Option<String>,
+}
+
+fn executable(name: &str) -> String {
+    if cfg!(windows) { format!("{}.exe", name) } else { name.to_string() }
+}
+
+/// 安装目录下的工具路径，如 <home>/bin/java
+fn home_tool(home: &Path, tool: &str) -> PathBuf {
+    home.join("bin").join(executable(tool))
+}
+
+/// 从版本号取主版本号: 1.8.0_392 -> 8，17.0.15 -> 17
+pub fn major_of(version: &str) -> Option<u32> {
+    let mut parts = version.trim().split(|c: char| !c.is_ascii_digit()).filter(|p| !p.is_empty());
+    let first: u32 = parts.next()?.parse().ok()?;
+    if first == 1 { parts.next()?.parse().ok() } else { Some(first) }
+}
+
+/// 按数字逐段比较版本号
+fn version_key(version: &str) -> Vec<u32> {
+    version.split(|c: char| !c.is_ascii_digit()).filter_map(|p| p.parse().ok()).collect()
+}
+
+/// 读取 release 文件中的 KEY="value"
+fn release_value(release: &str, key: &str) -> Option<String> {
+    release.lines()
+        .find_map(|line| line.strip_prefix(key)?.strip_prefix('='))
+        .map(|value| value.trim().trim_matches('"').to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// 检查目录是否为 Java 安装目录，读取版本和发行方
+fn inspect_home(home: &Path) -> Option<Jdk> {
+    let java = home_tool(home, "java");
+    if !java.is_file() {
+        return None;
+    }
+    let release = fs::read_to_string(home.join("release")).unwrap_or_default();
+    let mut version = release_value(&release, "JAVA_VERSION");
+    let mut vendor = release_value(&release, "IMPLEMENTOR");
+    if version.is_none() || vendor.is_none() {
+        // 没有 release 文件时从 java -version 获取
+        let output = Command::new(&java).arg("-version").output().ok()?;
+        let text = String::from_utf8_lossy(&output.stderr).to_string();
+        let mut lines = text.lines();
+        if version.is_none() {
+            version = lines.next().and_then(|line| line.split('"').nth(1)).map(str::to_string);
+        }
+        if vendor.is_none() {
+            vendor = lines.next().map(|line| line.split(" (build").next().unwrap_or(line).trim().to_string());
+        }
+    }
+    let version = version?;
+    Some(Jdk {
+        home: home.to_path_buf(),
+        major: major_of(&version)?,
+        version,
+        vendor: vendor.unwrap_or_else(|| "未知".to_string()),
+        has_tools: home_tool(home, "jcmd").is_file(),
+    })
+}
+
+/// 存放多个 JDK 的目录
+fn jdk_roots() -> Vec<PathBuf> {
+    let mut roots: Vec<PathBuf> = Vec::new();
+    if cfg!(windows) {
+        for base in [env::var("ProgramFiles").ok(), env::var("ProgramW6432").ok()].into_iter().flatten() {
+            for vendor in ["Java", "Eclipse Adoptium", "Microsoft", "Zulu", "Amazon Corretto", "BellSoft"] {
+                roots.push(Path::new(&base).join(vendor));
+            }
+        }
+    } else {
+        for dir in ["/usr/lib/jvm", "/usr/java", "/usr/local/java", "/opt/java", "/opt/jdk", "/opt", "/Library/Java/JavaVirtualMachines"] {
+            roots.push(PathBuf::from(dir));
+        }
+    }
+    if let Ok(sdkman) = env::var("SDKMAN_DIR") {
+        roots.push(Path::new(&sdkman).join("candidates/java"));
+    }
+    if let Some(home) = dirs::home_dir() {
+        roots.push(home.join(".sdkman/candidates/java"));
+        roots.push(home.join(".jdks"));
+        roots.push(home.join(".asdf/installs/java"));
+    }
+    roots
+}
+
+/// PATH 中第一个 java 所在的安装目录
+fn path_java_home() -> Option<PathBuf> {
+    let java = env::split_paths(&env::var_os("PATH")?)
+        .map(|dir| dir.join(executable("java")))
+        .find(|path| path.is_file())?;
+    let home = fs::canonicalize(java).ok()?.parent()?.parent()?.to_path_buf();
+    // JDK 8 的 java 可能位于 <jdk>/jre/bin
+    match home.parent() {
+        Some(parent) if home.ends_with("jre") && home_tool(parent, "java").is_file() => Some(parent.to_path_buf()),
+        _ => Some(home),
+    }
+}
+
+/// JAVA_HOME 环境变量指向的目录
+fn env_java_home() -> Option<PathBuf> {
+    env::var_os("JAVA_HOME").map(PathBuf::from).filter(|home| !home.as_os_str().is_empty())
+}
+
+/// 发现本机安装的 JDK，按主版本号和版本号从新到旧排列
+pub fn discover_jdks() -> Vec<Jdk> {
+    let mut candidates: Vec<PathBuf> = Vec::new();
+    candidates.extend(env_java_home());
+    candidates.extend(path_java_home());
+    for root in jdk_roots() {
+        let Ok(entries) = fs::read_dir(&root) else { continue };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            // macOS 的 JDK 位于 <name>.jdk/Contents/Home
+            let mac_home = path.join("Contents/Home");
+            candidates.push(if mac_home.is_dir() { mac_home } else { path });
+        }
+    }
+
+    let mut seen = HashSet::new();
+    let mut jdks: Vec<Jdk> = candidates.into_iter()
+        .filter_map(|home| fs::canonicalize(&home).ok())
+        .filter(|home| seen.insert(home.clone()))
+        .filter_map(|home| inspect_home(&home))
+        .collect();
+    jdks.sort_by_key(|jdk| Reverse(version_key(&jdk.version)));
+    jdks
+}
+
+/// 按主版本号选择 JDK：优先带诊断工具的，其次版本最新的
+fn find_by_major(major: u32) -> Result<Jdk, String> {
+    let jdks = discover_jdks();
+    let matching: Vec<&Jdk> = jdks.iter().filter(|jdk| jdk.major == major).collect();
+    if let Some(jdk) = matching.iter().find(|jdk| jdk.has_tools).or(matching.first()) {
+        return Ok((*jdk).clone());
+    }
+    let mut majors: Vec<u32> = jdks.iter().map(|jdk| jdk.major).collect();
+    majors.dedup();
+    let available = if majors.is_empty() {
+        "无".to_string()
+    } else {
+        majors.iter().map(u32::to_string).collect::<Vec<_>>().join(", ")
+    };
+    Err(format!("未找到 Java {} (已安装: {}，见 'jdks' 命令)", major, available))
+}
+
+/// 把 java_home 的值 (安装目录或主版本号) 解析为安装目录
+pub fn resolve_home(spec: &str) -> Result<PathBuf, String> {
+    let spec = spec.trim();
+    if let Ok(major) = spec.parse::<u32>() {
+        return find_by_major(major).map(|jdk| jdk.home);
+    }
+    let home = PathBuf::from(spec);
+    if home_tool(&home, "java").is_file() {
+        Ok(home)
+    } else {
+        Err(format!("无效的 java_home: {} (找不到 {})", spec, home_tool(&home, "java").display()))
+    }
+}
+
+/// 检查 java_bin 的值：路径必须存在，不含路径分隔符时在 PATH 中查找
+pub fn validate_bin(spec: &str) -> Result<(), String> {
+    let path = Path::new(spec.trim());
+    let found = if path.components().count() > 1 {
+        path.is_file()
+    } else {
+        env::var_os("PATH").is_some_and(|paths| env::split_paths(&paths).any(|dir| dir.join(path).is_file()))
+    };
+    if found { Ok(()) } else { Err(format!("找不到 java 可执行文件: {}", spec)) }
+}
+
+/// 确定JAR使用的 java。优先级: JAR 的 java_bin、java_home，全局的 java_bin、java_home，最后是 PATH 中的 java
+pub fn resolve_java(jar: &str) -> Result<JavaRuntime, String> {
+    let options = JarOptions::load(jar);
+    let config = GlobalConfig::load();
+    let settings = [
+        ("JAR 选项 java_bin", options.java_bin.as_deref(), true),
+        ("JAR 选项 java_home", options.java_home.as_deref(), false),
+        ("全局 java_bin", Some(config.process.java_bin.as_str()), true),
+        ("全局 java_home", Some(config.process.java_home.as_str()), false),
+    ];
+    for (source, value, is_bin) in settings {
+        let Some(value) = value.map(str::trim).filter(|v| !v.is_empty()) else { continue };
+        let java = if is_bin {
+            value.to_string()
+        } else {
+            let home = resolve_home(value).map_err(|e| format!("{}: {}", source, e))?;
+            home_tool(&home, "java").display().to_string()
+        };
+        return Ok(JavaRuntime { java, source: Some(format!("{} = {}", source, value)) });
+    }
+    Ok(JavaRuntime { java: "java".to_string(), source: None })
+}
+
+/// 与JAR所用 java 同目录的 JDK 工具 (如 jcmd)，找不到时使用 PATH 中的
+pub fn tool_for(jar: &str, tool: &str) -> String {
+    resolve_java(jar).ok()
+        .map(|runtime| PathBuf::from(runtime.java))
+        .filter(|java| java.components().count() > 1)
+        .and_then(|java| java.parent().map(|bin| bin.join(executable(tool))))
+        .filter(|path| path.is_file())
+        .map(|path| path.display().to_string())
+        .unwrap_or_else(|| tool.to_string())
+}
+
+/// 显示已安装的 JDK 和当前的 Java 配置
+pub fn show_jdks() {
+    println!("\n{}", "=== 已安装的 Java ===".bright_blue().bold());
+    let jdks = discover_jdks();
+    if jdks.is_empty() {
+        print_warn!("未找到已安装的 Java");
+    }
+    let java_home = env_java_home().and_then(|home| fs::canonicalize(home).ok());
+    let path_home = path_java_home();
+    for jdk in &jdks {
+        let mut marks = Vec::new();
+        if path_home.as_ref() == Some(&jdk.home) {
+            marks.push("PATH");
+        }
+        if java_home.as_ref() == Some(&jdk.home) {
+            marks.push("JAVA_HOME");
+        }
+        let kind = if jdk.has_tools { "JDK" } else { "JRE" };
+        println!("  {} {} {} {}",
+            format!("{:>3}", jdk.major).bright_green().bold(),
+            format!("{:<14}", jdk.version).yellow(),
+            format!("{:<3} {:<20}", kind, jdk.vendor).white(),
+            jdk.home.display().to_string().cyan(),
+        );
+        if !marks.is_empty() {
+            println!("      {}", format!("[{}]", marks.join(", ")).bright_black());
+        }
+    }
+
+    let config = GlobalConfig::load();
+    println!("\n{}:", "全局设置".bright_green());
+    let show = |value: &str| if value.is_empty() { "未设置".bright_black() } else { value.yellow() };
+    println!("  java_home: {}", show(&config.process.java_home));
+    println!("  java_bin:  {}", show(&config.process.java_bin));
+    println!("💡 使用 {} 或 {} 选择 Java，值可以是安装目录或主版本号",
+        "global-config set-java-home <目录|版本|off>".cyan(), "configs set <jar> java_home <目录|版本>".cyan());
+}
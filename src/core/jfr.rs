@@ -0,0 +1,222 @@
+// jfr.rs - Java Flight Recorder
+// 通过 jcmd JFR.start/JFR.dump/JFR.stop 控制录制，录制文件保存在 .Manager_Jar/data/jfr/<jar>/ 并按配置保留最近的几个
+use std::fs;
+use std::path::{Path, PathBuf};
+use colored::Colorize;
+use regex::Regex;
+use crate::core::config::{GlobalConfig, JarOptions};
+use crate::core::process;
+use crate::utils::files::format_file_size;
+
+const JFR_DIR: &str = ".Manager_Jar/data/jfr";
+/// 随JAR启动的持续录制的名称
+const CONTINUOUS_RECORDING: &str = "continuous";
+
+/// 录制文件目录
+fn recording_dir(jar: &str) -> PathBuf {
+    PathBuf::from(JFR_DIR).join(crate::core::registry::state_key(jar))
+}
+
+/// 创建录制目录并返回绝对路径（jcmd 和 JVM 参数中的路径由目标进程解析）
+fn ensure_recording_dir(jar: &str) -> Result<PathBuf, String> {
+    let dir = recording_dir(jar);
+    fs::create_dir_all(&dir).map_err(|e| format!("创建目录失败 {}: {}", dir.display(), e))?;
+    fs::canonicalize(&dir).map_err(|e| format!("无法解析目录 {}: {}", dir.display(), e))
+}
+
+fn new_recording_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{}-{}.jfr", chrono::Local::now().format("%Y%m%d-%H%M%S"), name))
+}
+
+/// JAR已保存的录制文件，按时间从旧到新
+pub fn recordings(jar: &str) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(recording_dir(jar))
+        .map(|entries| entries.flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "jfr"))
+            .collect())
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+/// 只保留最近的 max_recordings 个录制文件
+fn prune_recordings(jar: &str) {
+    let keep = GlobalConfig::load().jfr.max_recordings;
+    if keep == 0 {
+        return;
+    }
+    let files = recordings(jar);
+    for path in &files[..files.len().saturating_sub(keep)] {
+        if fs::remove_file(path).is_ok() {
+            print_info!("删除旧的录制文件: {}", path.display());
+        }
+    }
+}
+
+fn validate_name(name: &str) -> Result<(), String> {
+    if !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        Ok(())
+    } else {
+        Err(format!("无效的录制名称: {} (只能包含字母、数字、- 和 _)", name))
+    }
+}
+
+/// JFR 的时长格式: 数字加单位 s、m、h、d
+fn validate_duration(duration: &str) -> Result<(), String> {
+    if Regex::new(r"^\d+(s|m|h|d)$").unwrap().is_match(duration) {
+        Ok(())
+    } else {
+        Err(format!("无效的时长: {} (如 30s、10m、2h)", duration))
+    }
+}
+
+fn running_pid(jar: &str) -> Result<u32, String> {
+    process::get_pid(jar).ok_or_else(|| format!("{} 未在运行", jar))
+}
+
+/// 打印 jcmd 的输出（去掉第一行的 "<pid>:"）
+fn print_jcmd_output(output: &str) {
+    for line in output.lines().skip(1).filter(|l| !l.trim().is_empty()) {
+        println!("  {}", line.trim_end().bright_black());
+    }
+}
+
+/// 开始录制；指定时长时到期后 JVM 自动写入录制文件
+pub fn start_recording(jar: &str, name: &str, duration: Option<&str>, settings: &str) -> Result<(), String> {
+    validate_name(name)?;
+    if let Some(duration) = duration {
+        validate_duration(duration)?;
+    }
+    let pid = running_pid(jar)?;
+
+    let mut args = vec!["JFR.start".to_string(), format!("name={}", name), format!("settings={}", settings)];
+    let mut target = None;
+    if let Some(duration) = duration {
+        let path = new_recording_path(&ensure_recording_dir(jar)?, name);
+        args.push(format!("duration={}", duration));
+        args.push(format!("filename={}", path.display()));
+        target = Some(path);
+    }
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let output = process::jcmd(jar, pid, &args)?;
+    if output.contains("Could not") {
+        return Err(output.lines().skip(1).collect::<Vec<_>>().join(" ").trim().to_string());
+    }
+
+    print_success!("已开始 JFR 录制: {} (设置: {})", name.cyan(), settings.yellow());
+    print_jcmd_output(&output);
+    match target {
+        Some(path) => {
+            println!("  {} 后自动保存到: {}", duration.unwrap_or_default().yellow(), path.display().to_string().cyan());
+            prune_recordings(jar);
+        },
+        None => println!("💡 使用 {} 保存当前数据，{} 结束录制", format!("jfr dump {} -n {}", jar, name).cyan(), format!("jfr stop {} -n {}", jar, name).cyan()),
+    }
+    Ok(())
+}
+
+/// 把录制的当前数据写入文件（录制继续进行）；未指定名称时写入所有录制
+pub fn dump_recording(jar: &str, name: Option<&str>) -> Result<(), String> {
+    let pid = running_pid(jar)?;
+    let path = new_recording_path(&ensure_recording_dir(jar)?, name.unwrap_or("all"));
+    let mut args = vec!["JFR.dump".to_string()];
+    if let Some(name) = name {
+        validate_name(name)?;
+        args.push(format!("name={}", name));
+    }
+    args.push(format!("filename={}", path.display()));
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let output = process::jcmd(jar, pid, &args)?;
+    report_saved(jar, &path, &output)
+}
+
+/// 结束录制；discard 为 false 时先把数据写入文件
+pub fn stop_recording(jar: &str, name: &str, discard: bool) -> Result<(), String> {
+    validate_name(name)?;
+    let pid = running_pid(jar)?;
+    if discard {
+        let output = process::jcmd(jar, pid, &["JFR.stop", &format!("name={}", name)])?;
+        print_success!("已结束 JFR 录制: {} (数据已丢弃)", name.cyan());
+        print_jcmd_output(&output);
+        return Ok(());
+    }
+    let path = new_recording_path(&ensure_recording_dir(jar)?, name);
+    let output = process::jcmd(jar, pid, &["JFR.stop", &format!("name={}", name), &format!("filename={}", path.display())])?;
+    report_saved(jar, &path, &output)
+}
+
+fn report_saved(jar: &str, path: &Path, output: &str) -> Result<(), String> {
+    match fs::metadata(path) {
+        Ok(metadata) => {
+            print_success!("录制已保存: {} ({})", path.display().to_string().cyan(), format_file_size(metadata.len()).bright_green());
+            prune_recordings(jar);
+            println!("💡 可用 JDK Mission Control 或 'jfr print --events jdk.ExecutionSample' 分析");
+            Ok(())
+        },
+        Err(_) => Err(format!("录制文件未生成: {}", output.lines().skip(1).collect::<Vec<_>>().join(" ").trim())),
+    }
+}
+
+/// 显示进行中的录制和已保存的录制文件
+pub fn list_recordings(jar: &str) {
+    println!("\n{}", format!("=== {} JFR 录制 ===", jar).bright_blue().bold());
+    match process::get_pid(jar) {
+        Some(pid) => match process::jcmd(jar, pid, &["JFR.check"]) {
+            Ok(output) => {
+                println!("{}:", "进行中".bright_green());
+                print_jcmd_output(&output);
+            },
+            Err(e) => print_warn!("无法查询进行中的录制: {}", e),
+        },
+        None => println!("  {} 未在运行", jar.bright_black()),
+    }
+
+    println!("{}:", "已保存".bright_green());
+    let files = recordings(jar);
+    if files.is_empty() {
+        println!("  {}", "无".bright_black());
+    }
+    for path in &files {
+        let size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        println!("  {} ({})", path.display().to_string().cyan(), format_file_size(size).bright_green());
+    }
+}
+
+/// 在状态信息中显示最近的录制文件
+pub fn print_status(jar: &str) {
+    let files = recordings(jar);
+    if let Some(continuous) = JarOptions::load(jar).jfr.as_deref() {
+        println!("  持续 JFR: {} (设置: {})", "启用".bright_green(), continuous.yellow());
+    }
+    if files.is_empty() {
+        return;
+    }
+    println!("  JFR 录制: {} 个", files.len().to_string().yellow());
+    for path in files.iter().rev().take(3) {
+        let size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        println!("    {} ({})", path.display().to_string().cyan(), format_file_size(size).bright_black());
+    }
+}
+
+/// 启用了持续录制的JAR的 JVM 参数：以磁盘缓冲持续录制，进程退出时写入文件
+pub fn continuous_jvm_args(jar: &str) -> Vec<String> {
+    let settings = match JarOptions::load(jar).jfr {
+        Some(settings) => settings,
+        None => return Vec::new(),
+    };
+    let dir = match ensure_recording_dir(jar) {
+        Ok(dir) => dir,
+        Err(e) => {
+            print_warn!("持续 JFR 未启用: {}", e);
+            return Vec::new();
+        },
+    };
+    let config = GlobalConfig::load();
+    prune_recordings(jar);
+    vec![format!(
+        "-XX:StartFlightRecording=name={},settings={},disk=true,maxage={},maxsize={},dumponexit=true,filename={}",
+        CONTINUOUS_RECORDING, settings, config.jfr.max_age, config.jfr.max_size,
+        new_recording_path(&dir, CONTINUOUS_RECORDING).display(),
+    )]
+}
@@ -0,0 +1,346 @@
+// jsonlog.rs - 结构化 JSON 日志
+// 识别 logback (logstash-logback-encoder)、log4j2 JsonLayout/JsonTemplateLayout、ECS 等编码器输出的
+// 单行 JSON 日志，渲染为 `时间 级别 logger 消息` 并支持按字段选择和过滤
+use colored::Colorize;
+use regex::Regex;
+use serde_json::{Map, Value};
+use crate::core::logging;
+
+/// 常见字段的别名，按优先级排列
+const TIMESTAMP_KEYS: &[&str] = &["@timestamp", "timestamp", "time", "ts", "instant", "timeMillis"];
+const LEVEL_KEYS: &[&str] = &["level", "log.level", "severity", "levelname"];
+const LOGGER_KEYS: &[&str] = &["logger_name", "loggerName", "logger", "log.logger"];
+const MESSAGE_KEYS: &[&str] = &["message", "msg"];
+const THREAD_KEYS: &[&str] = &["thread_name", "threadName", "thread", "process.thread.name"];
+const STACK_KEYS: &[&str] = &["stack_trace", "stackTrace", "error.stack_trace", "exception", "thrown"];
+
+/// 字段过滤条件
+enum FieldFilter {
+    /// key=value，忽略大小写
+    Equals(String, String),
+    /// key!=value，忽略大小写
+    NotEquals(String, String),
+    /// key~regex
+    Matches(String, Regex),
+}
+
+/// JSON 日志的显示方式：字段选择和过滤条件
+#[derive(Default)]
+pub struct JsonView {
+    fields: Vec<String>,
+    filters: Vec<FieldFilter>,
+}
+
+impl JsonView {
+    /// 解析 `--fields` 和 `--where` 参数
+    /// 过滤条件格式: `key=value`、`key!=value`、`key~正则`
+    pub fn new(fields: &[String], filters: &[String]) -> Result<Self, String> {
+        let mut parsed = Vec::new();
+        for filter in filters {
+            // 以第一个运算符分隔，值或正则中可以再包含运算符
+            let position = filter.find(['=', '~', '!'].as_slice())
+                .filter(|&pos| pos > 0 && (&filter[pos..pos + 1] != "!" || filter[pos..].starts_with("!=")));
+            let pos = match position {
+                Some(pos) => pos,
+                None => return Err(format!("无效的过滤条件: {} (格式: key=value、key!=value 或 key~正则)", filter)),
+            };
+            let key = filter[..pos].trim().to_string();
+            let parsed_filter = match &filter[pos..pos + 1] {
+                "!" => FieldFilter::NotEquals(key, filter[pos + 2..].trim().to_string()),
+                "=" => FieldFilter::Equals(key, filter[pos + 1..].trim().to_string()),
+                _ => {
+                    let pattern = filter[pos + 1..].trim();
+                    let regex = Regex::new(pattern).map_err(|e| format!("无效的正则表达式 {}: {}", pattern, e))?;
+                    FieldFilter::Matches(key, regex)
+                },
+            };
+            parsed.push(parsed_filter);
+        }
+
+        Ok(Self {
+            fields: fields.iter().map(|f| f.trim().to_string()).filter(|f| !f.is_empty()).collect(),
+            filters: parsed,
+        })
+    }
+
+    /// 是否设置了过滤条件
+    pub fn has_filters(&self) -> bool {
+        !self.filters.is_empty()
+    }
+
+    /// 日志行是否满足过滤条件；有过滤条件时非 JSON 行一律不满足
+    pub fn matches(&self, line: &str) -> bool {
+        if self.filters.is_empty() {
+            return true;
+        }
+        let object = match parse_json_line(line) {
+            Some(object) => object,
+            None => return false,
+        };
+        self.filters.iter().all(|filter| match filter {
+            FieldFilter::Equals(key, value) => field_text(&object, key).is_some_and(|v| v.eq_ignore_ascii_case(value)),
+            FieldFilter::NotEquals(key, value) => !field_text(&object, key).is_some_and(|v| v.eq_ignore_ascii_case(value)),
+            FieldFilter::Matches(key, regex) => field_text(&object, key).is_some_and(|v| regex.is_match(&v)),
+        })
+    }
+
+    /// 打印一行日志：JSON 行格式化显示（含堆栈），其余行按原样高亮
+    pub fn print(&self, line: &str) {
+        let object = match parse_json_line(line) {
+            Some(object) => object,
+            None => {
+                logging::print_highlighted(line);
+                return;
+            }
+        };
+
+        if !self.fields.is_empty() {
+            println!("{}", self.selected_fields(&object, line).join(" "));
+            return;
+        }
+
+        let level = field_text(&object, "level").unwrap_or_default();
+        let mut parts = Vec::new();
+        if let Some(ts) = field_text(&object, "timestamp").or_else(|| logging::line_timestamp(line).map(str::to_string)) {
+            parts.push(ts.bright_black().to_string());
+        }
+        if !level.is_empty() {
+            parts.push(color_level(&format!("{:<5}", level)));
+        }
+        if let Some(logger) = field_text(&object, "logger") {
+            parts.push(logger.cyan().to_string());
+        }
+        let message = field_text(&object, "message").unwrap_or_default();
+        parts.push(if is_error_level(&level) { message.red().to_string() } else { message });
+        println!("{}", parts.join(" "));
+
+        for (i, stack_line) in stack_trace_lines(&object).iter().enumerate() {
+            if i == 0 || !stack_line.trim_start().starts_with("at ") {
+                println!("    {}", stack_line.red());
+            } else {
+                println!("    {}", stack_line.bright_black());
+            }
+        }
+    }
+
+    /// 单行纯文本形式（不含颜色和堆栈），用于搜索结果；非 JSON 行返回 None
+    pub fn render_plain(&self, line: &str) -> Option<String> {
+        let object = parse_json_line(line)?;
+        if !self.fields.is_empty() {
+            return Some(self.selected_fields(&object, line).join(" "));
+        }
+        let parts: Vec<String> = ["timestamp", "level", "logger", "message"]
+            .iter()
+            .filter_map(|key| field_text(&object, key))
+            .collect();
+        Some(parts.join(" "))
+    }
+
+    fn selected_fields(&self, object: &Map<String, Value>, line: &str) -> Vec<String> {
+        self.fields.iter().map(|key| {
+            let value = field_text(object, key).unwrap_or_else(|| "-".to_string());
+            match key.as_str() {
+                "level" => color_level(&value),
+                "timestamp" | "ts" | "time" => value.bright_black().to_string(),
+                "logger" => value.cyan().to_string(),
+                "stream" => logging::line_stream(line).unwrap_or("-").to_string(),
+                _ => value,
+            }
+        }).collect()
+    }
+}
+
+/// 解析一行 JSON 日志（可带日志管道前缀），不是 JSON 对象时返回 None
+pub fn parse_json_line(line: &str) -> Option<Map<String, Value>> {
+    let body = logging::strip_line_prefix(line).trim();
+    if !body.starts_with('{') || !body.ends_with('}') {
+        return None;
+    }
+    match serde_json::from_str(body) {
+        Ok(Value::Object(object)) => Some(object),
+        _ => None,
+    }
+}
+
+/// 按名称取字段值：先匹配标准字段的别名，再按原始键名或点分路径查找
+fn field_text(object: &Map<String, Value>, key: &str) -> Option<String> {
+    let aliases: &[&str] = match key {
+        "timestamp" | "ts" | "time" => TIMESTAMP_KEYS,
+        "level" => LEVEL_KEYS,
+        "logger" => LOGGER_KEYS,
+        "message" | "msg" => MESSAGE_KEYS,
+        "thread" => THREAD_KEYS,
+        _ => &[],
+    };
+    for alias in aliases {
+        if let Some(value) = lookup(object, alias) {
+            return Some(match *alias {
+                "instant" | "timeMillis" => format_epoch(value),
+                _ => value_text(value),
+            });
+        }
+    }
+    lookup(object, key).map(value_text)
+}
+
+/// 按原始键名查找，找不到时按点分路径查找嵌套对象 (如 mdc.traceId)
+fn lookup<'a>(object: &'a Map<String, Value>, key: &str) -> Option<&'a Value> {
+    if let Some(value) = object.get(key) {
+        return Some(value);
+    }
+    let mut parts = key.split('.');
+    let mut current = object.get(parts.next()?)?;
+    for part in parts {
+        current = current.as_object()?.get(part)?;
+    }
+    Some(current)
+}
+
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+/// log4j2 的 instant 对象或 timeMillis 毫秒数转为本地时间
+fn format_epoch(value: &Value) -> String {
+    let (secs, nanos) = match value {
+        Value::Object(instant) => (
+            instant.get("epochSecond").and_then(Value::as_i64).unwrap_or(0),
+            instant.get("nanoOfSecond").and_then(Value::as_u64).unwrap_or(0) as u32,
+        ),
+        Value::Number(millis) => {
+            let millis = millis.as_i64().unwrap_or(0);
+            (millis / 1000, (millis % 1000) as u32 * 1_000_000)
+        },
+        other => return value_text(other),
+    };
+    chrono::DateTime::from_timestamp(secs, nanos)
+        .map(|time| time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S%.3f").to_string())
+        .unwrap_or_else(|| value.to_string())
+}
+
+/// 取出异常堆栈的各行，兼容字符串和 log4j2 的 thrown 对象
+fn stack_trace_lines(object: &Map<String, Value>) -> Vec<String> {
+    let value = match STACK_KEYS.iter().find_map(|key| lookup(object, key)) {
+        Some(value) => value,
+        None => return Vec::new(),
+    };
+    match value {
+        Value::String(text) => text.lines().map(|l| l.trim_end().replace('\t', "    ")).collect(),
+        Value::Object(thrown) => thrown_lines(thrown),
+        _ => Vec::new(),
+    }
+}
+
+/// log4j2 JsonLayout 的 thrown 对象: {name, message, extendedStackTrace: [...], cause: {...}}
+fn thrown_lines(thrown: &Map<String, Value>) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = Some(thrown);
+    while let Some(thrown) = current {
+        let name = thrown.get("name").map(value_text).unwrap_or_default();
+        let header = match thrown.get("message").map(value_text) {
+            Some(message) if !message.is_empty() => format!("{}: {}", name, message),
+            _ => name,
+        };
+        lines.push(if lines.is_empty() { header } else { format!("Caused by: {}", header) });
+
+        let frames = thrown.get("extendedStackTrace").or_else(|| thrown.get("stackTrace"));
+        for frame in frames.and_then(Value::as_array).into_iter().flatten() {
+            let field = |key: &str| frame.get(key).map(value_text).unwrap_or_default();
+            let class = field("class");
+            let class = if class.is_empty() { field("className") } else { class };
+            let method = field("method");
+            let method = if method.is_empty() { field("methodName") } else { method };
+            let file = field("file");
+            let file = if file.is_empty() { field("fileName") } else { file };
+            let line = field("line");
+            let line = if line.is_empty() { field("lineNumber") } else { line };
+            lines.push(format!("    at {}.{}({}:{})", class, method, file, line));
+        }
+        current = thrown.get("cause").and_then(Value::as_object);
+    }
+    lines
+}
+
+fn is_error_level(level: &str) -> bool {
+    matches!(level.to_ascii_uppercase().as_str(), "ERROR" | "FATAL" | "SEVERE")
+}
+
+fn color_level(level: &str) -> String {
+    match level.trim().to_ascii_uppercase().as_str() {
+        "ERROR" | "FATAL" | "SEVERE" => level.red().bold().to_string(),
+        "WARN" | "WARNING" => level.yellow().bold().to_string(),
+        "INFO" => level.green().to_string(),
+        "DEBUG" | "TRACE" | "FINE" => level.bright_black().to_string(),
+        _ => level.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOGBACK: &str = r#"{"@timestamp":"2024-05-01T10:00:00.000+08:00","level":"ERROR","logger_name":"com.example.Api","thread_name":"main","message":"request failed","mdc":{"traceId":"abc"},"stack_trace":"java.lang.IllegalStateException: boom\n\tat com.example.Api.call(Api.java:10)"}"#;
+
+    fn view(fields: &[&str], filters: &[&str]) -> JsonView {
+        let strings = |items: &[&str]| items.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        JsonView::new(&strings(fields), &strings(filters)).unwrap()
+    }
+
+    #[test]
+    fn parses_json_lines_with_pipeline_prefix() {
+        assert!(parse_json_line(LOGBACK).is_some());
+        assert!(parse_json_line(&format!("2024-05-01 10:00:00 [out] {}", LOGBACK)).is_some());
+        assert!(parse_json_line("2024-05-01 10:00:00 INFO plain text").is_none());
+        assert!(parse_json_line("{not json}").is_none());
+    }
+
+    #[test]
+    fn resolves_field_aliases_and_nested_paths() {
+        let object = parse_json_line(LOGBACK).unwrap();
+        assert_eq!(field_text(&object, "level").as_deref(), Some("ERROR"));
+        assert_eq!(field_text(&object, "logger").as_deref(), Some("com.example.Api"));
+        assert_eq!(field_text(&object, "thread").as_deref(), Some("main"));
+        assert_eq!(field_text(&object, "mdc.traceId").as_deref(), Some("abc"));
+        assert_eq!(field_text(&object, "missing"), None);
+        assert_eq!(stack_trace_lines(&object), ["java.lang.IllegalStateException: boom", "    at com.example.Api.call(Api.java:10)"]);
+    }
+
+    #[test]
+    fn parses_filters() {
+        assert!(JsonView::new(&[], &["level".to_string()]).is_err());
+        assert!(JsonView::new(&[], &["=value".to_string()]).is_err());
+        assert!(JsonView::new(&[], &["message~(".to_string()]).is_err());
+        assert!(view(&[], &["url=a=b", "level!=info", "message~fail(ed)?"]).has_filters());
+    }
+
+    #[test]
+    fn matches_filters() {
+        assert!(view(&[], &["level=error"]).matches(LOGBACK));
+        assert!(!view(&[], &["level!=ERROR"]).matches(LOGBACK));
+        assert!(view(&[], &["message~^request", "mdc.traceId=abc"]).matches(LOGBACK));
+        assert!(!view(&[], &["mdc.traceId=xyz"]).matches(LOGBACK));
+        assert!(!view(&[], &["level=error"]).matches("ERROR plain text"));
+        assert!(view(&[], &[]).matches("ERROR plain text"));
+    }
+
+    #[test]
+    fn renders_plain_text() {
+        assert_eq!(view(&[], &[]).render_plain(LOGBACK).as_deref(),
+                   Some("2024-05-01T10:00:00.000+08:00 ERROR com.example.Api request failed"));
+        assert_eq!(view(&["thread", "mdc.traceId", "missing"], &[]).render_plain(LOGBACK).as_deref(), Some("main abc -"));
+        assert_eq!(view(&[], &[]).render_plain("plain"), None);
+    }
+
+    #[test]
+    fn formats_log4j2_thrown_and_epoch() {
+        let line = r#"{"timeMillis":0,"level":"WARN","message":"x","thrown":{"name":"java.io.IOException","message":"closed","extendedStackTrace":[{"class":"a.B","method":"c","file":"B.java","line":3}],"cause":{"name":"java.net.SocketException"}}}"#;
+        let object = parse_json_line(line).unwrap();
+        assert_eq!(stack_trace_lines(&object), ["java.io.IOException: closed", "    at a.B.c(B.java:3)", "Caused by: java.net.SocketException"]);
+        let expected = chrono::DateTime::from_timestamp(0, 0).unwrap().with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S%.3f").to_string();
+        assert_eq!(field_text(&object, "timestamp"), Some(expected));
+    }
+}
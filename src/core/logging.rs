@@ -1,77 +1,87 @@
 // logging.rs - 日志管理模块
-use std::process::Command;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::sync::Mutex;
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime};
 use colored::Colorize;
-use crate::core::config::GlobalConfig;
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use encoding_rs::Encoding;
+use regex::RegexBuilder;
+use crate::core::config::{GlobalConfig, JarOptions, LogMode};
+use crate::core::highlight;
+use crate::core::jsonlog::JsonView;
+
+/// 轮转后的旧日志在最后一次写入多久之后才允许压缩（秒）
+/// 运行中的进程可能仍持有被轮转文件的句柄，过早压缩会丢失其后续输出
+pub const COMPRESS_QUIET_SECS: u64 = 60;
+
+/// 后台压缩任务句柄，进程退出前需等待完成
+static BACKGROUND_COMPRESSIONS: Mutex<Vec<JoinHandle<()>>> = Mutex::new(Vec::new());
 
 // 显示JAR日志 - 直接实现，供 main.rs 调用
-pub fn show_jar_log(jar: &str, lines: u32) {
+// stderr_only 为 true 时只显示错误输出流，JSON 日志按 view 选择字段和过滤
+// follow 为 true 时显示完最后几行后继续跟踪新写入的日志
+pub fn show_jar_log(jar: &str, lines: u32, stderr_only: bool, follow: bool, view: &JsonView) {
     let config = GlobalConfig::load();
-    let log_file = config.get_log_file_path(jar);
-    let log_path = Path::new(&log_file);
     
     // 检查是否需要轮转日志
-    if config.should_rotate_log(&log_file) {
-        drop(config); // 释放锁
-        if let Err(e) = GlobalConfig::load().rotate_log(jar) {
-            print_warn!("日志轮转失败: {}", e);
-        }
-    } else {
-        drop(config); // 释放锁
+    if let Err(e) = config.rotate_jar_logs(jar) {
+        print_warn!("日志轮转失败: {}", e);
     }
     
+    // 分流模式直接读取对应的文件，合并模式按管道写入的 [err] 标记过滤
+    let split = JarOptions::load(jar).log_mode == LogMode::Split;
+    let log_file = match (split, stderr_only) {
+        (true, true) => config.get_stream_log_path(jar, "err"),
+        (true, false) => config.get_stream_log_path(jar, "out"),
+        (false, _) => config.get_log_file_path(jar),
+    };
+    let filter_stderr = stderr_only && !split;
+    let encoding = JarOptions::load(jar).encoding();
+    drop(config); // 释放锁
+    let log_path = Path::new(&log_file);
+    let filter = |line: &str| (!filter_stderr || line_stream(line) == Some("err")) && view.matches(line);
+    
     if !log_path.exists() {
         print_warn!("日志文件不存在: {}", log_file);
         println!("提示: JAR 应用启动后会自动创建日志文件");
+        if follow {
+            follow_log(log_path, encoding, &filter, view);
+        }
         return;
     }
     
     // 检查文件大小
     if let Ok(metadata) = fs::metadata(&log_file) {
         let size = metadata.len();
-        if size == 0 {
+        if size == 0 && !follow {
             print_warn!("日志文件为空: {}", log_file);
             return;
         }
         println!("📄 日志文件: {} (大小: {} 字节)", log_file.cyan(), size.to_string().bright_green());
     }
     
-    println!("📖 显示最后 {} 行日志:", lines.to_string().bright_green());
+    let stream_hint = if stderr_only { " (stderr)" } else { "" };
+    println!("📖 显示最后 {} 行日志{}:", lines.to_string().bright_green(), stream_hint.yellow());
     println!("{}", "─".repeat(80).bright_blue());
     
-    let output = if cfg!(target_os = "windows") {
-        Command::new("powershell")
-            .args(&[
-                "-Command", 
-                &format!("Get-Content '{}' -Tail {} -Encoding UTF8", log_file, lines)
-            ])
-            .output()
-    } else {
-        Command::new("tail")
-            .args(&["-n", &lines.to_string(), &log_file])
-            .output()
-    };
-    
-    match output {
-        Ok(result) => {
-            let content = String::from_utf8_lossy(&result.stdout);
-            if content.trim().is_empty() {
-                print_warn!("日志内容为空或无法读取");
+    match read_last_lines(log_path, lines as usize, encoding, &filter) {
+        Ok(content) => {
+            if content.is_empty() && lines > 0 {
+                if view.has_filters() {
+                    print_warn!("没有满足过滤条件的 JSON 日志");
+                } else if filter_stderr {
+                    print_warn!("没有 stderr 输出 (需启用日志管道才能区分输出流)");
+                } else {
+                    print_warn!("日志内容为空或无法读取");
+                }
             } else {
-                // 简单的日志高亮
-                for line in content.lines() {
-                    if line.contains("ERROR") || line.contains("Exception") || line.contains("Failed") {
-                        println!("{}", line.red());
-                    } else if line.contains("WARN") || line.contains("WARNING") {
-                        println!("{}", line.yellow());
-                    } else if line.contains("INFO") || line.contains("Started") || line.contains("Success") {
-                        println!("{}", line.green());
-                    } else if line.contains("DEBUG") {
-                        println!("{}", line.bright_black());
-                    } else {
-                        println!("{}", line);
-                    }
+                for line in &content {
+                    view.print(line);
                 }
             }
         }
@@ -81,8 +91,128 @@ pub fn show_jar_log(jar: &str, lines: u32) {
         }
     }
     
+    if follow {
+        follow_log(log_path, encoding, &filter, view);
+        return;
+    }
     println!("{}", "─".repeat(80).bright_blue());
-    println!("💡 提示: 使用 {} 实时查看日志", "tail -f".bright_cyan());
+    println!("💡 提示: 使用 {} 实时查看日志", format!("log {} -f", jar).bright_cyan());
+}
+
+// 持续输出日志文件新写入的内容，文件被轮转（变小或重建）后从新文件开头继续
+fn follow_log(path: &Path, encoding: &'static Encoding, filter: &dyn Fn(&str) -> bool, view: &JsonView) {
+    const POLL_INTERVAL: Duration = Duration::from_millis(500);
+    
+    println!("{}", format!("── 正在跟踪 {} (Ctrl+C 退出) ──", path.display()).bright_blue());
+    let mut position = fs::metadata(path).map_or(0, |m| m.len());
+    let mut pending: Vec<u8> = Vec::new();
+    loop {
+        let len = fs::metadata(path).map_or(0, |m| m.len());
+        if len < position {
+            position = 0;
+            pending.clear();
+        }
+        if len > position {
+            let read = fs::File::open(path).and_then(|mut file| {
+                file.seek(SeekFrom::Start(position))?;
+                file.take(len - position).read_to_end(&mut pending)
+            });
+            if let Ok(count) = read {
+                position += count as u64;
+            }
+            // 只输出完整的行，未写完的行等下一次读取
+            while let Some(end) = pending.iter().position(|b| *b == b'\n') {
+                let line: Vec<u8> = pending.drain(..=end).collect();
+                let line = decode_log_line(&line[..end], encoding);
+                let line = line.trim_end_matches('\r');
+                if filter(line) {
+                    view.print(line);
+                }
+            }
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+// 按配置的规则高亮日志行
+pub fn print_highlighted(line: &str) {
+    println!("{}", highlight::paint(line));
+}
+
+/// 解码一行日志：合法的 UTF-8 按 UTF-8 处理（Manager_Jar 自身写入的记录），否则按JAR配置的字符集解码
+pub fn decode_log_line(bytes: &[u8], encoding: &'static Encoding) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => encoding.decode_without_bom_handling(bytes).0.into_owned(),
+    }
+}
+
+// 查找日志管道前缀中的流标记，返回 (标记位置, 流, 标记长度)
+fn find_stream_marker(line: &str) -> Option<(usize, &'static str, usize)> {
+    // 时间戳格式可配置，只在行首附近查找标记
+    let head_end = line.char_indices().nth(64).map_or(line.len(), |(i, _)| i);
+    let head = &line[..head_end];
+    [("out", " [out] "), ("err", " [err] "), ("sys", " [sys] ")]
+        .into_iter()
+        .filter_map(|(tag, marker)| head.find(marker).map(|pos| (pos, tag, marker.len())))
+        .min_by_key(|(pos, _, _)| *pos)
+}
+
+/// 解析日志管道写入的前缀 (`<时间戳> [out|err|sys] <内容>`)，返回流标记和日志内容
+pub fn split_line_prefix(line: &str) -> (Option<&'static str>, &str) {
+    find_stream_marker(line).map_or((None, line), |(pos, tag, len)| (Some(tag), &line[pos + len..]))
+}
+
+/// 日志管道写入的时间戳，非日志管道写入的行返回 None
+pub fn line_timestamp(line: &str) -> Option<&str> {
+    find_stream_marker(line).map(|(pos, _, _)| &line[..pos])
+}
+
+/// 日志行的流标记 (out/err/sys)，非日志管道写入的行返回 None
+pub fn line_stream(line: &str) -> Option<&'static str> {
+    split_line_prefix(line).0
+}
+
+/// 从文件末尾向前读取最后 count 行满足条件的日志（按原顺序返回）
+pub fn read_last_lines(path: &Path, count: usize, encoding: &'static Encoding, filter: &dyn Fn(&str) -> bool) -> io::Result<Vec<String>> {
+    const CHUNK_SIZE: u64 = 64 * 1024;
+    
+    let mut file = fs::File::open(path)?;
+    let mut position = file.metadata()?.len();
+    let mut carry: Vec<u8> = Vec::new();
+    let mut lines = Vec::new();
+    let mut at_end = true;
+    
+    while position > 0 && lines.len() < count {
+        let start = position.saturating_sub(CHUNK_SIZE);
+        let mut chunk = vec![0u8; (position - start) as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(&mut chunk)?;
+        chunk.extend_from_slice(&carry);
+        position = start;
+        
+        let mut segments: Vec<&[u8]> = chunk.split(|b| *b == b'\n').collect();
+        // 块首的片段可能是不完整的行，留到下一轮拼接
+        let head = if position > 0 { segments.remove(0).to_vec() } else { Vec::new() };
+        if at_end && segments.last().is_some_and(|s| s.is_empty()) {
+            segments.pop();
+        }
+        at_end = false;
+        
+        for segment in segments.into_iter().rev() {
+            let line = decode_log_line(segment, encoding).trim_end_matches('\r').to_string();
+            if filter(&line) {
+                lines.push(line);
+                if lines.len() >= count {
+                    break;
+                }
+            }
+        }
+        carry = head;
+    }
+    
+    lines.reverse();
+    Ok(lines)
 }
 
 // 清理日志文件
@@ -94,10 +224,9 @@ pub fn clean_logs() -> Result<(), String> {
         let mut cleaned = 0;
         for entry in entries.flatten() {
             let path = entry.path();
-            if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("log") {
-                if fs::remove_file(&path).is_ok() {
-                    cleaned += 1;
-                }
+            let is_log = path.file_name().and_then(|n| n.to_str()).is_some_and(is_managed_log_file);
+            if path.is_file() && is_log && fs::remove_file(&path).is_ok() {
+                cleaned += 1;
             }
         }
         print_success!("已清理 {} 个日志文件", cleaned);
@@ -119,10 +248,13 @@ pub fn list_logs() {
         
         for entry in entries.flatten() {
             let path = entry.path();
-            if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("log") {
-                if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
+            if !path.is_file() {
+                continue;
+            }
+            if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
+                if is_managed_log_file(name) {
                     if let Ok(metadata) = fs::metadata(&path) {
-                        logs.push((name.to_string(), metadata.len()));
+                        logs.push((name.to_string(), metadata.len(), gzip_uncompressed_size(&path)));
                     }
                 }
             }
@@ -133,18 +265,25 @@ pub fn list_logs() {
         } else {
             print_success!("发现 {} 个日志文件:", logs.len());
             logs.sort_by(|a, b| a.0.cmp(&b.0));
-            for (name, size) in logs {
-                let size_str = if size > 1024 * 1024 {
-                    format!("{:.1} MB", size as f64 / (1024.0 * 1024.0))
-                } else if size > 1024 {
-                    format!("{:.1} KB", size as f64 / 1024.0)
-                } else {
-                    format!("{} B", size)
-                };
+            let mut total_size = 0;
+            let mut total_original = 0;
+            for (name, size, original) in logs {
+                total_size += size;
+                total_original += original.unwrap_or(size);
+                let size_str = format_log_size(size);
+                
+                // 压缩文件同时显示压缩前后的大小
+                if let Some(original) = original {
+                    let ratio = if original > 0 { size as f64 * 100.0 / original as f64 } else { 100.0 };
+                    println!("  {} ({} / 原始 {}, {:.0}%) {}",
+                             name.cyan(), size_str.bright_green(),
+                             format_log_size(original).bright_black(), ratio, "[已压缩]".bright_blue());
+                    continue;
+                }
                 
                 // 检查是否需要轮转
                 let full_path = format!("{}/{}", log_dir, name);
-                let needs_rotation = config.should_rotate_log(&full_path);
+                let needs_rotation = name.ends_with(".log") && config.should_rotate_log(&full_path);
                 let rotation_hint = if needs_rotation {
                     format!(" {}", "[需要轮转]".yellow())
                 } else {
@@ -154,12 +293,530 @@ pub fn list_logs() {
                 println!("  {} ({}){}", name.cyan(), size_str.bright_green(), rotation_hint);
             }
             
+            println!("\n  合计: {} (解压后 {})", format_log_size(total_size).bright_green(), format_log_size(total_original).bright_black());
+            print_quota_usage(&config, total_size);
             println!("\n💡 提示:");
             println!("  • 日志保存天数: {} 天", config.log.retention_days.to_string().yellow());
             println!("  • 文件大小限制: {} MB", config.log.max_file_size_mb.to_string().yellow());
             println!("  • 轮转功能: {}", if config.log.enable_rotation { "启用".green() } else { "禁用".red() });
+            println!("  • 压缩旧日志: {}", if config.log.enable_compression { "启用".green() } else { "禁用".red() });
         }
     } else {
         print_warn!("无法访问日志目录: {}", log_dir);
     }
 }
+
+// 显示日志目录和各JAR的磁盘配额使用情况
+fn print_quota_usage(config: &GlobalConfig, total_size: u64) {
+    let usage = |used: u64, quota_mb: u64| {
+        let quota = quota_mb.saturating_mul(1024 * 1024);
+        let percent = if quota > 0 { used as f64 * 100.0 / quota as f64 } else { 0.0 };
+        let text = format!("{} / {} MB ({:.0}%)", format_log_size(used), quota_mb, percent);
+        if percent >= 100.0 {
+            text.red().bold().to_string()
+        } else if percent >= 80.0 {
+            text.yellow().to_string()
+        } else {
+            text.green().to_string()
+        }
+    };
+
+    if config.log.quota_mb > 0 {
+        println!("  磁盘配额: {}", usage(total_size, config.log.quota_mb));
+    } else {
+        println!("  磁盘配额: {}", "不限制".bright_black());
+    }
+    for (jar, quota_mb) in config.jar_log_quotas() {
+        let used = config.jar_log_files_all(&jar).iter().map(|f| f.size).sum();
+        println!("    {} {}", jar.cyan(), usage(used, quota_mb));
+    }
+}
+
+// 在JAR的全部日志（含轮转和压缩的旧日志）中搜索
+pub fn grep_logs(jar: &str, pattern: &str, ignore_case: bool, view: &JsonView) -> Result<(), String> {
+    let regex = RegexBuilder::new(pattern)
+        .case_insensitive(ignore_case)
+        .build()
+        .map_err(|e| format!("无效的正则表达式: {}", e))?;
+    
+    let config = GlobalConfig::load();
+    let encoding = JarOptions::load(jar).encoding();
+    let log_files = config.get_jar_log_files(jar);
+    let mut files = Vec::new();
+    for log_file in &log_files {
+        files.extend(list_log_generations(log_file));
+        if Path::new(log_file).exists() {
+            files.push(PathBuf::from(log_file));
+        }
+    }
+    
+    if files.is_empty() {
+        print_warn!("日志文件不存在: {}", log_files.join(", "));
+        return Ok(());
+    }
+    
+    let mut total = 0;
+    for file in &files {
+        let name = file.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let reader = open_log_reader(file).map_err(|e| format!("读取日志失败 {}: {}", name, e))?;
+        for (index, line) in reader.split(b'\n').enumerate() {
+            let line = match line {
+                Ok(bytes) => decode_log_line(&bytes, encoding).trim_end_matches('\r').to_string(),
+                Err(e) => {
+                    print_warn!("读取 {} 时出错: {}", name, e);
+                    break;
+                }
+            };
+            if regex.is_match(&line) && view.matches(&line) {
+                total += 1;
+                // JSON 日志显示格式化后的内容，匹配的部分不在其中时不高亮
+                let display = view.render_plain(&line).unwrap_or(line);
+                let highlighted = regex.replace_all(&display, |caps: &regex::Captures| caps[0].red().bold().to_string());
+                println!("{}:{}: {}", name.cyan(), (index + 1).to_string().bright_black(), highlighted);
+            }
+        }
+    }
+    
+    println!("{}", "─".repeat(80).bright_blue());
+    if total == 0 {
+        print_warn!("在 {} 个日志文件中未找到匹配: {}", files.len(), pattern);
+    } else {
+        print_success!("在 {} 个日志文件中找到 {} 处匹配", files.len(), total.to_string().bright_green());
+    }
+    Ok(())
+}
+
+// ============= 运行会话 =============
+
+/// 会话横幅的起始标记，每次启动JAR时写入日志
+pub const SESSION_MARKER: &str = "===== Manager_Jar 会话开始 =====";
+
+/// 直接启动时在进程创建后补写的 PID 行
+const SESSION_PID_PREFIX: &str = "===== Manager_Jar 会话 PID: ";
+
+/// 生成会话横幅：时间、PID、java 版本和完整命令行 (密码、令牌等参数值已脱敏)
+/// 直接启动时横幅在进程创建之前写入，此时还没有 PID，进程创建后再追加 session_pid_line
+pub fn session_banner(pid: Option<u32>, java_version: &str, command: &[String]) -> Vec<String> {
+    let command_line = command.iter()
+        .map(|arg| if arg.contains(' ') { format!("\"{}\"", arg) } else { arg.clone() })
+        .collect::<Vec<_>>()
+        .join(" ");
+    let mut banner = vec![
+        SESSION_MARKER.to_string(),
+        format!("  时间: {}", chrono::Local::now().format("%Y-%m-%d %H:%M:%S")),
+    ];
+    banner.extend(pid.map(|pid| format!("  PID: {}", pid)));
+    banner.extend([
+        format!("  Java: {}", java_version),
+        format!("  命令: {}", crate::core::bundle::redact_secrets(&command_line)),
+        "=".repeat(32),
+    ]);
+    banner
+}
+
+/// 直接启动时补写的会话 PID 行，可能出现在进程的最初几行输出之后
+pub fn session_pid_line(pid: u32) -> String {
+    format!("{}{} =====", SESSION_PID_PREFIX, pid)
+}
+
+/// 会话是否为正在运行的进程：按横幅中的 PID 判断，没有 PID 的最后一个会话视为当前运行的会话
+fn is_running_session(sessions: &[SessionInfo], session: &SessionInfo, running_pid: Option<u32>) -> bool {
+    match (session.pid, running_pid) {
+        (Some(pid), Some(running)) => pid == running,
+        (None, Some(_)) => session.index == sessions.len(),
+        _ => false,
+    }
+}
+
+/// 当前会话 (最后一个会话横幅之后) 的最后若干行，已去掉日志管道的行前缀
+pub fn current_session_lines(log_files: &[String], count: usize, encoding: &'static Encoding) -> Vec<String> {
+    let mut lines = Vec::new();
+    for log_file in log_files {
+        let tail = read_last_lines(Path::new(log_file), count, encoding, &|_| true).unwrap_or_default();
+        let start = tail.iter().rposition(|line| strip_line_prefix(line) == SESSION_MARKER).map(|i| i + 1).unwrap_or(0);
+        lines.extend(tail[start..].iter().map(|line| strip_line_prefix(line).to_string()));
+    }
+    lines
+}
+
+/// 向日志文件追加若干行
+pub fn append_lines(path: &str, lines: &[String]) -> io::Result<()> {
+    use std::io::Write;
+    let mut file = fs::OpenOptions::new().create(true).append(true).open(path)?;
+    let mut content = lines.join("\n");
+    content.push('\n');
+    file.write_all(content.as_bytes())
+}
+
+/// 去掉日志管道写入的 `<时间戳> [流] ` 前缀，返回日志内容本身
+pub fn strip_line_prefix(line: &str) -> &str {
+    split_line_prefix(line).1
+}
+
+/// 日志中的一次运行会话
+pub struct SessionInfo {
+    /// 会话编号（从 1 开始，越大越新）
+    pub index: usize,
+    /// 启动时间
+    pub started: String,
+    /// 进程ID
+    pub pid: Option<u32>,
+    /// 会话开始所在的日志文件
+    pub file: String,
+    /// 会话日志行数
+    pub lines: usize,
+}
+
+/// 会话选择方式
+pub enum SessionSelector {
+    /// 指定编号
+    Index(usize),
+    /// 上一次运行（跳过仍在运行的当前会话）
+    Previous,
+}
+
+/// 获取某个日志（含轮转和压缩的旧日志）按时间排序的全部文件
+pub fn log_files_in_order(log_file: &str) -> Vec<PathBuf> {
+    let mut files = list_log_generations(log_file);
+    if Path::new(log_file).exists() {
+        files.push(PathBuf::from(log_file));
+    }
+    files
+}
+
+/// 依次读取所有文件的每一行
+pub fn for_each_log_line(files: &[PathBuf], encoding: &'static Encoding, mut visit: impl FnMut(&Path, &str)) -> io::Result<()> {
+    for file in files {
+        let reader = open_log_reader(file)?;
+        for line in reader.split(b'\n') {
+            let line = line?;
+            let line = decode_log_line(&line, encoding);
+            visit(file, line.trim_end_matches('\r'));
+        }
+    }
+    Ok(())
+}
+
+/// 扫描日志中的所有会话
+pub fn scan_sessions(files: &[PathBuf]) -> io::Result<Vec<SessionInfo>> {
+    let mut sessions: Vec<SessionInfo> = Vec::new();
+    let mut header_lines = 0;
+    // 会话横幅由 Manager_Jar 以 UTF-8 写入，不需要按JAR的字符集解码
+    for_each_log_line(files, encoding_rs::UTF_8, |file, line| {
+        let body = strip_line_prefix(line);
+        if body == SESSION_MARKER {
+            sessions.push(SessionInfo {
+                index: sessions.len() + 1,
+                started: String::new(),
+                pid: None,
+                file: file.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+                lines: 0,
+            });
+            header_lines = 5;
+            return;
+        }
+        if let Some(session) = sessions.last_mut() {
+            if let Some(value) = body.strip_prefix(SESSION_PID_PREFIX) {
+                if session.pid.is_none() {
+                    session.pid = value.trim_end_matches('=').trim().parse().ok();
+                }
+                return;
+            }
+            session.lines += 1;
+            // 横幅紧随标记之后，只在其中解析字段
+            if header_lines > 0 {
+                header_lines -= 1;
+                if let Some(value) = body.strip_prefix("  时间: ") {
+                    session.started = value.to_string();
+                } else if let Some(value) = body.strip_prefix("  PID: ") {
+                    session.pid = value.trim().parse().ok();
+                }
+            }
+        }
+    })?;
+    Ok(sessions)
+}
+
+// 列出JAR日志中的所有会话
+pub fn list_sessions(jar: &str) -> Result<(), String> {
+    let config = GlobalConfig::load();
+    let log_file = config.get_jar_log_files(jar).remove(0);
+    let files = log_files_in_order(&log_file);
+    if files.is_empty() {
+        print_warn!("日志文件不存在: {}", log_file);
+        return Ok(());
+    }
+    
+    let sessions = scan_sessions(&files).map_err(|e| format!("读取日志失败: {}", e))?;
+    if sessions.is_empty() {
+        print_warn!("日志中没有会话记录（旧版本启动的日志不含会话横幅）");
+        return Ok(());
+    }
+    
+    let running_pid = crate::core::process::get_pid(jar);
+    print_success!("{} 的运行会话 ({} 个):", jar.bright_cyan(), sessions.len());
+    for session in &sessions {
+        let pid = session.pid.map_or("?".to_string(), |pid| pid.to_string());
+        let current = if is_running_session(&sessions, session, running_pid) {
+            format!(" {}", "[运行中]".green())
+        } else {
+            String::new()
+        };
+        println!("  #{:<4} {}  PID: {:<8} {} 行  ({}){}",
+                 session.index.to_string().bright_green(), session.started.yellow(), pid,
+                 session.lines, session.file.bright_black(), current);
+    }
+    println!("\n💡 使用 {} 或 {} 查看指定会话", 
+             format!("log {} --session N", jar).cyan(), format!("log {} --previous", jar).cyan());
+    Ok(())
+}
+
+// 显示指定会话的最后若干行日志
+pub fn show_session_log(jar: &str, selector: SessionSelector, lines: u32, stderr_only: bool, view: &JsonView) -> Result<(), String> {
+    let config = GlobalConfig::load();
+    let split = JarOptions::load(jar).log_mode == LogMode::Split;
+    let log_file = match (split, stderr_only) {
+        (true, true) => config.get_stream_log_path(jar, "err"),
+        (true, false) => config.get_stream_log_path(jar, "out"),
+        (false, _) => config.get_log_file_path(jar),
+    };
+    let filter_stderr = stderr_only && !split;
+    
+    let files = log_files_in_order(&log_file);
+    let sessions = scan_sessions(&files).map_err(|e| format!("读取日志失败: {}", e))?;
+    if sessions.is_empty() {
+        return Err("日志中没有会话记录".to_string());
+    }
+    
+    let target = match selector {
+        SessionSelector::Index(index) => index,
+        SessionSelector::Previous => {
+            let running_pid = crate::core::process::get_pid(jar);
+            let latest = sessions.len();
+            if is_running_session(&sessions, &sessions[latest - 1], running_pid) { latest - 1 } else { latest }
+        }
+    };
+    let session = match sessions.get(target.wrapping_sub(1)) {
+        Some(session) => session,
+        None => return Err(format!("会话不存在: #{} (共 {} 个会话)", target, sessions.len())),
+    };
+    
+    // 只保留目标会话的最后若干行
+    let mut current = 0;
+    let mut tail: std::collections::VecDeque<String> = std::collections::VecDeque::new();
+    let encoding = JarOptions::load(jar).encoding();
+    for_each_log_line(&files, encoding, |_, line| {
+        if strip_line_prefix(line) == SESSION_MARKER {
+            current += 1;
+        }
+        if current == target && (!filter_stderr || line_stream(line) == Some("err")) && view.matches(line) {
+            if tail.len() == lines as usize {
+                tail.pop_front();
+            }
+            tail.push_back(line.to_string());
+        }
+    }).map_err(|e| format!("读取日志失败: {}", e))?;
+    
+    let pid = session.pid.map_or("?".to_string(), |pid| pid.to_string());
+    println!("📄 会话 #{} | 启动于 {} | PID: {} | 共 {} 行",
+             session.index.to_string().bright_green(), session.started.yellow(), pid, session.lines);
+    println!("📖 显示该会话最后 {} 行日志:", tail.len().to_string().bright_green());
+    println!("{}", "─".repeat(80).bright_blue());
+    for line in &tail {
+        view.print(line);
+    }
+    println!("{}", "─".repeat(80).bright_blue());
+    Ok(())
+}
+
+// ============= 日志代际与压缩 =============
+
+/// 判断文件名是否是 Manager_Jar 管理的日志文件（活动日志、轮转的旧日志或其压缩文件）
+pub fn is_managed_log_file(name: &str) -> bool {
+    if name.ends_with(".tmp") {
+        return false;
+    }
+    name.ends_with(".log") || name.contains(".log.")
+}
+
+/// 判断是否是 GC 日志 (<jar>.gc.log 及 JVM 轮转出的 .0 ~ .N、.current)
+/// GC 日志由 JVM 循环覆盖写入，不参与 Manager_Jar 的压缩、过期清理、配额删除和投递
+pub fn is_gc_log_file(name: &str) -> bool {
+    name.contains(".gc.log")
+}
+
+/// 判断是否是正在写入的活动日志：*.log，以及 Java 8 GC 日志轮转时的 *.current
+pub fn is_active_log_name(name: &str) -> bool {
+    name.ends_with(".log") || name.ends_with(".current")
+}
+
+/// 列出某个活动日志的所有轮转代（按时间从旧到新排序，含 .gz）
+pub fn list_log_generations(log_path: &str) -> Vec<PathBuf> {
+    let path = Path::new(log_path);
+    let (dir, base) = match (path.parent(), path.file_name().and_then(|n| n.to_str())) {
+        (Some(dir), Some(base)) => (dir, base),
+        _ => return Vec::new(),
+    };
+    let prefix = format!("{}.", base);
+    
+    let mut generations = Vec::new();
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with(&prefix) && !name.ends_with(".tmp") && entry.path().is_file() {
+                generations.push(entry.path());
+            }
+        }
+    }
+    // 轮转后缀为时间戳，按文件名排序即按时间排序
+    generations.sort();
+    generations
+}
+
+/// 生成下一个轮转代的文件名: <日志>.<YYYYmmdd-HHMMSS>
+pub fn next_generation_path(log_path: &str) -> PathBuf {
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S").to_string();
+    let mut candidate = PathBuf::from(format!("{}.{}", log_path, stamp));
+    let mut seq = 1;
+    while candidate.exists() || PathBuf::from(format!("{}.gz", candidate.display())).exists() {
+        candidate = PathBuf::from(format!("{}.{}-{}", log_path, stamp, seq));
+        seq += 1;
+    }
+    candidate
+}
+
+/// 将日志文件压缩为 <文件>.gz，成功后删除原文件
+pub fn compress_log_file(path: &Path) -> Result<PathBuf, String> {
+    let gz_path = PathBuf::from(format!("{}.gz", path.display()));
+    let tmp_path = PathBuf::from(format!("{}.gz.tmp", path.display()));
+    if gz_path.exists() {
+        return Err(format!("压缩文件已存在，保留原日志: {}", gz_path.display()));
+    }
+    
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+    let mut source = fs::File::open(path).map_err(|e| format!("打开日志失败: {}", e))?;
+    let target = fs::File::create(&tmp_path).map_err(|e| format!("创建压缩文件失败: {}", e))?;
+    
+    let mut encoder = GzEncoder::new(target, Compression::default());
+    let result = io::copy(&mut source, &mut encoder).and_then(|_| encoder.finish());
+    let target = match result {
+        Ok(file) => file,
+        Err(e) => {
+            fs::remove_file(&tmp_path).ok();
+            return Err(format!("压缩日志失败: {}", e));
+        }
+    };
+    
+    // 保留原始修改时间，使保留天数按日志内容的时间计算
+    if let Some(modified) = modified {
+        target.set_modified(modified).ok();
+    }
+    drop(target);
+    
+    fs::rename(&tmp_path, &gz_path).map_err(|e| format!("保存压缩文件失败: {}", e))?;
+    fs::remove_file(path).map_err(|e| format!("删除已压缩的日志失败: {}", e))?;
+    Ok(gz_path)
+}
+
+/// 在后台线程中压缩日志文件
+pub fn compress_in_background(path: PathBuf) {
+    let handle = std::thread::spawn(move || {
+        if let Err(e) = compress_log_file(&path) {
+            print_warn!("后台压缩 {} 失败: {}", path.display(), e);
+        }
+    });
+    if let Ok(mut handles) = BACKGROUND_COMPRESSIONS.lock() {
+        handles.push(handle);
+    }
+}
+
+/// 离开作用域时等待后台压缩完成，覆盖命令处理中的所有提前返回
+pub struct BackgroundCompressionGuard;
+
+impl Drop for BackgroundCompressionGuard {
+    fn drop(&mut self) {
+        wait_background_compressions();
+    }
+}
+
+/// 等待所有后台压缩任务完成
+pub fn wait_background_compressions() {
+    let handles = match BACKGROUND_COMPRESSIONS.lock() {
+        Ok(mut handles) => std::mem::take(&mut *handles),
+        Err(_) => return,
+    };
+    for handle in handles {
+        handle.join().ok();
+    }
+}
+
+/// 判断轮转出的旧日志是否已经静默足够久，可以安全压缩
+pub fn is_generation_quiet(path: &Path) -> bool {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age >= Duration::from_secs(COMPRESS_QUIET_SECS))
+}
+
+/// 判断是否为 gzip 压缩的日志
+pub fn is_gzip_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "gz")
+}
+
+/// 打开日志文件读取，.gz 文件自动解压
+pub fn open_log_reader(path: &Path) -> io::Result<Box<dyn BufRead>> {
+    let file = fs::File::open(path)?;
+    if is_gzip_file(path) {
+        Ok(Box::new(BufReader::new(MultiGzDecoder::new(file))))
+    } else {
+        Ok(Box::new(BufReader::new(file)))
+    }
+}
+
+/// 读取 gzip 文件尾部记录的原始大小（非 gzip 文件返回 None）
+pub fn gzip_uncompressed_size(path: &Path) -> Option<u64> {
+    if !is_gzip_file(path) {
+        return None;
+    }
+    let mut file = fs::File::open(path).ok()?;
+    file.seek(SeekFrom::End(-4)).ok()?;
+    let mut trailer = [0u8; 4];
+    file.read_exact(&mut trailer).ok()?;
+    Some(u32::from_le_bytes(trailer) as u64)
+}
+
+// 格式化日志文件大小
+fn format_log_size(size: u64) -> String {
+    if size > 1024 * 1024 {
+        format!("{:.1} MB", size as f64 / (1024.0 * 1024.0))
+    } else if size > 1024 {
+        format!("{:.1} KB", size as f64 / 1024.0)
+    } else {
+        format!("{} B", size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scans_sessions_with_pid_written_after_start() {
+        let path = std::env::temp_dir().join(format!("manager_jar_sessions_test_{}.log", std::process::id()));
+        let mut lines = session_banner(Some(100), "17", &["java".to_string(), "-jar".to_string(), "app.jar".to_string()]);
+        lines.push("first run".to_string());
+        lines.extend(session_banner(None, "17", &["java".to_string()]));
+        lines.push("early output".to_string());
+        lines.push(session_pid_line(200));
+        lines.push("later output".to_string());
+        fs::write(&path, lines.join("\n") + "\n").unwrap();
+
+        let sessions = scan_sessions(std::slice::from_ref(&path)).unwrap();
+        assert_eq!(sessions.iter().map(|s| s.pid).collect::<Vec<_>>(), [Some(100), Some(200)]);
+        assert_eq!(sessions[1].lines, 6);
+        assert!(is_running_session(&sessions, &sessions[1], Some(200)));
+        assert!(!is_running_session(&sessions, &sessions[0], Some(200)));
+        let _ = fs::remove_file(&path);
+    }
+}
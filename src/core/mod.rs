@@ -3,6 +3,24 @@
 pub mod process;
 pub mod config;
 pub mod logging;
+pub mod pipeline;
+pub mod exceptions;
+pub mod jsonlog;
+pub mod highlight;
+pub mod syslog;
+pub mod bundle;
+pub mod threads;
+pub mod heap;
+pub mod jfr;
+pub mod gc;
+pub mod jdk;
+pub mod inspect;
+pub mod preflight;
+pub mod runcopy;
+pub mod deploy;
+pub mod registry;
+pub mod instance;
+pub mod ports;
 
 // 重新导出常用功能
 // Individual functions can be imported as needed
@@ -0,0 +1,318 @@
+// pipeline.rs - 日志管道模块
+// 由 Manager_Jar 接管子进程的 stdout/stderr，为每行添加时间戳和流标记，并实时按大小轮转
+use std::fmt::Write as FmtWrite;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::core::config::{GlobalConfig, JarOptions, LogMode};
+use crate::core::logging;
+use crate::core::process;
+use crate::core::syslog::{SyslogSink, SyslogTarget};
+
+#[cfg(target_os = "windows")]
+use std::os::windows::process::CommandExt;
+
+/// 以日志管道模式运行的命令行标志
+pub const PIPELINE_FLAG: &str = "--log-pipeline";
+
+/// 进程退出后等待 syslog 缓存发送完的最长时间
+const SYSLOG_FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 日志文件被外部移走（如守护进程轮转）后，多久检查一次并重新打开
+const REOPEN_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+// Windows创建标志常量
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+#[cfg(target_os = "windows")]
+const DETACHED_PROCESS: u32 = 0x00000008;
+
+/// 日志行来源
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Stream {
+    /// 子进程标准输出
+    Out,
+    /// 子进程标准错误
+    Err,
+    /// Manager_Jar 自身写入的记录
+    Sys,
+}
+
+impl Stream {
+    pub fn tag(&self) -> &'static str {
+        match self {
+            Stream::Out => "out",
+            Stream::Err => "err",
+            Stream::Sys => "sys",
+        }
+    }
+}
+
+/// 带时间戳前缀和实时轮转的日志写入器
+pub struct LogWriter {
+    path: String,
+    file: File,
+    size: u64,
+    max_size: u64,
+    compress: bool,
+    timestamp_format: String,
+    last_reopen_check: Instant,
+}
+
+impl LogWriter {
+    /// 以追加方式打开日志文件
+    pub fn open(path: &str, config: &GlobalConfig) -> io::Result<Self> {
+        if let Some(parent) = Path::new(path).parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let size = file.metadata()?.len();
+        let max_size = if config.log.enable_rotation {
+            config.log.max_file_size_mb as u64 * 1024 * 1024
+        } else {
+            0
+        };
+
+        Ok(Self {
+            path: path.to_string(),
+            file,
+            size,
+            max_size,
+            compress: config.log.enable_compression,
+            timestamp_format: config.log.timestamp_format.clone(),
+            last_reopen_check: Instant::now(),
+        })
+    }
+
+    /// 写入一行日志: `<时间戳> [<流>] <内容>`
+    pub fn write_line(&mut self, stream: Stream, line: &[u8]) -> io::Result<()> {
+        self.reopen_if_moved()?;
+
+        let mut record = format!("{} [{}] ", format_timestamp(&self.timestamp_format), stream.tag()).into_bytes();
+        record.extend_from_slice(line);
+        record.push(b'\n');
+
+        self.file.write_all(&record)?;
+        self.size += record.len() as u64;
+
+        if self.max_size > 0 && self.size >= self.max_size {
+            self.rotate()?;
+        }
+        Ok(())
+    }
+
+    /// 轮转当前日志：关闭句柄后再移走，旧文件可立即压缩
+    fn rotate(&mut self) -> io::Result<()> {
+        let rotated = logging::next_generation_path(&self.path);
+        fs::rename(&self.path, &rotated)?;
+        self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        self.size = 0;
+
+        if self.compress {
+            logging::compress_in_background(rotated);
+        }
+        Ok(())
+    }
+
+    /// 日志文件被外部轮转或删除后重新创建
+    fn reopen_if_moved(&mut self) -> io::Result<()> {
+        if self.last_reopen_check.elapsed() < REOPEN_CHECK_INTERVAL {
+            return Ok(());
+        }
+        self.last_reopen_check = Instant::now();
+
+        if !Path::new(&self.path).exists() {
+            self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+            self.size = 0;
+        }
+        Ok(())
+    }
+}
+
+/// 按配置格式生成时间戳，格式串无效时退回默认格式
+pub fn format_timestamp(format: &str) -> String {
+    let now = chrono::Local::now();
+    let mut stamp = String::new();
+    if write!(stamp, "{}", now.format(format)).is_err() {
+        stamp = now.format("%Y-%m-%d %H:%M:%S").to_string();
+    }
+    stamp
+}
+
+/// 在后台启动日志管道进程，由其启动实际命令并接管输出
+pub fn spawn_pipeline(jar: &str, command: &[String], env: &[(String, String)]) -> io::Result<Child> {
+    let current_exe = std::env::current_exe()?;
+
+    let mut cmd = if cfg!(target_os = "windows") {
+        Command::new(&current_exe)
+    } else {
+        // Linux: 使用 nohup 确保终端关闭后管道进程继续运行
+        let mut cmd = Command::new("nohup");
+        cmd.arg(&current_exe);
+        cmd
+    };
+    cmd.arg(PIPELINE_FLAG).arg(jar).arg("--").args(command);
+    // 环境变量由管道进程传给 java
+    cmd.envs(env.iter().map(|(key, value)| (key, value)));
+    cmd.stdin(Stdio::null());
+    cmd.stdout(Stdio::null());
+    cmd.stderr(Stdio::null());
+
+    // 设置创建标志：无窗口 + 分离进程
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(CREATE_NO_WINDOW | DETACHED_PROCESS);
+
+    cmd.spawn()
+}
+
+/// 日志管道模式入口: `--log-pipeline <jar> -- <程序> [参数...]`
+pub fn run_pipeline_mode(args: &[String]) {
+    let (jar, command) = match args {
+        [jar, separator, _, ..] if separator == "--" => (jar.as_str(), &args[2..]),
+        _ => {
+            eprintln!("用法: {} <jar> -- <程序> [参数...]", PIPELINE_FLAG);
+            std::process::exit(2);
+        }
+    };
+    let program = &command[0];
+
+    let config = GlobalConfig::load();
+    let open_writer = |log_file: String| match LogWriter::open(&log_file, &config) {
+        Ok(writer) => Arc::new(Mutex::new(writer)),
+        Err(e) => {
+            eprintln!("打开日志文件失败 {}: {}", log_file, e);
+            std::process::exit(1);
+        }
+    };
+    
+    // 合并模式下两个流共用同一个写入器
+    let (out_writer, err_writer) = match JarOptions::load(jar).log_mode {
+        LogMode::Combined => {
+            let writer = open_writer(config.get_log_file_path(jar));
+            (Arc::clone(&writer), writer)
+        },
+        LogMode::Split => (
+            open_writer(config.get_stream_log_path(jar, "out")),
+            open_writer(config.get_stream_log_path(jar, "err")),
+        ),
+    };
+    let writers = [out_writer, err_writer];
+
+    // syslog 转发（可选），配置错误只记录到日志，不影响启动
+    let syslog = match SyslogTarget::for_jar(jar, &config).map(|target| target.and_then(|t| SyslogSink::start(t, jar, &config))) {
+        Some(Ok(sink)) => Some(sink),
+        Some(Err(e)) => {
+            write_sys_line(&writers, None, &format!("syslog 转发未启用: {}", e));
+            None
+        },
+        None => None,
+    };
+    let syslog = syslog.as_ref();
+
+    let java_version = process::java_version(program).unwrap_or_else(|| "未知".to_string());
+    let mut cmd = Command::new(program);
+    cmd.args(&command[1..]);
+    cmd.stdin(Stdio::null());
+    cmd.stdout(Stdio::piped());
+    cmd.stderr(Stdio::piped());
+
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(CREATE_NO_WINDOW);
+
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            write_sys_line(&writers, syslog, &format!("启动失败: {} ({})", e, program));
+            if let Some(sink) = syslog {
+                sink.close(SYSLOG_FLUSH_TIMEOUT);
+            }
+            std::process::exit(1);
+        }
+    };
+    process::save_pid(jar, child.id());
+    if let Some(sink) = syslog {
+        sink.set_proc_id(child.id());
+    }
+
+    // 先写会话横幅再开始转发输出，保证横幅位于本次运行的所有输出之前
+    for line in logging::session_banner(Some(child.id()), &java_version, command) {
+        write_sys_line(&writers, syslog, &line);
+    }
+
+    let encoding = JarOptions::load(jar).encoding();
+    let mut pumps = Vec::new();
+    if let Some(stdout) = child.stdout.take() {
+        pumps.push(spawn_pump(stdout, Stream::Out, Arc::clone(&writers[0]), syslog.cloned(), encoding));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        pumps.push(spawn_pump(stderr, Stream::Err, Arc::clone(&writers[1]), syslog.cloned(), encoding));
+    }
+
+    let status = child.wait();
+    for pump in pumps {
+        pump.join().ok();
+    }
+
+    match status {
+        Ok(status) => match status.code() {
+            Some(code) => write_sys_line(&writers, syslog, &format!("进程已退出 (退出码: {})", code)),
+            None => write_sys_line(&writers, syslog, "进程已被信号终止"),
+        },
+        Err(e) => write_sys_line(&writers, syslog, &format!("等待进程退出失败: {}", e)),
+    }
+    if let Some(sink) = syslog {
+        let unsent = sink.close(SYSLOG_FLUSH_TIMEOUT);
+        if unsent > 0 {
+            write_sys_line(&writers, None, &format!("syslog 接收端不可用，{} 行日志未能转发", unsent));
+        }
+    }
+    logging::wait_background_compressions();
+}
+
+/// 逐行读取子进程输出并写入日志，启用时同时转发到 syslog
+fn spawn_pump<R: Read + Send + 'static>(
+    source: R,
+    stream: Stream,
+    writer: Arc<Mutex<LogWriter>>,
+    syslog: Option<Arc<SyslogSink>>,
+    encoding: &'static encoding_rs::Encoding,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut reader = BufReader::new(source);
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            match reader.read_until(b'\n', &mut line) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {
+                    while matches!(line.last(), Some(b'\n') | Some(b'\r')) {
+                        line.pop();
+                    }
+                    if let Ok(mut writer) = writer.lock() {
+                        writer.write_line(stream, &line).ok();
+                    }
+                    if let Some(sink) = &syslog {
+                        sink.send(stream, &logging::decode_log_line(&line, encoding));
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// 写入 Manager_Jar 自身的记录，分流模式下两个日志都会写入
+fn write_sys_line(writers: &[Arc<Mutex<LogWriter>>; 2], syslog: Option<&Arc<SyslogSink>>, message: &str) {
+    if let Some(sink) = syslog {
+        sink.send(Stream::Sys, message);
+    }
+    let shared = Arc::ptr_eq(&writers[0], &writers[1]);
+    for writer in writers.iter().take(if shared { 1 } else { 2 }) {
+        if let Ok(mut writer) = writer.lock() {
+            writer.write_line(Stream::Sys, message.as_bytes()).ok();
+        }
+    }
+}
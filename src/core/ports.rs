@@ -0,0 +1,330 @@
+// ports.rs - 命名端口分配和监听端口查询
+// JAR 选项 ports 声明命名端口 (如 http,management=9090)，启动时分配空闲端口并记录在运行记录中
+// 端口通过参数中的 ${PORT_<名称>} 和环境变量 PORT_<名称> 传给应用；http/management 未被引用时自动添加 Spring Boot 参数
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::net::{Ipv4Addr, Ipv6Addr, TcpListener};
+use colored::Colorize;
+use regex::Regex;
+use crate::core::config::{GlobalConfig, JarOptions};
+use crate::core::runcopy::RunRecord;
+use crate::core::{instance, process};
+
+/// 未指定端口时自动分配
+const AUTO: &str = "auto";
+/// 未在参数中引用时自动注入的 Spring Boot 参数
+const SPRING_PROPERTIES: &[(&str, &str)] = &[("http", "server.port"), ("management", "management.server.port")];
+
+/// 解析 ports 选项: "http,management=9090"，未给端口的为 auto
+pub fn parse_spec(spec: &str) -> Result<BTreeMap<String, String>, String> {
+    let name_pattern = Regex::new(r"^[A-Za-z][A-Za-z0-9_]*$").unwrap();
+    let mut ports = BTreeMap::new();
+    for item in spec.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+        let (name, value) = item.split_once('=').map(|(n, v)| (n.trim(), v.trim())).unwrap_or((item, AUTO));
+        if !name_pattern.is_match(name) {
+            return Err(format!("无效的端口名: {} (字母开头，只能包含字母、数字、_)", name));
+        }
+        if value != AUTO && value.parse::<u16>().ok().filter(|port| *port > 0).is_none() {
+            return Err(format!("无效的端口: {}={} (1-65535 或 auto)", name, value));
+        }
+        ports.insert(name.to_string(), value.to_string());
+    }
+    Ok(ports)
+}
+
+/// 其他运行中的JAR记录的端口
+fn ports_of_others(jar: &str) -> BTreeSet<u16> {
+    process::get_running_jars().into_iter()
+        .filter(|(id, _)| id != jar)
+        .filter_map(|(id, _)| RunRecord::load(&id))
+        .flat_map(|record| record.ports.into_values())
+        .collect()
+}
+
+fn is_free(port: u16) -> bool {
+    TcpListener::bind(("0.0.0.0", port)).is_ok()
+}
+
+/// 固定端口加上实例的端口偏移，超出范围时返回 None
+fn with_offset(value: &str, offset: u32) -> Option<u16> {
+    value.parse::<u16>().ok().and_then(|port| u16::try_from(port as u32 + offset).ok())
+}
+
+/// JAR选项 ports 中的固定端口 (已加上实例的端口偏移)，用于启动前检查
+pub fn fixed_ports(jar: &str) -> Vec<u16> {
+    let offset = instance::port_offset(jar);
+    JarOptions::load(jar).ports.values()
+        .filter(|value| *value != AUTO)
+        .filter_map(|value| with_offset(value, offset))
+        .collect()
+}
+
+/// 为JAR分配命名端口：固定端口加上实例的端口偏移；auto 优先沿用上次的端口，否则从配置的范围中选择空闲端口
+pub fn allocate(jar: &str) -> Result<BTreeMap<String, u16>, String> {
+    let declared = JarOptions::load(jar).ports;
+    let mut ports = BTreeMap::new();
+    if declared.is_empty() {
+        return Ok(ports);
+    }
+    let range = GlobalConfig::load().ports;
+    if range.range_start == 0 || range.range_start > range.range_end {
+        return Err(format!("无效的自动端口范围: {}-{}", range.range_start, range.range_end));
+    }
+    let previous = RunRecord::load(jar).map(|record| record.ports).unwrap_or_default();
+    let mut taken = ports_of_others(jar);
+
+    let offset = instance::port_offset(jar);
+    for (name, value) in declared.iter().filter(|(_, value)| *value != AUTO) {
+        let port = with_offset(value, offset)
+            .ok_or_else(|| format!("端口 {}={} 加上实例偏移 {} 后超出范围", name, value, offset))?;
+        taken.insert(port);
+        ports.insert(name.clone(), port);
+    }
+    for name in declared.iter().filter(|(_, value)| *value == AUTO).map(|(name, _)| name) {
+        let usable = |port: &u16| (range.range_start..=range.range_end).contains(port) && !taken.contains(port) && is_free(*port);
+        let port = previous.get(name).copied().filter(usable)
+            .or_else(|| (range.range_start..=range.range_end).find(usable))
+            .ok_or_else(|| format!("端口范围 {}-{} 中没有可分配给 {} 的空闲端口", range.range_start, range.range_end, name))?;
+        taken.insert(port);
+        ports.insert(name.clone(), port);
+    }
+    Ok(ports)
+}
+
+/// 把端口代入启动参数；参数中没有引用任何端口时，为 http/management 添加对应的 Spring Boot 参数
+pub fn apply_args(ports: &BTreeMap<String, u16>, args: &[String]) -> Vec<String> {
+    let referenced = args.iter().any(|arg| arg.contains("${PORT_"));
+    let mut result: Vec<String> = args.iter()
+        .map(|arg| ports.iter().fold(arg.clone(), |arg, (name, port)| arg.replace(&format!("${{PORT_{}}}", name), &port.to_string())))
+        .collect();
+    if !referenced {
+        for (name, property) in SPRING_PROPERTIES {
+            let configured = result.iter().any(|arg| arg.contains(&format!("{}=", property)));
+            if let (Some(port), false) = (ports.get(*name), configured) {
+                result.push(format!("--{}={}", property, port));
+            }
+        }
+    }
+    result
+}
+
+/// 传给应用的环境变量 PORT_<名称>
+pub fn env_vars(ports: &BTreeMap<String, u16>) -> Vec<(String, String)> {
+    ports.iter().map(|(name, port)| (format!("PORT_{}", name), port.to_string())).collect()
+}
+
+pub fn format_ports(ports: &BTreeMap<String, u16>) -> String {
+    ports.iter().map(|(name, port)| format!("{}={}", name, port)).collect::<Vec<_>>().join(", ")
+}
+
+/// 一个监听中的 TCP 端口
+struct Listener {
+    address: String,
+    port: u16,
+    inode: u64,
+}
+
+/// /proc/net/tcp 中的十六进制地址 (按主机字节序存放的 32 位字)
+fn parse_address(hex: &str) -> Option<String> {
+    let words: Vec<u32> = (0..hex.len() / 8).map(|i| u32::from_str_radix(&hex[i * 8..i * 8 + 8], 16)).collect::<Result<_, _>>().ok()?;
+    let bytes: Vec<u8> = words.iter().flat_map(|word| word.to_ne_bytes()).collect();
+    let address = match bytes.len() {
+        4 => Ipv4Addr::new(bytes[0], bytes[1], bytes[2], bytes[3]).to_string(),
+        16 => {
+            let ip = Ipv6Addr::from(<[u8; 16]>::try_from(bytes.as_slice()).ok()?);
+            ip.to_ipv4_mapped().map(|v4| v4.to_string()).unwrap_or_else(|| ip.to_string())
+        },
+        _ => return None,
+    };
+    Some(if address == "0.0.0.0" || address == "::" { "*".to_string() } else { address })
+}
+
+/// 解析 /proc/net/tcp 的一行，不是 LISTEN 状态时返回 None
+fn parse_listener(line: &str) -> Option<Listener> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    // 字段: sl local_address rem_address st ... uid timeout inode
+    if fields.len() < 10 || fields[3] != "0A" {
+        return None;
+    }
+    let (address, port) = fields[1].split_once(':')?;
+    Some(Listener {
+        address: parse_address(address)?,
+        port: u16::from_str_radix(port, 16).ok()?,
+        inode: fields[9].parse().ok()?,
+    })
+}
+
+/// 读取 /proc/net/tcp 和 tcp6 中处于 LISTEN 状态的端口
+fn listening_sockets() -> Vec<Listener> {
+    let mut listeners = Vec::new();
+    for file in ["/proc/net/tcp", "/proc/net/tcp6"] {
+        let Ok(content) = fs::read_to_string(file) else { continue };
+        for listener in content.lines().skip(1).filter_map(parse_listener) {
+            if !listeners.iter().any(|l: &Listener| l.port == listener.port && l.address == listener.address) {
+                listeners.push(listener);
+            }
+        }
+    }
+    listeners.sort_by_key(|l| (l.port, l.address.clone()));
+    listeners
+}
+
+/// socket inode -> 持有它的进程，扫描 /proc/<pid>/fd (无权限的进程跳过)
+fn socket_owners() -> HashMap<u64, u32> {
+    let mut owners = HashMap::new();
+    let Ok(entries) = fs::read_dir("/proc") else { return owners };
+    for entry in entries.flatten() {
+        let Ok(pid) = entry.file_name().to_string_lossy().parse::<u32>() else { continue };
+        let Ok(fds) = fs::read_dir(entry.path().join("fd")) else { continue };
+        for fd in fds.flatten() {
+            let Ok(target) = fs::read_link(fd.path()) else { continue };
+            let target = target.to_string_lossy();
+            if let Some(inode) = target.strip_prefix("socket:[").and_then(|rest| rest.strip_suffix(']')).and_then(|inode| inode.parse().ok()) {
+                owners.insert(inode, pid);
+            }
+        }
+    }
+    owners
+}
+
+/// 进程监听的端口
+fn listening_ports_of(pid: u32) -> BTreeSet<u16> {
+    let owners = socket_owners();
+    listening_sockets().into_iter()
+        .filter(|l| owners.get(&l.inode) == Some(&pid))
+        .map(|l| l.port)
+        .collect()
+}
+
+/// 在状态信息中显示分配的端口及是否在监听
+pub fn print_status(jar: &str, pid: u32) {
+    let Some(record) = RunRecord::load(jar).filter(|record| !record.ports.is_empty()) else { return };
+    let listening = if cfg!(target_os = "linux") { Some(listening_ports_of(pid)) } else { None };
+    let ports: Vec<String> = record.ports.iter().map(|(name, port)| {
+        let state = match &listening {
+            Some(listening) if listening.contains(port) => " (监听中)".green().to_string(),
+            Some(_) => " (未监听)".yellow().to_string(),
+            None => String::new(),
+        };
+        format!("{}={}{}", name, port.to_string().bright_green(), state)
+    }).collect();
+    println!("  端口: {}", ports.join(", "));
+}
+
+/// 列出所有监听端口及所属的JAR
+pub fn show_ports(managed_only: bool) -> Result<(), String> {
+    if !cfg!(target_os = "linux") {
+        return Err("ports 命令需要 Linux 的 /proc/net/tcp".to_string());
+    }
+    let running = process::get_running_jars();
+    let records: HashMap<String, RunRecord> = running.iter()
+        .filter_map(|(jar, _)| RunRecord::load(jar).map(|record| (jar.clone(), record)))
+        .collect();
+    let owners = socket_owners();
+    let listeners = listening_sockets();
+
+    println!("\n{}", "=== 监听端口 ===".bright_blue().bold());
+    println!("{}", "  端口    地址             PID      JAR".bright_black());
+    let mut shown = 0;
+    for listener in &listeners {
+        let pid = owners.get(&listener.inode).copied();
+        let jar = pid.and_then(|pid| running.iter().find(|(_, p)| *p == pid)).map(|(jar, _)| jar);
+        if managed_only && jar.is_none() {
+            continue;
+        }
+        let owner = match (jar, pid) {
+            (Some(jar), _) => {
+                let name = records.get(jar)
+                    .and_then(|record| record.ports.iter().find(|(_, port)| **port == listener.port))
+                    .map(|(name, _)| format!(" ({})", name))
+                    .unwrap_or_default();
+                format!("{}{}", jar.bright_cyan(), name.yellow())
+            },
+            (None, Some(pid)) => {
+                let comm = fs::read_to_string(format!("/proc/{}/comm", pid)).unwrap_or_default();
+                format!("- [{}]", comm.trim()).bright_black().to_string()
+            },
+            (None, None) => "-".bright_black().to_string(),
+        };
+        let pid = pid.map(|pid| pid.to_string()).unwrap_or_else(|| "-".to_string());
+        println!("  {} {:<16} {:<8} {}", format!("{:<7}", listener.port).bright_green(), listener.address, pid, owner);
+        shown += 1;
+    }
+    if shown == 0 {
+        println!("  {}", "无监听端口".bright_black());
+    }
+
+    // 分配了但没有在监听的端口
+    let mut missing = Vec::new();
+    for (jar, pid) in &running {
+        let Some(record) = records.get(jar) else { continue };
+        for (name, port) in &record.ports {
+            let listening = listeners.iter().any(|l| l.port == *port && owners.get(&l.inode) == Some(pid));
+            if !listening {
+                missing.push(format!("{} {}={}", jar, name, port));
+            }
+        }
+    }
+    if !missing.is_empty() {
+        println!("\n{}:", "已分配但未监听".bright_yellow());
+        for item in missing {
+            println!("  {}", item);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_port_spec() {
+        let ports = parse_spec(" http , management=9090,debug = auto ").unwrap();
+        assert_eq!(ports.get("http").map(String::as_str), Some("auto"));
+        assert_eq!(ports.get("management").map(String::as_str), Some("9090"));
+        assert_eq!(ports.get("debug").map(String::as_str), Some("auto"));
+        assert!(parse_spec("").unwrap().is_empty());
+        assert!(parse_spec("1http").is_err());
+        assert!(parse_spec("http=0").is_err());
+        assert!(parse_spec("http=65536").is_err());
+        assert!(parse_spec("http=abc").is_err());
+    }
+
+    #[test]
+    fn adds_instance_offset_within_range() {
+        assert_eq!(with_offset("8080", 2), Some(8082));
+        assert_eq!(with_offset("65535", 1), None);
+        assert_eq!(with_offset("auto", 1), None);
+    }
+
+    // /proc/net/tcp 按主机字节序存放地址，以下样例取自 x86_64
+    #[test]
+    #[cfg(target_endian = "little")]
+    fn parses_proc_net_addresses() {
+        assert_eq!(parse_address("0100007F").as_deref(), Some("127.0.0.1"));
+        assert_eq!(parse_address("00000000").as_deref(), Some("*"));
+        assert_eq!(parse_address("00000000000000000000000000000000").as_deref(), Some("*"));
+        assert_eq!(parse_address("00000000000000000000000001000000").as_deref(), Some("::1"));
+        assert_eq!(parse_address("0000000000000000FFFF00000100007F").as_deref(), Some("127.0.0.1"));
+        assert_eq!(parse_address("B80D0120000000000000000001000000").as_deref(), Some("2001:db8::1"));
+        assert_eq!(parse_address("0100007").as_deref(), None);
+        assert_eq!(parse_address("XYZ0007F").as_deref(), None);
+    }
+
+    #[test]
+    #[cfg(target_endian = "little")]
+    fn parses_listening_lines() {
+        let v4 = "   0: 0100007F:1F90 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 12345 1 0000000000000000 100 0 0 10 0";
+        let listener = parse_listener(v4).unwrap();
+        assert_eq!((listener.address.as_str(), listener.port, listener.inode), ("127.0.0.1", 8080, 12345));
+
+        let v6 = "   1: 00000000000000000000000000000000:2382 00000000000000000000000000000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 23456 1 0000000000000000 100 0 0 10 0";
+        let listener = parse_listener(v6).unwrap();
+        assert_eq!((listener.address.as_str(), listener.port, listener.inode), ("*", 9090, 23456));
+
+        let established = "   2: 0100007F:1F90 0100007F:C350 01 00000000:00000000 00:00000000 00000000  1000        0 34567 1 0000000000000000 20 4 30 10 -1";
+        assert!(parse_listener(established).is_none());
+        assert!(parse_listener("  sl  local_address rem_address   st tx_queue rx_queue").is_none());
+    }
+}
@@ -0,0 +1,272 @@
+// preflight.rs - 启动预检和失败诊断
+// 启动前检查 java、所需的 Java 版本、Main-Class、端口、日志目录；进程立即退出时从日志中识别常见错误
+use std::fs;
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use colored::Colorize;
+use regex::Regex;
+use crate::core::config::GlobalConfig;
+use crate::core::{gc, inspect, logging, process};
+
+/// 磁盘剩余空间低于该值 (MB) 时警告
+const LOW_DISK_MB: u64 = 100;
+/// 磁盘剩余空间低于该值 (MB) 时不启动
+const MIN_DISK_MB: u64 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Level {
+    Pass,
+    Warn,
+    Fail,
+}
+
+/// 一项检查的结果
+struct Check {
+    level: Level,
+    message: String,
+    hint: Option<String>,
+}
+
+impl Check {
+    fn pass(message: String) -> Self {
+        Check { level: Level::Pass, message, hint: None }
+    }
+
+    fn warn(message: String, hint: Option<String>) -> Self {
+        Check { level: Level::Warn, message, hint }
+    }
+
+    fn fail(message: String, hint: Option<String>) -> Self {
+        Check { level: Level::Fail, message, hint }
+    }
+}
+
+/// 启动前检查，有检查失败时返回错误；全部通过时只输出一行
+/// 在准备运行副本和分配端口之前执行，args 为用户的启动参数
+pub fn run_preflight(jar: &str, java: &str, args: &[String]) -> Result<(), String> {
+    let config = GlobalConfig::load();
+    if !config.process.preflight {
+        return Ok(());
+    }
+    let mut checks = Vec::new();
+    checks.extend(check_java(jar, java));
+    checks.extend(check_ports(jar, args));
+    checks.extend(check_log_dir(&config.log.log_dir));
+
+    let failures = checks.iter().filter(|c| c.level == Level::Fail).count();
+    if checks.iter().all(|c| c.level == Level::Pass) {
+        println!("  预检: {} ({})", "通过".bright_green(),
+            checks.iter().map(|c| c.message.as_str()).collect::<Vec<_>>().join("，").bright_black());
+        return Ok(());
+    }
+    println!("  {}:", "预检".bright_yellow());
+    for check in &checks {
+        let mark = match check.level {
+            Level::Pass => "✓".green(),
+            Level::Warn => "⚠".yellow(),
+            Level::Fail => "✗".red(),
+        };
+        println!("    {} {}", mark, check.message);
+        if let Some(hint) = &check.hint {
+            println!("      💡 {}", hint);
+        }
+    }
+    if failures > 0 {
+        Err(format!("预检失败 ({} 项)，未启动 {}", failures, jar))
+    } else {
+        Ok(())
+    }
+}
+
+/// java 是否可用、版本是否满足JAR的 class 文件版本，清单是否有 Main-Class
+/// 应用自身的类版本过高时无法启动；只有依赖的版本过高时警告 (依赖中的类可能只在部分功能中用到)
+fn check_java(jar: &str, java: &str) -> Vec<Check> {
+    let mut checks = Vec::new();
+    let major = match process::java_version(java) {
+        Some(line) => {
+            let major = gc::java_major_version(&line);
+            checks.push(Check::pass(format!("Java {}", major.map(|m| m.to_string()).unwrap_or(line))));
+            major
+        },
+        None => {
+            checks.push(Check::fail(format!("找不到可执行的 java: {}", java),
+                Some("安装 JDK，或用 'jdks' 查看已安装的版本并设置 java_home".to_string())));
+            None
+        },
+    };
+
+    match inspect::read_jar_info(&crate::core::registry::jar_path(jar)) {
+        Ok(info) => {
+            let app_required = info.class_version.map(inspect::java_for_class_version);
+            let dependency = info.dependencies.iter()
+                .filter_map(|dep| dep.class_version.map(|version| (inspect::java_for_class_version(version), dep)))
+                .max_by_key(|(required, _)| *required);
+            match (major, app_required, dependency) {
+                (Some(major), Some(required), _) if major < required => {
+                    checks.push(Check::fail(format!("{} 需要 Java {}，当前 java 为 Java {}", jar, required, major),
+                        Some(format!("configs set {} java_home {}", jar, required))));
+                },
+                (Some(major), _, Some((required, dep))) if major < required => {
+                    checks.push(Check::warn(format!("依赖 {} 需要 Java {}，当前 java 为 Java {}", dep.file, required, major),
+                        Some(format!("用到该依赖时会报 UnsupportedClassVersionError，可用 configs set {} java_home {} 切换", jar, required))));
+                },
+                _ => {},
+            }
+            if info.attribute("Main-Class").is_none() {
+                checks.push(Check::fail("清单中没有 Main-Class，无法用 java -jar 启动".to_string(),
+                    Some(format!("用 'inspect {}' 检查JAR的打包方式", jar))));
+            } else {
+                checks.push(Check::pass("Main-Class".to_string()));
+            }
+        },
+        Err(e) => checks.push(Check::fail(e, Some("JAR文件可能损坏或未完整上传".to_string()))),
+    }
+    checks
+}
+
+/// 从命令行中找出配置的监听端口: --server.port=8080、-Dserver.port=8080、--port 8080 等
+pub fn configured_ports(command: &[String]) -> Vec<u16> {
+    let pattern = Regex::new(r"^(?:--|-D)(?:server\.port|management\.server\.port|port)=(\d+)$").unwrap();
+    let mut ports = Vec::new();
+    for (index, arg) in command.iter().enumerate() {
+        let port = match pattern.captures(arg) {
+            Some(caps) => caps[1].parse().ok(),
+            None if arg == "--port" => command.get(index + 1).and_then(|value| value.parse().ok()),
+            None => None,
+        };
+        if let Some(port) = port.filter(|port| *port > 0 && !ports.contains(port)) {
+            ports.push(port);
+        }
+    }
+    ports
+}
+
+/// 占用端口的进程 (ss 输出的进程信息)，无法获取时返回 None
+pub fn port_owner(port: u16) -> Option<String> {
+    if cfg!(target_os = "windows") {
+        return None;
+    }
+    let output = Command::new("ss").args(["-ltnpH", &format!("sport = :{}", port)]).output().ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    let users = Regex::new(r#"users:\(\((.*)\)\)"#).unwrap();
+    text.lines().find_map(|line| users.captures(line).map(|caps| caps[1].to_string()))
+}
+
+/// 启动参数中的端口和JAR选项 ports 中的固定端口是否空闲
+fn check_ports(jar: &str, args: &[String]) -> Vec<Check> {
+    let mut ports = configured_ports(args);
+    for port in crate::core::ports::fixed_ports(jar) {
+        if !ports.contains(&port) {
+            ports.push(port);
+        }
+    }
+    ports.into_iter().map(|port| match TcpListener::bind(("0.0.0.0", port)) {
+        Ok(_) => Check::pass(format!("端口 {}", port)),
+        Err(e) if e.kind() == std::io::ErrorKind::AddrInUse => {
+            let owner = port_owner(port).map(|owner| format!(" (被 {} 占用)", owner)).unwrap_or_default();
+            Check::fail(format!("端口 {} 已被占用{}", port, owner), Some("停止占用端口的进程，或修改启动参数中的端口".to_string()))
+        },
+        // 权限不足等情况交给 JVM 报错
+        Err(e) => Check::warn(format!("无法检查端口 {}: {}", port, e), None),
+    }).collect()
+}
+
+/// 目录所在磁盘的可用空间 (MB)
+fn available_disk_mb(dir: &Path) -> Option<u64> {
+    if cfg!(target_os = "windows") {
+        return None;
+    }
+    let output = Command::new("df").arg("-Pk").arg(dir).output().ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    // 第二行: 文件系统 总量 已用 可用 使用率 挂载点
+    let available: u64 = text.lines().nth(1)?.split_whitespace().nth(3)?.parse().ok()?;
+    Some(available / 1024)
+}
+
+fn check_log_dir(log_dir: &str) -> Vec<Check> {
+    let dir = PathBuf::from(log_dir);
+    let probe = dir.join(format!(".preflight-{}", std::process::id()));
+    let mut checks = vec![match fs::write(&probe, b"") {
+        Ok(_) => {
+            let _ = fs::remove_file(&probe);
+            Check::pass("日志目录可写".to_string())
+        },
+        Err(e) => Check::fail(format!("日志目录不可写 {}: {}", log_dir, e), Some("检查目录权限，或用 global-config set-log-dir 修改".to_string())),
+    }];
+    if let Some(available) = available_disk_mb(&dir) {
+        let hint = Some("清理磁盘，或用 global-config clean-logs / set-log-quota 控制日志大小".to_string());
+        if available < MIN_DISK_MB {
+            checks.push(Check::fail(format!("日志目录所在磁盘只剩 {} MB", available), hint));
+        } else if available < LOW_DISK_MB {
+            checks.push(Check::warn(format!("日志目录所在磁盘只剩 {} MB", available), hint));
+        }
+    }
+    checks
+}
+
+/// 从进程立即退出后的日志中识别失败原因，返回诊断说明
+pub fn diagnose_exit(jar: &str, log_files: &[String]) -> Option<String> {
+    // 只看本次会话的输出
+    let lines = logging::current_session_lines(log_files, 200, crate::core::config::JarOptions::load(jar).encoding());
+
+    let diagnosis = lines.iter().find_map(|line| diagnose_line(jar, line));
+    let output: Vec<&String> = lines.iter().filter(|line| !line.trim().is_empty() && !line.starts_with("  ") && !line.starts_with("===")).collect();
+    if !output.is_empty() {
+        println!("  {}:", "最后的输出".bright_black());
+        for line in &output[output.len().saturating_sub(5)..] {
+            println!("    {}", line.bright_black());
+        }
+    }
+    diagnosis
+}
+
+/// 识别一行日志中的已知错误
+fn diagnose_line(jar: &str, line: &str) -> Option<String> {
+    if line.contains("UnsupportedClassVersionError") {
+        let versions = Regex::new(r"class file version (\d+)\.\d+\), this version of the Java Runtime only recognizes class file versions up to (\d+)").unwrap();
+        return Some(match versions.captures(line) {
+            Some(caps) => {
+                let required = inspect::java_for_class_version(caps[1].parse().unwrap_or(0));
+                let current = inspect::java_for_class_version(caps[2].parse().unwrap_or(0));
+                format!("Java 版本过低: JAR 需要 Java {}，当前为 Java {} (用 'configs set {} java_home {}' 选择)", required, current, jar, required)
+            },
+            None => format!("Java 版本过低 (UnsupportedClassVersionError)，用 'inspect {}' 查看所需版本", jar),
+        });
+    }
+    if line.contains("no main manifest attribute") {
+        return Some(format!("JAR清单中没有 Main-Class，不是可执行JAR (用 'inspect {}' 检查)", jar));
+    }
+    if line.contains("Invalid or corrupt jarfile") || line.contains("Unable to access jarfile") {
+        return Some("JAR文件损坏或无法读取，请重新上传".to_string());
+    }
+    if line.contains("BindException") || line.contains("Address already in use") || line.contains("was already in use") {
+        let port = Regex::new(r"[Pp]ort (\d+)|:(\d+)").unwrap().captures(line)
+            .and_then(|caps| caps.get(1).or(caps.get(2)).map(|m| m.as_str().to_string()));
+        return Some(match port {
+            Some(port) => {
+                let owner = port.parse().ok().and_then(port_owner).map(|owner| format!("，占用者: {}", owner)).unwrap_or_default();
+                format!("端口 {} 已被占用{}", port, owner)
+            },
+            None => "端口已被占用 (BindException)".to_string(),
+        });
+    }
+    if line.contains("OutOfMemoryError") {
+        let kind = line.split("OutOfMemoryError:").nth(1).map(str::trim).unwrap_or("");
+        return Some(if kind.contains("Metaspace") {
+            "内存不足 (Metaspace)，可增加 -XX:MaxMetaspaceSize".to_string()
+        } else {
+            format!("内存不足 ({})，可增加 -Xmx 或用 'heap' 命令分析", if kind.is_empty() { "OutOfMemoryError" } else { kind })
+        });
+    }
+    if line.contains("Could not reserve enough space for object heap") || line.contains("Invalid maximum heap size") || line.contains("Invalid initial heap size") {
+        return Some("堆大小参数无效或超过可用内存，请检查 -Xmx/-Xms".to_string());
+    }
+    if line.contains("Could not find or load main class") || line.contains("ClassNotFoundException") {
+        return Some("找不到主类或依赖类，可能缺少依赖或 Main-Class 配置错误".to_string());
+    }
+    if line.contains("Unrecognized option") || line.contains("Unrecognized VM option") {
+        return Some(format!("JVM 不支持的参数: {}", line.trim()));
+    }
+    None
+}
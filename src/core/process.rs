@@ -1,11 +1,14 @@
 // process.rs - 进程管理模块
 use std::fs;
 use std::path::PathBuf;
-use std::process::{Command, Stdio};
+use std::process::{Child, Command, Stdio};
 use colored::Colorize;
 use std::thread;
 use std::time::Duration;
 use crate::core::config::GlobalConfig;
+use crate::core::logging;
+use crate::core::pipeline;
+use crate::core::registry::state_key;
 
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
@@ -25,7 +28,7 @@ pub fn is_jar_running(jar_file: &str) -> bool {
 
 // 获取JAR的PID
 pub fn get_pid(jar_file: &str) -> Option<u32> {
-    let pid_file = PathBuf::from(PID_DIR).join(format!("{}.pid", jar_file.replace(".jar", "")));
+    let pid_file = PathBuf::from(PID_DIR).join(format!("{}.pid", state_key(jar_file)));
     if pid_file.exists() {
         if let Ok(content) = fs::read_to_string(&pid_file) {
             if let Ok(pid) = content.trim().parse::<u32>() {
@@ -44,7 +47,7 @@ pub fn get_pid(jar_file: &str) -> Option<u32> {
 // 保存JAR的PID
 pub fn save_pid(jar_file: &str, pid: u32) {
     fs::create_dir_all(PID_DIR).ok();
-    let pid_file = PathBuf::from(PID_DIR).join(format!("{}.pid", jar_file.replace(".jar", "")));
+    let pid_file = PathBuf::from(PID_DIR).join(format!("{}.pid", state_key(jar_file)));
     if let Err(e) = fs::write(&pid_file, pid.to_string()) {
         print_warn!("保存PID文件失败: {}", e);
     }
@@ -52,7 +55,7 @@ pub fn save_pid(jar_file: &str, pid: u32) {
 
 // 移除JAR的PID文件
 pub fn remove_pid(jar_file: &str) {
-    let pid_file = PathBuf::from(PID_DIR).join(format!("{}.pid", jar_file.replace(".jar", "")));
+    let pid_file = PathBuf::from(PID_DIR).join(format!("{}.pid", state_key(jar_file)));
     fs::remove_file(&pid_file).ok();
 }
 
@@ -106,9 +109,10 @@ pub fn start_jar(jar: &str, args: &[String]) -> Result<(), String> {
             
             // 显示现有进程信息
             let config = GlobalConfig::load();
-            let log_file = config.get_log_file_path(jar);
-            if PathBuf::from(&log_file).exists() {
-                println!("  日志文件: {}", log_file.cyan());
+            for log_file in config.get_jar_log_files(jar) {
+                if PathBuf::from(&log_file).exists() {
+                    println!("  日志文件: {}", log_file.cyan());
+                }
             }
             println!("  后台运行: {}", "是".bright_green());
             println!("💡 提示: 使用 'stop' 命令停止，或 'status' 查看详情");
@@ -126,16 +130,20 @@ pub fn start_jar(jar: &str, args: &[String]) -> Result<(), String> {
     // 确保目录存在
     fs::create_dir_all(&config.log.log_dir).map_err(|e| format!("创建日志目录失败: {}", e))?;
     fs::create_dir_all(PID_DIR).map_err(|e| format!("创建PID目录失败: {}", e))?;
+    crate::core::registry::remember_path(jar);
     
-    let log_file = config.get_log_file_path(jar);
+    let log_files = config.get_jar_log_files(jar);
+    let log_file = log_files.join(", ");
     
     // 检查是否需要轮转日志
-    if config.should_rotate_log(&log_file) {
-        if let Err(e) = config.rotate_log(jar) {
-            print_warn!("日志轮转失败: {}", e);
-        }
+    if let Err(e) = config.rotate_jar_logs(jar) {
+        print_warn!("日志轮转失败: {}", e);
     }
     
+    let use_pipeline = config.log.enable_pipeline;
+    if !use_pipeline && crate::core::syslog::SyslogTarget::for_jar(jar, &config).is_some() {
+        print_warn!("syslog 转发需要启用日志管道 (global-config set-log-pipeline true)，本次运行不会转发");
+    }
     drop(config); // 释放锁
     
     print_success!("正在启动 {}...", jar.bright_cyan());
@@ -143,74 +151,85 @@ pub fn start_jar(jar: &str, args: &[String]) -> Result<(), String> {
         println!("  启动参数: {}", args.join(" ").yellow());
     }
     
-    let child = if cfg!(target_os = "windows") {
-        // Windows: 使用特殊标志确保进程独立运行
-        let mut cmd = Command::new("java");
-        cmd.arg("-jar").arg(jar);
-        for arg in args { cmd.arg(arg); }
-        
-        // 重定向输出到日志文件
-        if let Ok(file) = fs::File::create(&log_file) {
-            cmd.stdout(Stdio::from(file.try_clone().unwrap()));
-            cmd.stderr(Stdio::from(file));
-        } else {
-            cmd.stdout(Stdio::null());
-            cmd.stderr(Stdio::null());
-        }
-        
-        // 设置创建标志：无窗口 + 分离进程
-        cmd.creation_flags(CREATE_NO_WINDOW | DETACHED_PROCESS);
-        
-        cmd.spawn()
+    // 先做启动前检查，检查失败时不会留下运行副本
+    let java = crate::core::jdk::resolve_java(jar)?.java;
+    crate::core::preflight::run_preflight(jar, &java, &crate::core::instance::expand_args(jar, args))?;
+    
+    let mut record = crate::core::runcopy::prepare_run(jar)?;
+    if let Some(copy) = &record.run_copy {
+        println!("  运行副本: {}", copy.cyan());
+    }
+    record.ports = crate::core::ports::allocate(jar)?;
+    if !record.ports.is_empty() {
+        println!("  端口: {}", crate::core::ports::format_ports(&record.ports).yellow());
+    }
+    let args = crate::core::ports::apply_args(&record.ports, args);
+    let env = crate::core::ports::env_vars(&record.ports);
+    let command = build_java_command(jar, &record.launch_path(jar), &args)?;
+    let java_version = java_version(&command[0]).unwrap_or_else(|| "未知".to_string());
+    let child = if use_pipeline {
+        // 由日志管道进程启动 java 并接管输出，java 的PID由管道进程写入
+        remove_pid(jar);
+        pipeline::spawn_pipeline(jar, &command, &env)
     } else {
-        // Linux: 使用 nohup 确保后台运行
-        let mut cmd = Command::new("nohup");
-        cmd.arg("java").arg("-jar").arg(jar);
-        for arg in args { cmd.arg(arg); }
-        
-        // 重定向输出到日志文件
-        if let Ok(file) = fs::File::create(&log_file) {
-            cmd.stdout(Stdio::from(file.try_clone().unwrap()));
-            cmd.stderr(Stdio::from(file));
-        } else {
-            cmd.stdout(Stdio::null());
-            cmd.stderr(Stdio::null());
+        // 先写会话横幅再启动进程，保证横幅位于本次运行的所有输出之前
+        let banner = logging::session_banner(None, &java_version, &command);
+        for log_file in &log_files {
+            if let Err(e) = logging::append_lines(log_file, &banner) {
+                print_warn!("写入会话横幅失败: {}", e);
+            }
         }
-        
-        // 设置进程组，避免信号传播
-        cmd.stdin(Stdio::null());
-        
-        cmd.spawn()
+        spawn_direct(&command, &log_files, &env)
     };
     
     match child {
         Ok(mut process) => {
-            let pid = process.id();
-            save_pid(jar, pid);
+            let pid = if use_pipeline {
+                wait_for_pid(jar, Duration::from_secs(5))
+            } else {
+                save_pid(jar, process.id());
+                // 横幅写入时还没有 PID，补写一行供会话列表识别
+                let pid_line = [logging::session_pid_line(process.id())];
+                for log_file in &log_files {
+                    if let Err(e) = logging::append_lines(log_file, &pid_line) {
+                        print_warn!("写入会话 PID 失败: {}", e);
+                    }
+                }
+                Some(process.id())
+            };
             
             // 等待一小段时间确保进程启动成功
             thread::sleep(Duration::from_millis(500));
             
             // 验证进程是否真的在运行
-            if is_process_running(pid) {
-                // 立即分离进程，不等待退出
-                thread::spawn(move || {
-                    let _ = process.wait();
-                });
-                
-                print_success!("✅ 启动成功: {} (PID: {})", jar.bright_cyan(), pid.to_string().bright_green());
-                print_success!("🚀 进程已分离，可安全关闭终端");
-                println!("  日志文件: {}", log_file.cyan());
-                println!("  后台运行: {}", "是".bright_green());
-                println!("💡 使用 'status {}' 检查运行状态", jar);
-                Ok(())
-            } else {
-                // 进程启动失败
-                remove_pid(jar);
-                let error_msg = "进程启动后立即退出，可能是JAR文件损坏或缺少依赖".to_string();
-                print_error!("{}", error_msg);
-                println!("💡 请检查日志文件: {}", log_file.cyan());
-                Err(error_msg)
+            match pid.filter(|pid| is_process_running(*pid)) {
+                Some(pid) => {
+                    // 立即分离进程，不等待退出
+                    thread::spawn(move || {
+                        let _ = process.wait();
+                    });
+                    
+                    if let Err(e) = record.save(jar) {
+                        print_warn!("{}", e);
+                    }
+                    print_success!("✅ 启动成功: {} (PID: {})", jar.bright_cyan(), pid.to_string().bright_green());
+                    print_success!("🚀 进程已分离，可安全关闭终端");
+                    println!("  日志文件: {}", log_file.cyan());
+                    println!("  后台运行: {}", "是".bright_green());
+                    println!("💡 使用 'status {}' 检查运行状态", jar);
+                    Ok(())
+                },
+                None => {
+                    // 进程启动失败
+                    remove_pid(jar);
+                    let error_msg = match crate::core::preflight::diagnose_exit(jar, &log_files) {
+                        Some(diagnosis) => format!("进程启动后立即退出: {}", diagnosis),
+                        None => "进程启动后立即退出，可能是JAR文件损坏或缺少依赖".to_string(),
+                    };
+                    print_error!("{}", error_msg);
+                    println!("💡 请检查日志文件: {}", log_file.cyan());
+                    Err(error_msg)
+                }
             }
         },
         Err(e) => {
@@ -225,6 +244,90 @@ pub fn start_jar(jar: &str, args: &[String]) -> Result<(), String> {
     }
 }
 
+// 获取 java 版本信息（`java -version` 输出的第一行）
+pub fn java_version(java: &str) -> Option<String> {
+    let output = Command::new(java).arg("-version").output().ok()?;
+    // java -version 输出到 stderr
+    let text = String::from_utf8_lossy(&output.stderr);
+    text.lines().next().map(|line| line.trim().to_string()).filter(|line| !line.is_empty())
+}
+
+// 对运行中的 JVM 执行 jcmd 诊断命令，返回其输出；失败时返回第一行错误信息
+// 优先使用JAR所用 JDK 中的 jcmd，避免与目标 JVM 版本不一致
+pub fn jcmd(jar: &str, pid: u32, args: &[&str]) -> Result<String, String> {
+    let output = Command::new(crate::core::jdk::tool_for(jar, "jcmd")).arg(pid.to_string()).args(args).output()
+        .map_err(|e| format!("无法执行 jcmd (需要 JDK): {}", e))?;
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    if output.status.success() {
+        return Ok(stdout);
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let message = stderr.lines().chain(stdout.lines()).find(|l| !l.trim().is_empty()).unwrap_or("").trim().to_string();
+    Err(format!("jcmd 执行失败: {}", message))
+}
+
+// 构建启动JAR的完整命令行
+// launch 为 java -jar 使用的路径 (原JAR或运行副本)
+fn build_java_command(jar: &str, launch: &str, args: &[String]) -> Result<Vec<String>, String> {
+    let runtime = crate::core::jdk::resolve_java(jar)?;
+    if let Some(source) = &runtime.source {
+        println!("  Java: {} ({})", runtime.java.yellow(), source.bright_black());
+    }
+    let mut command = vec![runtime.java];
+    command.extend(crate::core::jfr::continuous_jvm_args(jar));
+    command.extend(crate::core::gc::gc_logging_jvm_args(jar, &command[0]));
+    command.extend(["-jar".to_string(), launch.to_string()]);
+    command.extend(crate::core::instance::expand_args(jar, args));
+    Ok(command)
+}
+
+// 直接启动进程，输出重定向到日志文件（不经过日志管道）
+// log_files 为一个文件时 stdout/stderr 合并写入，两个文件时分别写入
+fn spawn_direct(command: &[String], log_files: &[String], env: &[(String, String)]) -> std::io::Result<Child> {
+    let mut cmd = if cfg!(target_os = "windows") {
+        // Windows: 使用特殊标志确保进程独立运行
+        Command::new(&command[0])
+    } else {
+        // Linux: 使用 nohup 确保后台运行
+        let mut cmd = Command::new("nohup");
+        cmd.arg(&command[0]);
+        cmd
+    };
+    cmd.args(&command[1..]);
+    cmd.envs(env.iter().map(|(key, value)| (key, value)));
+    
+    // 重定向输出到日志文件（追加写入，保留之前运行的日志）
+    let open_append = |path: &String| fs::OpenOptions::new().create(true).append(true).open(path).ok();
+    let stdout_file = open_append(&log_files[0]);
+    let stderr_file = match log_files.get(1) {
+        Some(err_file) => open_append(err_file),
+        None => stdout_file.as_ref().and_then(|file| file.try_clone().ok()),
+    };
+    cmd.stdout(stdout_file.map_or_else(Stdio::null, Stdio::from));
+    cmd.stderr(stderr_file.map_or_else(Stdio::null, Stdio::from));
+    
+    // 设置进程组，避免信号传播
+    cmd.stdin(Stdio::null());
+    
+    // 设置创建标志：无窗口 + 分离进程
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(CREATE_NO_WINDOW | DETACHED_PROCESS);
+    
+    cmd.spawn()
+}
+
+// 等待日志管道进程写入JAR的PID
+fn wait_for_pid(jar: &str, timeout: Duration) -> Option<u32> {
+    let deadline = std::time::Instant::now() + timeout;
+    while std::time::Instant::now() < deadline {
+        if let Some(pid) = get_pid(jar) {
+            return Some(pid);
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    None
+}
+
 // 启动JAR应用（无额外参数版本）
 pub fn start_jar_simple(jar: &str) -> Result<(), String> {
     start_jar(jar, &[])
@@ -252,6 +355,7 @@ pub fn stop_jar(jar: &str) -> Result<(), String> {
             Ok(out) if out.status.success() => {
                 remove_pid(jar);
                 print_success!("已停止 {} (PID: {})", jar.bright_cyan(), pid.to_string().bright_green());
+                collect_run_copies();
                 Ok(())
             },
             Ok(out) => {
@@ -274,6 +378,14 @@ pub fn stop_jar(jar: &str) -> Result<(), String> {
     }
 }
 
+// 回收不再使用的运行副本
+fn collect_run_copies() {
+    let removed = crate::core::runcopy::collect_garbage();
+    if removed > 0 {
+        print_info!("已回收 {} 个不再使用的运行副本", removed);
+    }
+}
+
 // 重启JAR应用
 pub fn restart_jar(jar: &str, args: &[String]) -> Result<(), String> {
     print_success!("正在重启 {}...", jar.bright_cyan());
@@ -300,21 +412,22 @@ pub fn show_jar_status(jar: Option<&str>) {
                 
                 // 显示日志信息
                 let config = GlobalConfig::load();
-                let log_file = config.get_log_file_path(jar);
-                if PathBuf::from(&log_file).exists() {
-                    println!("  日志文件: {}", log_file.cyan());
-                    if let Ok(metadata) = fs::metadata(&log_file) {
-                        println!("  日志大小: {}", format_file_size(metadata.len()).bright_blue());
-                        if config.should_rotate_log(&log_file) {
-                            println!("  轮转状态: {}", "需要轮转".yellow());
+                for log_file in config.get_jar_log_files(jar) {
+                    if PathBuf::from(&log_file).exists() {
+                        println!("  日志文件: {}", log_file.cyan());
+                        if let Ok(metadata) = fs::metadata(&log_file) {
+                            println!("  日志大小: {}", format_file_size(metadata.len()).bright_blue());
+                            if config.should_rotate_log(&log_file) {
+                                println!("  轮转状态: {}", "需要轮转".yellow());
+                            }
                         }
+                    } else {
+                        println!("  日志文件: {} ({})", log_file.bright_black(), "尚未创建".yellow());
                     }
-                } else {
-                    println!("  日志文件: {}", "尚未创建".yellow());
                 }
                 
                 // 显示配置信息
-                let config_file = format!(".Manager_Jar/configs/{}.config", jar.replace(".jar", ""));
+                let config_file = format!(".Manager_Jar/configs/{}.config", state_key(jar));
                 if PathBuf::from(&config_file).exists() {
                     if let Ok(config) = fs::read_to_string(&config_file) {
                         if !config.trim().is_empty() {
@@ -324,6 +437,8 @@ pub fn show_jar_status(jar: Option<&str>) {
                 } else {
                     println!("  保存配置: {}", "无".bright_black());
                 }
+                crate::core::runcopy::print_status(jar);
+                crate::core::ports::print_status(jar, pid);
                 
                 println!("  运行模式: {}", "后台分离".bright_green());
                 println!("  终端安全: {}", "可关闭".bright_green());
@@ -364,6 +479,9 @@ pub fn show_jar_status(jar: Option<&str>) {
             println!("  PID文件: {}", "不存在".bright_black());
         }
         
+        crate::core::jfr::print_status(jar);
+        crate::core::instance::print_instances(jar);
+
         println!("\n💡 可用命令:");
         if get_pid(jar).is_some() && is_jar_running(jar) {
             println!("  {} - 停止应用", format!("stop {}", jar).cyan());
@@ -372,7 +490,7 @@ pub fn show_jar_status(jar: Option<&str>) {
             println!("  {} - 强制终止", format!("kill {}", jar).cyan());
         } else {
             println!("  {} - 启动应用", format!("start {}", jar).cyan());
-            if PathBuf::from(&format!(".Manager_Jar/configs/{}.config", jar.replace(".jar", ""))).exists() {
+            if PathBuf::from(&format!(".Manager_Jar/configs/{}.config", state_key(jar))).exists() {
                 println!("  {} - 快速启动", format!("quick {}", jar).cyan());
             }
         }
@@ -393,7 +511,7 @@ pub fn get_running_jars() -> Vec<(String, u32)> {
             let path = entry.path();
             if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
                 if name.ends_with(".pid") {
-                    let jar_name = format!("{}.jar", name.trim_end_matches(".pid"));
+                    let jar_name = crate::core::registry::app_for_key(name.trim_end_matches(".pid"));
                     if let Some(pid) = get_pid(&jar_name) {
                         if is_process_running(pid) {
                             running_jars.push((jar_name, pid));
@@ -424,7 +542,7 @@ pub fn list_running_jars() {
             let path = entry.path();
             if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
                 if name.ends_with(".pid") {
-                    let jar_name = format!("{}.jar", name.trim_end_matches(".pid"));
+                    let jar_name = crate::core::registry::app_for_key(name.trim_end_matches(".pid"));
                     if let Some(pid) = get_pid(&jar_name) {
                         if is_process_running(pid) {
                             running_jars.push((jar_name, pid));
@@ -458,16 +576,24 @@ pub fn list_running_jars() {
             }
             println!("\n💡 使用 'start <jar>' 启动应用");
         } else {
-            println!("\n💡 当前目录下无可用的JAR文件");
+            println!("\n💡 查找目录中无可用的JAR文件");
         }
     } else {
         print_success!("🚀 运行中的 JAR 应用 ({} 个):", running_jars.len());
         let config = GlobalConfig::load();
+        // 同一JAR的实例排在一起，显示在JAR下方
+        use crate::core::instance;
+        running_jars.sort_by_key(|(jar, _)| (instance::base(jar).to_string(), instance::split(jar).1.is_some(), instance::port_offset(jar), jar.clone()));
+        let mut group = String::new();
         for (jar, pid) in running_jars {
-            let log_file = config.get_log_file_path(&jar);
-            let log_size = if let Ok(metadata) = fs::metadata(&log_file) {
-                let size_info = format_file_size(metadata.len());
-                let rotation_hint = if config.should_rotate_log(&log_file) {
+            let log_files = config.get_jar_log_files(&jar);
+            let total_size: Option<u64> = log_files.iter()
+                .filter_map(|log_file| fs::metadata(log_file).ok())
+                .map(|metadata| metadata.len())
+                .reduce(|a, b| a + b);
+            let log_size = if let Some(size) = total_size {
+                let size_info = format_file_size(size);
+                let rotation_hint = if log_files.iter().any(|log_file| config.should_rotate_log(log_file)) {
                     " [需要轮转]".yellow().to_string()
                 } else {
                     String::new()
@@ -477,8 +603,18 @@ pub fn list_running_jars() {
                 String::new()
             };
             
+            let label = match instance::split(&jar) {
+                (base, Some(name)) => {
+                    if group != base {
+                        println!("  {}", base.bright_cyan());
+                    }
+                    format!("  {}{}", instance::SEPARATOR, name)
+                },
+                (base, None) => base.to_string(),
+            };
+            group = instance::base(&jar).to_string();
             println!("  {} (PID: {}) [{}{}]", 
-                     jar.bright_cyan(), 
+                     label.bright_cyan(), 
                      pid.to_string().bright_green(),
                      "后台运行".bright_blue(),
                      log_size.bright_black());
@@ -503,6 +639,7 @@ pub fn kill_jar(jar: &str) -> Result<(), String> {
             Ok(_) => {
                 remove_pid(jar);
                 print_success!("已强制杀死 {} (PID: {})", jar.bright_cyan(), pid.to_string().bright_green());
+                collect_run_copies();
                 Ok(())
             },
             Err(e) => {
@@ -522,7 +659,7 @@ pub fn kill_jar(jar: &str) -> Result<(), String> {
 #[allow(dead_code)]
 pub fn get_jar_status(jar: &str) -> Result<JarStatus, String> {
     let jar_file = if jar.ends_with(".jar") { jar } else { &format!("{}.jar", jar) };
-    let pid_file = PathBuf::from(PID_DIR).join(format!("{}.pid", jar_file.replace(".jar", "")));
+    let pid_file = PathBuf::from(PID_DIR).join(format!("{}.pid", state_key(jar_file)));
     
     if !pid_file.exists() {
         return Ok(JarStatus {
@@ -0,0 +1,536 @@
+// registry.rs - 应用注册表
+// 逻辑应用名 (如 api) 对应JAR路径或通配符 (如 api-*.jar)；配置、PID、日志都按应用名保存，升级后JAR文件名变化也不会丢失
+// 另可为应用或JAR定义短别名。注册表保存在 .Manager_Jar/configs/apps.toml
+// 当前目录以外的JAR以完整路径标识，状态文件名带路径哈希以区分不同目录下的同名JAR
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use colored::Colorize;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use crate::core::config::GlobalConfig;
+use crate::core::{instance, process};
+
+const REGISTRY_FILE: &str = ".Manager_Jar/configs/apps.toml";
+/// 状态名 -> 当前目录以外的JAR的完整路径，用于从状态文件还原JAR
+const PATH_INDEX_FILE: &str = ".Manager_Jar/data/paths.toml";
+const SEQUENCE_DIR: &str = ".Manager_Jar/sequences";
+
+/// 一个注册的应用
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AppEntry {
+    /// JAR路径或文件名通配符 (* 和 ?)
+    pub jar: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Registry {
+    #[serde(default)]
+    pub apps: BTreeMap<String, AppEntry>,
+    /// 别名 -> 应用名或JAR文件名
+    #[serde(default)]
+    pub aliases: BTreeMap<String, String>,
+}
+
+impl Registry {
+    pub fn load() -> Self {
+        match fs::read_to_string(REGISTRY_FILE) {
+            Ok(content) => toml::from_str(&content).unwrap_or_else(|e| {
+                print_warn!("应用注册表解析失败，忽略: {}", e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        if let Some(dir) = Path::new(REGISTRY_FILE).parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("创建配置目录失败: {}", e))?;
+        }
+        let content = toml::to_string_pretty(self).map_err(|e| format!("序列化应用注册表失败: {}", e))?;
+        fs::write(REGISTRY_FILE, content).map_err(|e| format!("保存应用注册表失败: {}", e))
+    }
+
+    /// 按应用名查找，也接受 "<应用名>.jar" 形式 (PID 等状态文件按名称还原的结果)
+    fn entry(&self, app: &str) -> Option<&AppEntry> {
+        self.apps.get(app).or_else(|| self.apps.get(app.strip_suffix(".jar")?))
+    }
+}
+
+fn is_pattern(jar: &str) -> bool {
+    jar.contains('*') || jar.contains('?')
+}
+
+/// 文件名通配符转换为正则
+fn pattern_regex(pattern: &str) -> Regex {
+    let mut regex = String::from("^");
+    for c in pattern.chars() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    Regex::new(&regex).unwrap()
+}
+
+/// 拆分为数字和非数字段，使 api-1.10.jar 排在 api-1.9.jar 之后
+fn natural_key(name: &str) -> Vec<(u64, String)> {
+    Regex::new(r"\d+|\D+").unwrap().find_iter(name)
+        .map(|part| match part.as_str().parse::<u64>() {
+            Ok(number) => (number, String::new()),
+            Err(_) => (0, part.as_str().to_string()),
+        })
+        .collect()
+}
+
+/// 通配符匹配的所有JAR，按版本从旧到新排列
+fn pattern_matches(pattern: &str) -> Vec<PathBuf> {
+    let path = Path::new(pattern);
+    let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let regex = pattern_regex(&path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default());
+    let mut matches: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| entries.flatten()
+            .filter(|entry| entry.path().is_file() && regex.is_match(&entry.file_name().to_string_lossy()))
+            .map(|entry| if dir == Path::new(".") { PathBuf::from(entry.file_name()) } else { entry.path() })
+            .collect())
+        .unwrap_or_default();
+    matches.sort_by_key(|path| natural_key(&path.file_name().unwrap_or_default().to_string_lossy()));
+    matches
+}
+
+/// 应用当前对应的JAR：通配符取版本最新的一个，未匹配时返回 None
+fn resolve_entry(entry: &AppEntry) -> Option<PathBuf> {
+    if is_pattern(&entry.jar) {
+        pattern_matches(&entry.jar).pop()
+    } else {
+        Some(PathBuf::from(&entry.jar)).filter(|path| path.is_file())
+    }
+}
+
+/// 应用或JAR对应的JAR文件路径，未注册的名称按JAR文件名处理
+pub fn jar_path(app: &str) -> PathBuf {
+    let app = instance::base(app);
+    let registry = Registry::load();
+    match registry.entry(app) {
+        Some(entry) => resolve_entry(entry).unwrap_or_else(|| PathBuf::from(&entry.jar)),
+        None => PathBuf::from(app),
+    }
+}
+
+/// 是否为以通配符注册的应用
+pub fn is_pattern_app(app: &str) -> bool {
+    Registry::load().entry(instance::base(app)).is_some_and(|entry| is_pattern(&entry.jar))
+}
+
+/// 是否为注册的应用
+pub fn is_registered(app: &str) -> bool {
+    Registry::load().entry(instance::base(app)).is_some()
+}
+
+fn has_dir(jar: &str) -> bool {
+    Path::new(jar).parent().is_some_and(|dir| !dir.as_os_str().is_empty())
+}
+
+fn load_path_index() -> BTreeMap<String, String> {
+    fs::read_to_string(PATH_INDEX_FILE).ok()
+        .and_then(|content| toml::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// 配置、PID、日志等状态文件使用的名称
+/// 应用名和当前目录下的JAR为去掉 .jar 的名称；带目录的路径为 "<文件名>-<路径哈希>"；实例再加上 "@<实例名>"
+pub fn state_key(jar: &str) -> String {
+    if let (base, Some(name)) = instance::split(jar) {
+        return format!("{}{}{}", state_key(base), instance::SEPARATOR, name);
+    }
+    if !has_dir(jar) {
+        return jar.replace(".jar", "");
+    }
+    let stem = Path::new(jar).file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+    let hash = format!("{:x}", Sha256::digest(jar.as_bytes()));
+    format!("{}-{}", stem, &hash[..8])
+}
+
+/// 在路径索引中登记当前目录以外的JAR，启动和注册时调用；先写临时文件再改名，并发写入不会留下半个文件
+pub fn remember_path(jar: &str) {
+    let jar = instance::base(jar);
+    if !has_dir(jar) {
+        return;
+    }
+    let key = state_key(jar);
+    let mut index = load_path_index();
+    if index.get(&key).map(String::as_str) == Some(jar) {
+        return;
+    }
+    index.insert(key, jar.to_string());
+    let Ok(content) = toml::to_string_pretty(&index) else { return };
+    let temp = format!("{}.{}.tmp", PATH_INDEX_FILE, std::process::id());
+    let result = fs::create_dir_all(".Manager_Jar/data")
+        .and_then(|_| fs::write(&temp, content))
+        .and_then(|_| fs::rename(&temp, PATH_INDEX_FILE));
+    if let Err(e) = result {
+        let _ = fs::remove_file(&temp);
+        print_warn!("保存路径索引失败: {}", e);
+    }
+}
+
+/// 由状态名还原应用标识：注册的应用返回应用名，当前目录以外的JAR返回完整路径，否则返回JAR文件名
+pub fn app_for_key(key: &str) -> String {
+    if let (base, Some(name)) = instance::split(key) {
+        return instance::instance_id(&app_for_key(base), name);
+    }
+    if Registry::load().apps.contains_key(key) {
+        return key.to_string();
+    }
+    match load_path_index().remove(key) {
+        Some(path) => path,
+        None => format!("{}.jar", key),
+    }
+}
+
+/// 把JAR路径规范为唯一标识：当前目录下的文件用文件名，其余用完整路径
+/// 这样 ./app.jar 和 app.jar、../b/app.jar 和 /opt/b/app.jar 分别对应同一个JAR
+/// 只按字面去掉 . 和 ..，不解析符号链接：指向版本目录的链接切换后仍是同一个JAR
+pub fn normalize_path(jar: &str) -> String {
+    if !has_dir(jar) {
+        return jar.to_string();
+    }
+    let Ok(cwd) = env::current_dir() else { return jar.to_string() };
+    let mut path = PathBuf::new();
+    for component in cwd.join(jar).components() {
+        match component {
+            Component::CurDir => {},
+            Component::ParentDir => { path.pop(); },
+            component => path.push(component),
+        }
+    }
+    let in_cwd = path.parent() == Some(cwd.as_path());
+    match path.file_name() {
+        Some(name) if in_cwd => name.to_string_lossy().to_string(),
+        _ => path.display().to_string(),
+    }
+}
+
+/// 把命令行参数解析为应用标识：别名、应用名，或属于某个应用的JAR文件；其余按JAR文件名原样返回
+pub fn app_name(arg: &str) -> String {
+    if let (base, Some(name)) = instance::split(arg) {
+        if !Path::new(arg).exists() {
+            return instance::instance_id(&app_name(base), name);
+        }
+    }
+    let registry = Registry::load();
+    let arg = registry.aliases.get(arg).map(String::as_str).unwrap_or(arg);
+    if registry.apps.contains_key(arg) {
+        return arg.to_string();
+    }
+    let path = Path::new(arg);
+    for (name, entry) in &registry.apps {
+        // 通配符应用升级后旧的JAR已不存在，按文件名匹配通配符 (如旧告警规则中的 api-1.2.jar)
+        let matches = if is_pattern(&entry.jar) {
+            pattern_matches(&entry.jar).iter().any(|candidate| same_file(candidate, path))
+                || (!path.exists() && matches_pattern(&entry.jar, path))
+        } else {
+            same_file(Path::new(&entry.jar), path)
+        };
+        if matches {
+            return name.clone();
+        }
+    }
+    // PID 等状态文件还原出的 "<应用名>.jar"
+    match arg.strip_suffix(".jar") {
+        Some(key) if !path.exists() && registry.apps.contains_key(key) => key.to_string(),
+        _ => normalize_path(arg),
+    }
+}
+
+/// 路径是否与通配符在同一目录且文件名匹配
+fn matches_pattern(pattern: &str, path: &Path) -> bool {
+    let (Some(pattern_name), Some(name)) = (Path::new(pattern).file_name(), path.file_name()) else { return false };
+    let candidate = Path::new(pattern).with_file_name(name);
+    normalize_path(&candidate.display().to_string()) == normalize_path(&path.display().to_string())
+        && pattern_regex(&pattern_name.to_string_lossy()).is_match(&name.to_string_lossy())
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// 解析应用标识并检查JAR是否存在，失败时输出错误并返回 None
+pub fn resolve_app(arg: &str) -> Option<String> {
+    let app = app_name(arg);
+    let (base, name) = instance::split(&app);
+    if let Some(Err(e)) = name.map(instance::validate_name) {
+        print_error!("{}", e);
+        return None;
+    }
+    let registry = Registry::load();
+    let found = match registry.entry(base) {
+        Some(entry) => {
+            let found = resolve_entry(entry).is_some();
+            if !found {
+                print_error!("应用 {} 没有可用的JAR: {}", base.cyan(), entry.jar);
+            }
+            found
+        },
+        None => crate::utils::files::validate_jar_file(base),
+    };
+    found.then_some(app)
+}
+
+fn validate_name(name: &str) -> Result<(), String> {
+    let valid = Regex::new(r"^[A-Za-z0-9][A-Za-z0-9_.\-]*$").unwrap().is_match(name) && !name.ends_with(".jar");
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("无效的名称: {} (只能包含字母、数字、. _ -，且不能以 .jar 结尾)", name))
+    }
+}
+
+/// 注册应用；JAR原来按文件名保存的配置、PID 和日志迁移到应用名下
+pub fn add_app(name: &str, jar: &str) -> Result<(), String> {
+    validate_name(name)?;
+    let mut registry = Registry::load();
+    if registry.aliases.contains_key(name) {
+        return Err(format!("{} 已是别名", name));
+    }
+    let entry = AppEntry { jar: jar.to_string() };
+    let resolved = resolve_entry(&entry);
+    if resolved.is_none() {
+        print_warn!("当前没有匹配 {} 的JAR，应用将在JAR出现后可用", jar);
+    }
+    // 运行中的JAR的 PID 和日志文件正被使用，迁移后会与进程脱节
+    if let Some(path) = &resolved {
+        let current = normalize_path(&path.display().to_string());
+        if process::is_jar_running(&current) || !instance::running_instances(&current).is_empty() {
+            return Err(format!("{} 正在运行，请先停止后再注册为应用 {}", current, name));
+        }
+    }
+    let replaced = registry.apps.insert(name.to_string(), entry).is_some();
+    registry.save()?;
+    print_success!("已{}应用 {} → {}", if replaced { "更新" } else { "注册" }, name.bright_cyan(), jar.yellow());
+    if let Some(path) = resolved {
+        println!("  当前JAR: {}", path.display().to_string().cyan());
+        remember_path(&normalize_path(&path.display().to_string()));
+        migrate_state(&normalize_path(&path.display().to_string()), name);
+    }
+    Ok(())
+}
+
+/// 把按JAR保存的状态文件改到应用名下，目标已存在时跳过
+fn migrate_state(jar: &str, app: &str) {
+    let old = state_key(jar);
+    if old == app {
+        return;
+    }
+    let mut moves: Vec<(PathBuf, PathBuf)> = Vec::new();
+    for (dir, suffix) in [(".Manager_Jar/configs", ".config"), (".Manager_Jar/configs", ".options.toml"), (".Manager_Jar/data", ".pid"), (".Manager_Jar/data", ".run.toml")] {
+        moves.push((Path::new(dir).join(format!("{}{}", old, suffix)), Path::new(dir).join(format!("{}{}", app, suffix))));
+    }
+    // 日志: 各模式的活动日志、轮转的旧日志和 GC 日志
+    let config = GlobalConfig::load();
+    let gc_log = crate::core::gc::gc_log_path(&config, jar);
+    let mut logs: Vec<PathBuf> = config.jar_log_files_all(jar).into_iter().map(|info| info.path).collect();
+    logs.push(PathBuf::from(&gc_log));
+    logs.extend(crate::core::logging::list_log_generations(&gc_log));
+    let prefix = format!("{}.", old);
+    for log in logs {
+        let renamed = log.file_name()
+            .and_then(|name| name.to_str()?.strip_prefix(&prefix).map(|rest| format!("{}.{}", app, rest)));
+        if let Some(renamed) = renamed {
+            moves.push((log.clone(), log.with_file_name(renamed)));
+        }
+    }
+
+    let mut moved = 0;
+    for (from, to) in moves {
+        if !from.exists() {
+            continue;
+        }
+        if to.exists() {
+            print_warn!("{} 已存在，未迁移 {}", to.display(), from.display());
+            continue;
+        }
+        match fs::rename(&from, &to) {
+            Ok(_) => moved += 1,
+            Err(e) => print_warn!("迁移 {} 失败: {}", from.display(), e),
+        }
+    }
+    if moved > 0 {
+        print_info!("已把 {} 的 {} 个配置、PID 和日志文件迁移到应用 {} 下", jar, moved, app);
+    }
+    migrate_sequences(jar, app);
+}
+
+/// 包含该JAR或应用的启动序列名，按名称排序
+pub fn sequences_of(jar: &str) -> Vec<String> {
+    let app = app_name(instance::base(jar));
+    let Ok(entries) = fs::read_dir(SEQUENCE_DIR) else { return Vec::new() };
+    let mut names: Vec<String> = entries.flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "seq"))
+        .filter(|path| fs::read_to_string(path).is_ok_and(|content| {
+            content.lines().map(str::trim).any(|member| !member.is_empty() && app_name(member) == app)
+        }))
+        .filter_map(|path| Some(path.file_stem()?.to_string_lossy().to_string()))
+        .collect();
+    names.sort();
+    names
+}
+
+/// 把启动序列中的JAR改为应用名
+fn migrate_sequences(jar: &str, app: &str) {
+    let Ok(entries) = fs::read_dir(SEQUENCE_DIR) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(content) = fs::read_to_string(&path) else { continue };
+        let mut changed = false;
+        let lines: Vec<String> = content.lines()
+            .map(|line| {
+                let member = line.trim();
+                if !member.is_empty() && normalize_path(member) == jar {
+                    changed = true;
+                    app.to_string()
+                } else {
+                    line.to_string()
+                }
+            })
+            .collect();
+        if !changed {
+            continue;
+        }
+        match fs::write(&path, lines.join("\n") + "\n") {
+            Ok(_) => print_info!("已把序列 {} 中的 {} 改为应用 {}", path.display(), jar, app),
+            Err(e) => print_warn!("更新序列 {} 失败: {}", path.display(), e),
+        }
+    }
+}
+
+/// 取消注册应用，同时删除指向它的别名；配置和日志保留
+pub fn remove_app(name: &str) -> Result<(), String> {
+    let mut registry = Registry::load();
+    if registry.apps.remove(name).is_none() {
+        return Err(format!("未注册的应用: {}", name));
+    }
+    let aliases: Vec<String> = registry.aliases.iter().filter(|(_, target)| *target == name).map(|(alias, _)| alias.clone()).collect();
+    for alias in &aliases {
+        registry.aliases.remove(alias);
+    }
+    registry.save()?;
+    print_success!("已取消注册应用 {}", name.bright_cyan());
+    if !aliases.is_empty() {
+        println!("  同时删除别名: {}", aliases.join(", ").yellow());
+    }
+    Ok(())
+}
+
+/// 定义别名，目标可以是应用名或JAR文件名
+pub fn add_alias(alias: &str, target: &str) -> Result<(), String> {
+    validate_name(alias)?;
+    let mut registry = Registry::load();
+    if registry.apps.contains_key(alias) {
+        return Err(format!("{} 已是应用名", alias));
+    }
+    if Path::new(alias).exists() {
+        return Err(format!("当前目录已有同名文件: {}", alias));
+    }
+    let target = registry.aliases.get(target).cloned().unwrap_or_else(|| target.to_string());
+    if !registry.apps.contains_key(&target) && !Path::new(&target).is_file() {
+        return Err(format!("别名目标不存在: {} (需要是应用名或JAR文件)", target));
+    }
+    registry.aliases.insert(alias.to_string(), target.clone());
+    registry.save()?;
+    print_success!("已定义别名 {} → {}", alias.bright_cyan(), target.yellow());
+    Ok(())
+}
+
+pub fn remove_alias(alias: &str) -> Result<(), String> {
+    let mut registry = Registry::load();
+    if registry.aliases.remove(alias).is_none() {
+        return Err(format!("未定义的别名: {}", alias));
+    }
+    registry.save()?;
+    print_success!("已删除别名 {}", alias.bright_cyan());
+    Ok(())
+}
+
+/// 显示注册的应用和别名
+pub fn list_apps() {
+    let registry = Registry::load();
+    println!("\n{}", "=== 应用注册表 ===".bright_blue().bold());
+    if registry.apps.is_empty() {
+        println!("  {}", "无注册的应用".bright_black());
+    }
+    for (name, entry) in &registry.apps {
+        let status = if process::is_jar_running(name) { "运行中".green() } else { "已停止".red() };
+        let current = match resolve_entry(entry) {
+            Some(path) => path.display().to_string().cyan(),
+            None => "未找到JAR".red(),
+        };
+        let aliases: Vec<&str> = registry.aliases.iter().filter(|(_, target)| *target == name).map(|(alias, _)| alias.as_str()).collect();
+        println!("  {} [{}] {} → {}", format!("{:<16}", name).bright_cyan(), status, entry.jar.yellow(), current);
+        if !aliases.is_empty() {
+            println!("    别名: {}", aliases.join(", ").bright_black());
+        }
+    }
+    let jar_aliases: Vec<(&String, &String)> = registry.aliases.iter().filter(|(_, target)| !registry.apps.contains_key(*target)).collect();
+    if !jar_aliases.is_empty() {
+        println!("\n{}:", "JAR别名".bright_green());
+        for (alias, target) in jar_aliases {
+            println!("  {} → {}", alias.bright_cyan(), target.yellow());
+        }
+    }
+    println!("\n💡 使用 {} 注册应用，{} 定义别名",
+        "apps add <名称> <JAR或通配符>".cyan(), "apps alias <别名> <应用>".cyan());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_keys_for_cwd_jars_paths_and_instances() {
+        assert_eq!(state_key("api.jar"), "api");
+        assert_eq!(state_key("api.jar@2"), "api@2");
+        let hash = format!("{:x}", Sha256::digest("/opt/b/api.jar".as_bytes()));
+        let key = format!("api-{}", &hash[..8]);
+        assert_eq!(state_key("/opt/b/api.jar"), key);
+        assert_eq!(state_key("/opt/b/api.jar@w1"), format!("{}@w1", key));
+        assert_ne!(state_key("/opt/c/api.jar"), key);
+        // 计算状态名不写路径索引
+        assert!(!Path::new(PATH_INDEX_FILE).exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn normalizes_paths_lexically_and_keeps_symlinks() {
+        let dir = std::env::temp_dir().join(format!("manager_jar_registry_test_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("releases/v1")).unwrap();
+        fs::write(dir.join("releases/v1/app.jar"), b"").unwrap();
+        std::os::unix::fs::symlink(dir.join("releases/v1"), dir.join("current")).unwrap();
+
+        let root = dir.display().to_string();
+        assert_eq!(normalize_path(&format!("{}/current/app.jar", root)), format!("{}/current/app.jar", root));
+        assert_eq!(normalize_path(&format!("{}/current/../current/./app.jar", root)), format!("{}/current/app.jar", root));
+        // .. 按字面处理，不会先跟随链接到 releases/
+        assert_eq!(normalize_path(&format!("{}/current/../app.jar", root)), format!("{}/app.jar", root));
+        assert_eq!(normalize_path("./api.jar"), "api.jar");
+        assert_eq!(normalize_path("api.jar"), "api.jar");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn resolves_unregistered_names() {
+        assert_eq!(app_name("./api.jar"), "api.jar");
+        assert_eq!(app_name("api.jar@2"), "api.jar@2");
+        assert_eq!(app_for_key("api"), "api.jar");
+        assert_eq!(app_for_key("api@2"), "api.jar@2");
+    }
+}
@@ -0,0 +1,195 @@
+// runcopy.rs - 运行记录和不可变运行副本
+// 启动时记录JAR的 SHA-256；启用 run_copy 时从 .Manager_Jar/runtime/<hash>.jar 启动，覆盖原JAR不影响运行中的进程
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use crate::core::config::{GlobalConfig, JarOptions};
+use crate::core::{process, registry};
+
+const RUNTIME_DIR: &str = ".Manager_Jar/runtime";
+const RECORD_DIR: &str = ".Manager_Jar/data";
+/// 新建不久的副本不回收，避免删除其他JAR正在启动时准备的副本
+const GC_GRACE: Duration = Duration::from_secs(60);
+
+/// JAR最近一次启动的记录
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RunRecord {
+    /// 启动时JAR内容的 SHA-256
+    pub sha256: String,
+    /// 启动时JAR的大小
+    pub size: u64,
+    /// 启动时JAR的修改时间 (Unix 秒)
+    pub modified: u64,
+    /// 运行副本路径，未启用 run_copy 时为 None
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_copy: Option<String>,
+    /// 启动时间
+    pub started_at: String,
+    /// 本次启动分配的命名端口
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub ports: BTreeMap<String, u16>,
+}
+
+impl RunRecord {
+    fn file_path(jar: &str) -> PathBuf {
+        PathBuf::from(RECORD_DIR).join(format!("{}.run.toml", registry::state_key(jar)))
+    }
+
+    pub fn load(jar: &str) -> Option<Self> {
+        toml::from_str(&fs::read_to_string(Self::file_path(jar)).ok()?).ok()
+    }
+
+    pub fn save(&self, jar: &str) -> Result<(), String> {
+        fs::create_dir_all(RECORD_DIR).map_err(|e| format!("创建目录失败: {}", e))?;
+        let content = toml::to_string_pretty(self).map_err(|e| format!("序列化运行记录失败: {}", e))?;
+        fs::write(Self::file_path(jar), content).map_err(|e| format!("保存运行记录失败: {}", e))
+    }
+
+    /// java -jar 使用的路径
+    pub fn launch_path(&self, jar: &str) -> String {
+        self.run_copy.clone().unwrap_or_else(|| registry::jar_path(jar).display().to_string())
+    }
+
+    /// 短哈希，用于显示
+    pub fn short_hash(&self) -> &str {
+        &self.sha256[..12.min(self.sha256.len())]
+    }
+}
+
+/// JAR是否启用运行副本：JAR 选项优先，未设置时使用全局配置
+pub fn run_copy_enabled(jar: &str) -> bool {
+    JarOptions::load(jar).run_copy.unwrap_or_else(|| GlobalConfig::load().process.run_copy)
+}
+
+/// 计算文件的 SHA-256
+pub fn sha256_file(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// 文件的大小和修改时间 (Unix 秒)
+fn file_stamp(path: &Path) -> io::Result<(u64, u64)> {
+    let metadata = fs::metadata(path)?;
+    let modified = metadata.modified()?.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    Ok((metadata.len(), modified))
+}
+
+/// 启动前准备运行记录：计算哈希，启用 run_copy 时创建只读的运行副本
+/// 记录在启动成功后由调用方保存
+pub fn prepare_run(jar: &str) -> Result<RunRecord, String> {
+    let path = &registry::jar_path(jar);
+    let (size, modified) = file_stamp(path).map_err(|e| format!("无法读取 {}: {}", jar, e))?;
+    let sha256 = sha256_file(path).map_err(|e| format!("计算 {} 的哈希失败: {}", jar, e))?;
+    let mut record = RunRecord { sha256, size, modified, run_copy: None, started_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(), ports: BTreeMap::new() };
+    if !run_copy_enabled(jar) {
+        return Ok(record);
+    }
+
+    collect_garbage();
+    let copy = PathBuf::from(RUNTIME_DIR).join(format!("{}.jar", record.sha256));
+    if !copy.exists() {
+        fs::create_dir_all(RUNTIME_DIR).map_err(|e| format!("创建目录失败 {}: {}", RUNTIME_DIR, e))?;
+        // 先写临时文件再改名，中断时不会留下不完整的副本
+        let temp = copy.with_extension(format!("tmp-{}", std::process::id()));
+        fs::copy(path, &temp).map_err(|e| format!("创建运行副本失败: {}", e))?;
+        // 复制期间原JAR可能被替换，确认副本内容与哈希一致
+        if sha256_file(&temp).ok().as_deref() != Some(record.sha256.as_str()) {
+            let _ = fs::remove_file(&temp);
+            return Err(format!("{} 在复制期间被修改，请稍后重试", jar));
+        }
+        if let Ok(metadata) = fs::metadata(&temp) {
+            let mut permissions = metadata.permissions();
+            permissions.set_readonly(true);
+            let _ = fs::set_permissions(&temp, permissions);
+        }
+        fs::rename(&temp, &copy).map_err(|e| format!("创建运行副本失败: {}", e))?;
+    }
+    let _ = touch(&copy);
+    record.run_copy = Some(copy.display().to_string());
+    Ok(record)
+}
+
+/// 更新副本的修改时间，使刚用到的副本处于回收宽限期内
+fn touch(path: &Path) -> io::Result<()> {
+    File::options().append(true).open(path).or_else(|_| File::open(path))?.set_modified(SystemTime::now())
+}
+
+/// 磁盘上的JAR是否与运行中的版本不同
+pub fn jar_changed(jar: &str, record: &RunRecord) -> bool {
+    let path = registry::jar_path(jar);
+    match file_stamp(&path) {
+        // 大小和修改时间都没变时不计算哈希
+        Ok(stamp) if stamp == (record.size, record.modified) => false,
+        Ok(_) => sha256_file(&path).map(|hash| hash != record.sha256).unwrap_or(true),
+        Err(_) => true,
+    }
+}
+
+/// 在状态信息中显示运行版本，原JAR已被替换时提示重启
+pub fn print_status(jar: &str) {
+    let Some(record) = RunRecord::load(jar) else { return };
+    let source = match &record.run_copy {
+        Some(copy) => format!("运行副本 {}", copy),
+        None => "原JAR".to_string(),
+    };
+    println!("  运行版本: sha256 {} ({}，启动于 {})", record.short_hash().yellow(), source.bright_black(), record.started_at.bright_black());
+    if jar_changed(jar, &record) {
+        print_warn!("磁盘上的JAR与运行中的版本不同，需要重启才能生效");
+        if record.run_copy.is_none() {
+            println!("  {}", "JAR在运行中被覆盖可能导致 ClassNotFoundException，可启用 run_copy 避免".bright_black());
+        }
+    }
+}
+
+/// 运行中JAR的记录所引用的副本
+fn referenced_copies() -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(RECORD_DIR) else { return Vec::new() };
+    entries.flatten()
+        .filter_map(|entry| entry.file_name().to_str()?.strip_suffix(".run.toml").map(crate::core::registry::app_for_key))
+        .filter(|jar| process::get_pid(jar).is_some())
+        .filter_map(|jar| RunRecord::load(&jar)?.run_copy)
+        .map(PathBuf::from)
+        .collect()
+}
+
+/// 删除不再被运行中的JAR使用的副本，返回删除的数量
+pub fn collect_garbage() -> usize {
+    let Ok(entries) = fs::read_dir(RUNTIME_DIR) else { return 0 };
+    let referenced = referenced_copies();
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let recent = entry.metadata().ok()
+            .and_then(|m| m.modified().ok())
+            .and_then(|modified| modified.elapsed().ok())
+            .is_none_or(|age| age < GC_GRACE);
+        if recent || referenced.iter().any(|copy| copy == &path) {
+            continue;
+        }
+        // Windows 上只读文件需要先取消只读才能删除；仍在使用的副本会删除失败，留到下次
+        if let (true, Ok(metadata)) = (cfg!(target_os = "windows"), fs::metadata(&path)) {
+            let mut permissions = metadata.permissions();
+            #[allow(clippy::permissions_set_readonly_false)]
+            permissions.set_readonly(false);
+            let _ = fs::set_permissions(&path, permissions);
+        }
+        if fs::remove_file(&path).is_ok() {
+            removed += 1;
+        }
+    }
+    removed
+}
@@ -0,0 +1,500 @@
+// syslog.rs - syslog 转发
+// 日志管道将捕获的每行输出按 RFC 5424 格式发送到 syslog 接收端（UDP、TCP 或本地 Unix 套接字）
+// 接收端不可用时在内存中缓存并定期重连，恢复后按顺序补发
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::net::{TcpStream, UdpSocket};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use chrono::SecondsFormat;
+use regex::Regex;
+use crate::core::config::{GlobalConfig, JarOptions};
+use crate::core::pipeline::Stream;
+
+#[cfg(unix)]
+use std::os::unix::net::UnixDatagram;
+
+/// 重连间隔的上限
+const MAX_RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// TCP 连接超时
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// TCP 写超时，接收端不读取时不会卡住管道进程退出
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// UDP 和 Unix 数据报的最大长度，超出的消息截断发送
+const MAX_DATAGRAM_BYTES: usize = 8192;
+
+// syslog severity
+const SEVERITY_CRITICAL: u8 = 2;
+const SEVERITY_ERROR: u8 = 3;
+const SEVERITY_WARNING: u8 = 4;
+const SEVERITY_NOTICE: u8 = 5;
+const SEVERITY_INFO: u8 = 6;
+const SEVERITY_DEBUG: u8 = 7;
+
+/// syslog 接收端
+#[derive(Clone, Debug)]
+pub enum SyslogTarget {
+    Udp(String),
+    Tcp(String),
+    Unix(String),
+}
+
+impl SyslogTarget {
+    /// 解析地址: udp://主机:端口、tcp://主机:端口、unix:///路径，省略协议时为 UDP
+    pub fn parse(address: &str) -> Result<Self, String> {
+        let address = address.trim();
+        let target = if let Some(rest) = address.strip_prefix("udp://") {
+            SyslogTarget::Udp(rest.to_string())
+        } else if let Some(rest) = address.strip_prefix("tcp://") {
+            SyslogTarget::Tcp(rest.to_string())
+        } else if let Some(path) = address.strip_prefix("unix://") {
+            if cfg!(unix) {
+                return Ok(SyslogTarget::Unix(path.to_string()));
+            }
+            return Err("当前系统不支持 Unix 套接字".to_string());
+        } else if address.contains("://") {
+            return Err(format!("不支持的 syslog 协议: {} (可用: udp、tcp、unix)", address));
+        } else {
+            SyslogTarget::Udp(address.to_string())
+        };
+
+        match &target {
+            SyslogTarget::Udp(host) | SyslogTarget::Tcp(host) if !has_port(host) => {
+                Err(format!("syslog 地址缺少端口: {}", address))
+            },
+            _ => Ok(target),
+        }
+    }
+
+    /// 为JAR解析转发目标：单个JAR的选项优先，未设置时使用全局配置
+    pub fn for_jar(jar: &str, config: &GlobalConfig) -> Option<Result<Self, String>> {
+        match JarOptions::load(jar).syslog.as_deref() {
+            Some("off") => None,
+            Some(address) => Some(Self::parse(address)),
+            None if config.syslog.enabled => Some(Self::parse(&config.syslog.address)),
+            None => None,
+        }
+    }
+}
+
+impl std::fmt::Display for SyslogTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SyslogTarget::Udp(host) => write!(f, "udp://{}", host),
+            SyslogTarget::Tcp(host) => write!(f, "tcp://{}", host),
+            SyslogTarget::Unix(path) => write!(f, "unix://{}", path),
+        }
+    }
+}
+
+/// 地址是否带端口: 主机:端口 或 [IPv6]:端口
+fn has_port(host: &str) -> bool {
+    let port = match host.strip_prefix('[') {
+        Some(rest) => rest.split_once("]:").map(|(_, port)| port),
+        None => host.rsplit_once(':').map(|(_, port)| port),
+    };
+    port.is_some_and(|port| !port.is_empty())
+}
+
+/// 到接收端的连接
+enum Connection {
+    Udp(UdpSocket),
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(UnixDatagram),
+}
+
+impl Connection {
+    fn open(target: &SyslogTarget) -> io::Result<Self> {
+        match target {
+            SyslogTarget::Udp(host) => {
+                let socket = UdpSocket::bind(if host.starts_with('[') { "[::]:0" } else { "0.0.0.0:0" })?;
+                socket.connect(host)?;
+                Ok(Connection::Udp(socket))
+            },
+            SyslogTarget::Tcp(host) => {
+                let mut last_error = io::Error::new(io::ErrorKind::NotFound, format!("无法解析地址: {}", host));
+                for addr in std::net::ToSocketAddrs::to_socket_addrs(host)? {
+                    match TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT) {
+                        Ok(stream) => {
+                            stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+                            return Ok(Connection::Tcp(stream));
+                        },
+                        Err(e) => last_error = e,
+                    }
+                }
+                Err(last_error)
+            },
+            #[cfg(unix)]
+            SyslogTarget::Unix(path) => {
+                let socket = UnixDatagram::unbound()?;
+                socket.connect(path)?;
+                Ok(Connection::Unix(socket))
+            },
+            #[cfg(not(unix))]
+            SyslogTarget::Unix(_) => Err(io::Error::new(io::ErrorKind::Unsupported, "当前系统不支持 Unix 套接字")),
+        }
+    }
+
+    fn send(&mut self, message: &[u8]) -> io::Result<()> {
+        match self {
+            Connection::Udp(socket) => socket.send(truncate_datagram(message)).map(|_| ()),
+            // TCP 使用 RFC 6587 的长度前缀分帧
+            Connection::Tcp(stream) => {
+                let mut frame = format!("{} ", message.len()).into_bytes();
+                frame.extend_from_slice(message);
+                stream.write_all(&frame)
+            },
+            #[cfg(unix)]
+            Connection::Unix(socket) => socket.send(truncate_datagram(message)).map(|_| ()),
+        }
+    }
+}
+
+/// 截断到数据报的最大长度，不拆开 UTF-8 字符
+fn truncate_datagram(message: &[u8]) -> &[u8] {
+    if message.len() <= MAX_DATAGRAM_BYTES {
+        return message;
+    }
+    let mut end = MAX_DATAGRAM_BYTES;
+    while end > 0 && message[end] & 0xC0 == 0x80 {
+        end -= 1;
+    }
+    &message[..end]
+}
+
+/// 接收端不可用一类的错误，重连后可以重发；其他错误 (如消息过长) 重发也不会成功
+fn is_transient(error: &io::Error) -> bool {
+    matches!(error.kind(),
+        io::ErrorKind::ConnectionRefused | io::ErrorKind::ConnectionReset | io::ErrorKind::ConnectionAborted
+        | io::ErrorKind::NotConnected | io::ErrorKind::BrokenPipe | io::ErrorKind::TimedOut
+        | io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted | io::ErrorKind::NotFound
+        | io::ErrorKind::AddrNotAvailable | io::ErrorKind::WriteZero | io::ErrorKind::UnexpectedEof)
+}
+
+/// 发送队列
+struct Queue {
+    messages: VecDeque<Vec<u8>>,
+    dropped: u64,
+    closed: bool,
+    /// 后台线程已取出一条消息正在发送
+    sending: bool,
+    /// 关闭等待超时，后台线程不再重试
+    abandoned: bool,
+}
+
+/// 带缓存和自动重连的 syslog 发送端，在后台线程中发送
+pub struct SyslogSink {
+    queue: Arc<(Mutex<Queue>, Condvar)>,
+    capacity: usize,
+    facility: u8,
+    hostname: String,
+    app_name: String,
+    proc_id: Mutex<String>,
+    level: Regex,
+    worker: Mutex<Option<thread::JoinHandle<()>>>,
+}
+
+impl SyslogSink {
+    /// 创建发送端并启动后台发送线程
+    pub fn start(target: SyslogTarget, jar: &str, config: &GlobalConfig) -> Result<Arc<Self>, String> {
+        let facility = parse_facility(&config.syslog.facility)?;
+        let queue = Arc::new((Mutex::new(Queue { messages: VecDeque::new(), dropped: 0, closed: false, sending: false, abandoned: false }), Condvar::new()));
+        let worker_queue = Arc::clone(&queue);
+        let worker = thread::spawn(move || send_loop(target, worker_queue));
+
+        Ok(Arc::new(Self {
+            queue,
+            capacity: config.syslog.buffer_lines.max(1),
+            facility,
+            hostname: header_field(&crate::utils::system::local_hostname(), 255),
+            app_name: header_field(jar.trim_end_matches(".jar"), 48),
+            proc_id: Mutex::new("-".to_string()),
+            level: Regex::new(r"\b(FATAL|SEVERE|ERROR|WARN(?:ING)?|INFO|DEBUG|TRACE|FINE[RS]*T?)\b").unwrap(),
+            worker: Mutex::new(Some(worker)),
+        }))
+    }
+
+    /// 设置 PROCID 字段（被转发进程的PID）
+    pub fn set_proc_id(&self, pid: u32) {
+        if let Ok(mut proc_id) = self.proc_id.lock() {
+            *proc_id = pid.to_string();
+        }
+    }
+
+    /// 转发一行日志，队列已满时丢弃最旧的一行
+    pub fn send(&self, stream: Stream, line: &str) {
+        let message = self.format_message(stream, line);
+        let (lock, condvar) = &*self.queue;
+        if let Ok(mut queue) = lock.lock() {
+            if queue.messages.len() >= self.capacity {
+                queue.messages.pop_front();
+                queue.dropped += 1;
+            }
+            queue.messages.push_back(message.into_bytes());
+            condvar.notify_one();
+        }
+    }
+
+    /// 停止接收新日志，最多等待 timeout 把缓存发送完，返回未能发送的行数（含缓存溢出丢弃的）
+    pub fn close(&self, timeout: Duration) -> usize {
+        let (lock, condvar) = &*self.queue;
+        let deadline = Instant::now() + timeout;
+        if let Ok(mut queue) = lock.lock() {
+            queue.closed = true;
+            condvar.notify_all();
+            while (!queue.messages.is_empty() || queue.sending) && Instant::now() < deadline {
+                queue = match condvar.wait_timeout(queue, Duration::from_millis(100)) {
+                    Ok((queue, _)) => queue,
+                    Err(_) => return 0,
+                };
+            }
+            queue.abandoned = true;
+            condvar.notify_all();
+        }
+        // 后台线程放回未发送成功的消息后退出，之后再统计
+        if let Some(worker) = self.worker.lock().ok().and_then(|mut worker| worker.take()) {
+            worker.join().ok();
+        }
+        match lock.lock() {
+            Ok(mut queue) => {
+                let remaining = queue.messages.len() + queue.dropped as usize;
+                queue.messages.clear();
+                remaining
+            },
+            Err(_) => 0,
+        }
+    }
+
+    /// 按 RFC 5424 格式化: `<PRI>1 时间 主机 应用 PROCID MSGID - 消息`
+    fn format_message(&self, stream: Stream, line: &str) -> String {
+        let severity = self.infer_severity(stream, line);
+        let proc_id = self.proc_id.lock().map(|p| p.clone()).unwrap_or_else(|_| "-".to_string());
+        format!("<{}>1 {} {} {} {} {} - {}",
+                self.facility as u16 * 8 + severity as u16,
+                chrono::Local::now().to_rfc3339_opts(SecondsFormat::Micros, false),
+                self.hostname,
+                self.app_name,
+                proc_id,
+                stream.tag(),
+                line)
+    }
+
+    /// 根据行内的日志级别推断 severity，没有级别时 stderr 为 notice，其余为 info
+    fn infer_severity(&self, stream: Stream, line: &str) -> u8 {
+        let head_end = line.char_indices().nth(200).map_or(line.len(), |(i, _)| i);
+        match self.level.find(&line[..head_end]).map(|m| m.as_str()) {
+            Some("FATAL") => SEVERITY_CRITICAL,
+            Some("SEVERE") | Some("ERROR") => SEVERITY_ERROR,
+            Some("WARN") | Some("WARNING") => SEVERITY_WARNING,
+            Some("INFO") => SEVERITY_INFO,
+            Some(_) => SEVERITY_DEBUG,
+            None if stream == Stream::Err => SEVERITY_NOTICE,
+            None => SEVERITY_INFO,
+        }
+    }
+}
+
+/// 后台发送循环：连接失败或接收端不可用时保留消息，按指数退避重连；无法发送的消息丢弃
+fn send_loop(target: SyslogTarget, queue: Arc<(Mutex<Queue>, Condvar)>) {
+    let (lock, condvar) = &*queue;
+    let mut connection: Option<Connection> = None;
+    let mut retry_interval = Duration::from_secs(1);
+
+    loop {
+        // 在锁内取出队首消息，接收端暂时不可用时再放回队首；队列溢出时 send 丢弃的总是未取出的消息
+        let message = {
+            let mut guard = match lock.lock() {
+                Ok(guard) => guard,
+                Err(_) => return,
+            };
+            while guard.messages.is_empty() && !guard.closed {
+                guard = match condvar.wait(guard) {
+                    Ok(guard) => guard,
+                    Err(_) => return,
+                };
+            }
+            if guard.abandoned {
+                return;
+            }
+            match guard.messages.pop_front() {
+                Some(message) => {
+                    guard.sending = true;
+                    message
+                },
+                None => return,
+            }
+        };
+
+        if connection.is_none() {
+            connection = Connection::open(&target).ok();
+        }
+        let result = connection.as_mut().map(|conn| conn.send(&message));
+        let delivered = match &result {
+            Some(Ok(())) => Some(true),
+            Some(Err(e)) if !is_transient(e) => Some(false),
+            _ => None,
+        };
+        let Ok(mut guard) = lock.lock() else { return };
+        guard.sending = false;
+        if let Some(delivered) = delivered {
+            retry_interval = Duration::from_secs(1);
+            if !delivered {
+                guard.dropped += 1;
+            }
+            if guard.messages.is_empty() {
+                condvar.notify_all();
+            }
+            continue;
+        }
+
+        // 接收端不可用，放回队首等待后重连；关闭时由 close 的超时决定放弃
+        guard.messages.push_front(message);
+        connection = None;
+        if guard.abandoned {
+            return;
+        }
+        let _ = condvar.wait_timeout(guard, retry_interval);
+        retry_interval = (retry_interval * 2).min(MAX_RETRY_INTERVAL);
+    }
+}
+
+/// 发送一条测试消息，用于检查接收端配置
+pub fn send_test_message(jar: &str) -> Result<(), String> {
+    let config = GlobalConfig::load();
+    let target = match SyslogTarget::for_jar(jar, &config) {
+        Some(target) => target?,
+        None => return Err(format!("{} 未启用 syslog 转发", jar)),
+    };
+    let sink = SyslogSink::start(target.clone(), jar, &config)?;
+    sink.send(Stream::Sys, &format!("Manager_Jar syslog 测试消息 ({})", jar));
+    if sink.close(CONNECT_TIMEOUT) > 0 {
+        return Err(format!("无法发送到 {}", target));
+    }
+    print_success!("已向 {} 发送测试消息", target.to_string().cyan());
+    Ok(())
+}
+
+/// facility 名称或数字转为代码
+fn parse_facility(name: &str) -> Result<u8, String> {
+    const NAMES: &[&str] = &[
+        "kern", "user", "mail", "daemon", "auth", "syslog", "lpr", "news",
+        "uucp", "cron", "authpriv", "ftp", "ntp", "security", "console", "solaris-cron",
+        "local0", "local1", "local2", "local3", "local4", "local5", "local6", "local7",
+    ];
+    let name = name.trim().to_lowercase();
+    if let Ok(code) = name.parse::<u8>() {
+        if (code as usize) < NAMES.len() {
+            return Ok(code);
+        }
+    }
+    NAMES.iter()
+        .position(|n| *n == name)
+        .map(|code| code as u8)
+        .ok_or_else(|| format!("未知的 syslog facility: {}", name))
+}
+
+/// RFC 5424 头部字段只允许可打印 ASCII，空值用 "-"
+fn header_field(value: &str, max_len: usize) -> String {
+    let field: String = value.chars()
+        .filter(|c| c.is_ascii_graphic())
+        .take(max_len)
+        .collect();
+    if field.is_empty() { "-".to_string() } else { field }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn start(target: SyslogTarget) -> Arc<SyslogSink> {
+        SyslogSink::start(target, "demo.jar", &GlobalConfig::default()).unwrap()
+    }
+
+    fn receive(socket: impl Fn(&mut [u8]) -> io::Result<usize>) -> String {
+        let mut buffer = vec![0u8; 65536];
+        let len = socket(&mut buffer).unwrap();
+        String::from_utf8(buffer[..len].to_vec()).unwrap()
+    }
+
+    #[test]
+    fn parses_targets() {
+        assert!(matches!(SyslogTarget::parse("127.0.0.1:514"), Ok(SyslogTarget::Udp(_))));
+        assert!(matches!(SyslogTarget::parse("tcp://logs:6514"), Ok(SyslogTarget::Tcp(_))));
+        assert!(matches!(SyslogTarget::parse("udp://[::1]:514"), Ok(SyslogTarget::Udp(_))));
+        assert!(SyslogTarget::parse("tcp://logs").is_err());
+        assert!(SyslogTarget::parse("[::1]").is_err());
+        assert!(SyslogTarget::parse("udp://[::1]:").is_err());
+        assert!(SyslogTarget::parse("http://logs:80").is_err());
+    }
+
+    #[test]
+    fn sends_to_udp_listener_and_truncates_oversized_lines() {
+        let listener = UdpSocket::bind("127.0.0.1:0").unwrap();
+        listener.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let sink = start(SyslogTarget::Udp(listener.local_addr().unwrap().to_string()));
+        sink.set_proc_id(42);
+        sink.send(Stream::Err, &"x".repeat(100_000));
+        sink.send(Stream::Out, "2024-05-01 10:00:00 ERROR failed");
+        assert_eq!(sink.close(Duration::from_secs(5)), 0);
+
+        let oversized = receive(|buffer| listener.recv(buffer));
+        assert_eq!(oversized.len(), MAX_DATAGRAM_BYTES);
+        assert!(oversized.starts_with("<13>1 "));
+        let message = receive(|buffer| listener.recv(buffer));
+        assert!(message.starts_with("<11>1 "));
+        assert!(message.ends_with(" demo 42 out - 2024-05-01 10:00:00 ERROR failed"));
+    }
+
+    #[test]
+    fn overflow_never_duplicates_or_skips_the_line_in_flight() {
+        let listener = UdpSocket::bind("127.0.0.1:0").unwrap();
+        listener.set_read_timeout(Some(Duration::from_millis(500))).unwrap();
+        let mut config = GlobalConfig::default();
+        config.syslog.buffer_lines = 4;
+        let sink = SyslogSink::start(SyslogTarget::Udp(listener.local_addr().unwrap().to_string()), "demo.jar", &config).unwrap();
+        for i in 0..200 {
+            sink.send(Stream::Out, &format!("line {}", i));
+        }
+        let unsent = sink.close(Duration::from_secs(5));
+
+        let mut received = Vec::new();
+        let mut buffer = vec![0u8; 65536];
+        while let Ok(len) = listener.recv(&mut buffer) {
+            let message = String::from_utf8_lossy(&buffer[..len]).to_string();
+            received.push(message.rsplit(' ').next().unwrap().parse::<u32>().unwrap());
+        }
+        assert!(received.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", received);
+        assert_eq!(received.last(), Some(&199));
+        assert_eq!(received.len() + unsent, 200);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn sends_to_unix_listener() {
+        let path = std::env::temp_dir().join(format!("manager_jar_syslog_test_{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixDatagram::bind(&path).unwrap();
+        listener.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let sink = start(SyslogTarget::Unix(path.display().to_string()));
+        sink.send(Stream::Sys, "hello");
+        assert_eq!(sink.close(Duration::from_secs(5)), 0);
+
+        let message = receive(|buffer| listener.recv(buffer));
+        assert!(message.starts_with("<14>1 "));
+        assert!(message.ends_with(" demo - sys - hello"));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn truncates_on_char_boundary() {
+        let message = "中".repeat(MAX_DATAGRAM_BYTES);
+        let truncated = truncate_datagram(message.as_bytes());
+        assert!(truncated.len() <= MAX_DATAGRAM_BYTES);
+        assert!(std::str::from_utf8(truncated).is_ok());
+    }
+}
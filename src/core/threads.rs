@@ -0,0 +1,540 @@
+// threads.rs - 线程转储分析
+// 通过 jcmd Thread.print 或 SIGQUIT 获取 HotSpot 线程转储，统计线程状态，检测死锁和锁等待链，
+// 并结合 /proc/<pid>/task/*/stat 的 CPU 时间找出最繁忙的线程
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant};
+use colored::Colorize;
+use regex::Regex;
+use crate::core::config::{GlobalConfig, JarOptions};
+use crate::core::{logging, process};
+
+const DUMP_DIR: &str = ".Manager_Jar/dumps";
+/// 等待 SIGQUIT 触发的转储写入日志的最长时间
+const SIGQUIT_TIMEOUT: Duration = Duration::from_secs(10);
+/// 无法查询 CLK_TCK 时使用的默认值 (Linux 上通常为 100)
+const DEFAULT_CLOCK_TICKS_PER_SEC: f64 = 100.0;
+
+/// `threads` 命令选项
+pub struct ThreadsOptions {
+    /// 转储次数
+    pub count: u32,
+    /// 两次转储之间的间隔 (秒)
+    pub interval: u64,
+    /// 显示最繁忙的前N个线程
+    pub top: usize,
+    /// 每个线程显示的栈帧数
+    pub depth: usize,
+}
+
+/// 线程转储中的一个线程
+struct JavaThread {
+    name: String,
+    /// 本地线程ID (Linux 上即 /proc/<pid>/task 下的 tid)
+    nid: Option<u64>,
+    /// 转储中记录的累计 CPU 时间 (毫秒, JDK 11+)
+    cpu_ms: Option<f64>,
+    state: String,
+    frames: Vec<String>,
+    /// 持有的锁 (地址)
+    locked: Vec<String>,
+    /// 正在等待获取的锁 (地址, 类型)
+    waiting_for: Option<(String, String)>,
+}
+
+/// 获取线程转储：优先使用 jcmd，不可用时向进程发送 SIGQUIT 并从日志中提取
+pub fn capture_thread_dump(jar: &str, pid: u32) -> Result<String, String> {
+    let jcmd_error = match process::jcmd(jar, pid, &["Thread.print", "-l"]) {
+        Ok(dump) => return Ok(dump),
+        Err(e) => e,
+    };
+
+    if cfg!(unix) {
+        sigquit_dump(jar, pid).map_err(|e| format!("{}; SIGQUIT: {}", jcmd_error, e))
+    } else {
+        Err(jcmd_error)
+    }
+}
+
+/// 发送 SIGQUIT，JVM 把线程转储写到标准输出（即受管理的日志），再从日志新增的部分中提取
+fn sigquit_dump(jar: &str, pid: u32) -> Result<String, String> {
+    // -Xrs 时 JVM 不处理 SIGQUIT，信号会直接终止进程
+    if let Ok(cmdline) = fs::read(format!("/proc/{}/cmdline", pid)) {
+        if cmdline.split(|&b| b == 0).any(|arg| arg == b"-Xrs") {
+            return Err("进程使用了 -Xrs，无法通过 SIGQUIT 获取线程转储".to_string());
+        }
+    }
+
+    let config = GlobalConfig::load();
+    let log_file = config.get_jar_log_files(jar).into_iter().next().ok_or("找不到日志文件")?;
+    let encoding = JarOptions::load(jar).encoding();
+    let start = fs::metadata(&log_file).map(|m| m.len()).unwrap_or(0);
+
+    let status = Command::new("kill").args(["-QUIT", &pid.to_string()]).status()
+        .map_err(|e| format!("发送信号失败: {}", e))?;
+    if !status.success() {
+        return Err("发送信号失败".to_string());
+    }
+
+    let deadline = Instant::now() + SIGQUIT_TIMEOUT;
+    let mut started = false;
+    while Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(200));
+        let lines = read_appended_lines(Path::new(&log_file), start, encoding)?;
+        started = started || lines.iter().any(|l| l.starts_with("Full thread dump"));
+        if let Some(dump) = extract_dump(&lines) {
+            return Ok(dump);
+        }
+    }
+    Err(if started { "线程转储未在日志中写完".to_string() } else { format!("{} 中未出现线程转储", log_file) })
+}
+
+/// 读取日志从 offset 开始新写入的行，去掉日志管道前缀
+fn read_appended_lines(path: &Path, offset: u64, encoding: &'static encoding_rs::Encoding) -> Result<Vec<String>, String> {
+    let mut file = fs::File::open(path).map_err(|e| format!("读取日志失败: {}", e))?;
+    let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+    if len < offset {
+        return Err("日志在转储期间发生了轮转".to_string());
+    }
+    file.seek(SeekFrom::Start(offset)).map_err(|e| format!("读取日志失败: {}", e))?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes).map_err(|e| format!("读取日志失败: {}", e))?;
+    Ok(bytes.split(|&b| b == b'\n')
+        .map(|line| logging::decode_log_line(line, encoding))
+        .map(|line| logging::strip_line_prefix(line.trim_end_matches('\r')).to_string())
+        .collect())
+}
+
+/// 从 "Full thread dump" 截取到 "JNI global refs"（其后可能还有 JVM 的死锁报告）
+fn extract_dump(lines: &[String]) -> Option<String> {
+    let start = lines.iter().position(|l| l.starts_with("Full thread dump"))?;
+    let end = start + lines[start..].iter().position(|l| l.starts_with("JNI global refs"))?;
+    // 死锁报告紧随其后，以 "Found N deadlock" 结束
+    let mut last = end;
+    if lines.get(end + 2).is_some_and(|l| l.starts_with("Found one Java-level deadlock")) {
+        if let Some(found) = lines[end..].iter().position(|l| l.starts_with("Found ") && l.contains("deadlock") && l.ends_with('.')) {
+            last = end + found;
+        }
+    }
+    Some(lines[start..=last].join("\n"))
+}
+
+/// 解析 HotSpot 线程转储
+fn parse_dump(dump: &str) -> Vec<JavaThread> {
+    let nid_re = Regex::new(r"\bnid=(0x[0-9a-fA-F]+|\d+)").unwrap();
+    let cpu_re = Regex::new(r"\bcpu=([\d.]+)(ms|s)\b").unwrap();
+    let lock_re = Regex::new(r"<(0x[0-9a-fA-F]+)>(?:\s*\(a ([^)]+)\))?").unwrap();
+
+    let mut threads: Vec<JavaThread> = Vec::new();
+    let mut in_synchronizers = false;
+    for line in dump.lines() {
+        // 之后是 JVM 自带的死锁报告，其中的线程名会与前面重复
+        if line.starts_with("JNI global refs") || line.starts_with("Found one Java-level deadlock") {
+            break;
+        }
+        if let Some(rest) = line.strip_prefix('"') {
+            let name = rest.find("\" ").or_else(|| rest.rfind('"')).map_or(rest, |end| &rest[..end]);
+            let nid = nid_re.captures(line).and_then(|c| {
+                let text = &c[1];
+                match text.strip_prefix("0x") {
+                    Some(hex) => u64::from_str_radix(hex, 16).ok(),
+                    None => text.parse().ok(),
+                }
+            });
+            let cpu_ms = cpu_re.captures(line).and_then(|c| {
+                let value: f64 = c[1].parse().ok()?;
+                Some(if &c[2] == "s" { value * 1000.0 } else { value })
+            });
+            // 没有 Thread.State 行的是 JVM 内部线程 (GC、编译线程等)
+            threads.push(JavaThread {
+                name: name.to_string(),
+                nid,
+                cpu_ms,
+                state: "VM".to_string(),
+                frames: Vec::new(),
+                locked: Vec::new(),
+                waiting_for: None,
+            });
+            in_synchronizers = false;
+            continue;
+        }
+
+        let current = match threads.last_mut() {
+            Some(current) => current,
+            None => continue,
+        };
+        let trimmed = line.trim();
+        if let Some(state) = trimmed.strip_prefix("java.lang.Thread.State:") {
+            current.state = state.split_whitespace().next().unwrap_or("UNKNOWN").to_string();
+        } else if trimmed.starts_with("at ") {
+            current.frames.push(trimmed.to_string());
+        } else if trimmed.starts_with("Locked ownable synchronizers:") {
+            in_synchronizers = true;
+        } else if let Some(lock) = trimmed.strip_prefix("- ") {
+            let captures = match lock_re.captures(lock) {
+                Some(captures) => captures,
+                None => continue,
+            };
+            let address = captures[1].to_string();
+            let class = captures.get(2).map_or("", |m| m.as_str()).to_string();
+            if in_synchronizers || lock.starts_with("locked ") {
+                current.locked.push(address);
+            } else if lock.starts_with("waiting to lock ") || lock.starts_with("parking to wait for ") {
+                current.waiting_for = Some((address, class));
+            }
+        }
+    }
+    threads
+}
+
+/// 每个线程等待的锁的持有者: 线程下标 -> (持有者下标, 锁地址, 锁类型)
+fn lock_waits(threads: &[JavaThread]) -> HashMap<usize, (usize, String, String)> {
+    let mut owners: HashMap<&str, usize> = HashMap::new();
+    for (i, t) in threads.iter().enumerate() {
+        for address in &t.locked {
+            owners.insert(address.as_str(), i);
+        }
+    }
+    threads.iter().enumerate()
+        .filter_map(|(i, t)| {
+            let (address, class) = t.waiting_for.as_ref()?;
+            let owner = *owners.get(address.as_str())?;
+            (owner != i).then(|| (i, (owner, address.clone(), class.clone())))
+        })
+        .collect()
+}
+
+/// 在等待关系中查找环 (死锁)，每个环按线程下标返回
+fn find_deadlocks(waits: &HashMap<usize, (usize, String, String)>) -> Vec<Vec<usize>> {
+    let mut cycles: Vec<Vec<usize>> = Vec::new();
+    let mut starts: Vec<usize> = waits.keys().copied().collect();
+    starts.sort();
+    for start in starts {
+        let mut path = vec![start];
+        let mut current = start;
+        while let Some((owner, _, _)) = waits.get(&current) {
+            if let Some(pos) = path.iter().position(|&t| t == *owner) {
+                let mut cycle = path[pos..].to_vec();
+                // 同一个环只记录一次
+                let min_pos = cycle.iter().enumerate().min_by_key(|(_, &t)| t).map_or(0, |(i, _)| i);
+                cycle.rotate_left(min_pos);
+                if !cycles.contains(&cycle) {
+                    cycles.push(cycle);
+                }
+                break;
+            }
+            path.push(*owner);
+            current = *owner;
+        }
+    }
+    cycles
+}
+
+/// /proc/<pid>/task/*/stat 中 CPU 时间的单位：sysconf(_SC_CLK_TCK)，通过 getconf 查询
+fn clock_ticks_per_sec() -> f64 {
+    Command::new("getconf").arg("CLK_TCK").output().ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8_lossy(&output.stdout).trim().parse::<f64>().ok())
+        .filter(|ticks| *ticks > 0.0)
+        .unwrap_or(DEFAULT_CLOCK_TICKS_PER_SEC)
+}
+
+/// 线程转储文件名: <JAR>-threads-<YYYYmmdd-HHMMSS>.txt，同一秒内多次转储时加序号
+fn dump_file_path(jar: &str, stamp: &chrono::DateTime<chrono::Local>) -> PathBuf {
+    let prefix = format!("{}-threads-{}", crate::core::registry::state_key(jar), stamp.format("%Y%m%d-%H%M%S"));
+    let mut candidate = PathBuf::from(DUMP_DIR).join(format!("{}.txt", prefix));
+    let mut seq = 1;
+    while candidate.exists() {
+        candidate = PathBuf::from(DUMP_DIR).join(format!("{}-{}.txt", prefix, seq));
+        seq += 1;
+    }
+    candidate
+}
+
+/// 读取进程各线程的累计 CPU 时间 (tid -> 时钟滴答数)
+fn cpu_sample(pid: u32) -> Option<HashMap<u64, u64>> {
+    let entries = fs::read_dir(format!("/proc/{}/task", pid)).ok()?;
+    let mut sample = HashMap::new();
+    for entry in entries.flatten() {
+        let tid: u64 = match entry.file_name().to_string_lossy().parse() {
+            Ok(tid) => tid,
+            Err(_) => continue,
+        };
+        let stat = match fs::read_to_string(entry.path().join("stat")) {
+            Ok(stat) => stat,
+            Err(_) => continue,
+        };
+        // 线程名在括号中且可能含空格，从最后一个 ')' 之后按空格分隔，utime/stime 为第 14、15 个字段
+        let fields: Vec<&str> = stat.rsplit_once(')').map_or(Vec::new(), |(_, rest)| rest.split_whitespace().collect());
+        if let (Some(utime), Some(stime)) = (fields.get(11), fields.get(12)) {
+            sample.insert(tid, utime.parse::<u64>().unwrap_or(0) + stime.parse::<u64>().unwrap_or(0));
+        }
+    }
+    Some(sample)
+}
+
+/// 对JAR进程做一次或多次线程转储并分析
+pub fn analyze_threads(jar: &str, options: &ThreadsOptions) -> Result<(), String> {
+    let pid = process::get_pid(jar).ok_or_else(|| format!("{} 未在运行", jar))?;
+    fs::create_dir_all(DUMP_DIR).map_err(|e| format!("创建目录失败 {}: {}", DUMP_DIR, e))?;
+
+    let count = options.count.max(1);
+    let mut previous = cpu_sample(pid);
+    let mut sampled_at = Instant::now();
+    let mut history: Vec<Vec<JavaThread>> = Vec::new();
+    for round in 1..=count {
+        // 第一次转储前采样 1 秒的 CPU 时间，之后以两次转储的间隔为采样窗口
+        if round > 1 {
+            thread::sleep(Duration::from_secs(options.interval));
+        } else if previous.is_some() {
+            thread::sleep(Duration::from_secs(1));
+        }
+        let current = cpu_sample(pid);
+        let window = sampled_at.elapsed();
+        sampled_at = Instant::now();
+
+        let dump = capture_thread_dump(jar, pid)?;
+        let stamp = chrono::Local::now();
+        let dump_file = dump_file_path(jar, &stamp);
+        fs::write(&dump_file, &dump).map_err(|e| format!("保存线程转储失败: {}", e))?;
+
+        let threads = parse_dump(&dump);
+        if threads.is_empty() {
+            return Err(format!("无法解析线程转储，原始内容已保存到 {}", dump_file.display()));
+        }
+
+        println!("\n{}", format!("=== {} 线程转储 #{}/{} ({}) ===", jar, round, count, stamp.format("%H:%M:%S")).bright_blue().bold());
+        println!("  进程ID: {}  线程数: {}  转储文件: {}", pid.to_string().bright_green(), threads.len().to_string().yellow(), dump_file.display().to_string().cyan());
+
+        let cpu_delta = match (&previous, &current) {
+            (Some(before), Some(after)) => Some(after.iter()
+                .map(|(tid, ticks)| (*tid, ticks.saturating_sub(*before.get(tid).unwrap_or(ticks))))
+                .collect::<HashMap<u64, u64>>()),
+            _ => None,
+        };
+        print_states(&threads);
+        print_lock_analysis(&threads);
+        print_hot_threads(&threads, cpu_delta.as_ref(), window, options);
+
+        history.push(threads);
+        previous = current;
+        if round < count {
+            print_info!("{} 秒后进行下一次转储...", options.interval);
+        }
+    }
+
+    if history.len() > 1 {
+        print_stuck_threads(&history, options.depth);
+    }
+    Ok(())
+}
+
+fn print_states(threads: &[JavaThread]) {
+    let mut counts: Vec<(&str, usize)> = Vec::new();
+    for t in threads {
+        match counts.iter_mut().find(|(state, _)| *state == t.state) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((&t.state, 1)),
+        }
+    }
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    let summary: Vec<String> = counts.iter().map(|(state, count)| format!("{} {}", color_state(state), count)).collect();
+    println!("  线程状态: {}", summary.join(", "));
+}
+
+fn print_lock_analysis(threads: &[JavaThread]) {
+    let waits = lock_waits(threads);
+    let deadlocks = find_deadlocks(&waits);
+    let lock_text = |i: &usize| {
+        let (owner, address, class) = &waits[i];
+        format!("\"{}\" 等待 <{}> ({}) → 持有者 \"{}\"", threads[*i].name, address, class, threads[*owner].name)
+    };
+
+    if deadlocks.is_empty() {
+        println!("  死锁: {}", "未检测到".green());
+    } else {
+        print_error!("检测到 {} 处死锁:", deadlocks.len());
+        for (n, cycle) in deadlocks.iter().enumerate() {
+            println!("  {}", format!("死锁 #{}", n + 1).red().bold());
+            for i in cycle {
+                println!("    {}", lock_text(i).red());
+                if let Some(frame) = threads[*i].frames.first() {
+                    println!("        {}", frame.bright_black());
+                }
+            }
+        }
+    }
+
+    // 锁等待链：从没有被其他线程等待的线程开始，沿持有者追溯
+    let in_deadlock = |i: usize| deadlocks.iter().any(|cycle| cycle.contains(&i));
+    let waited_on: Vec<usize> = waits.values().map(|(owner, _, _)| *owner).collect();
+    let mut heads: Vec<usize> = waits.keys().copied().filter(|i| !waited_on.contains(i) && !in_deadlock(*i)).collect();
+    heads.sort();
+    if heads.is_empty() {
+        return;
+    }
+    println!("  锁等待链:");
+    for head in heads {
+        let mut chain = vec![format!("\"{}\"", threads[head].name)];
+        let mut current = head;
+        let mut visited = vec![head];
+        while let Some((owner, address, _)) = waits.get(&current) {
+            chain.push(format!("<{}>", address).bright_black().to_string());
+            chain.push(format!("\"{}\"", threads[*owner].name));
+            if visited.contains(owner) || in_deadlock(*owner) {
+                chain.push("(死锁)".red().to_string());
+                break;
+            }
+            visited.push(*owner);
+            current = *owner;
+        }
+        println!("    {}", chain.join(" → "));
+    }
+}
+
+fn print_hot_threads(threads: &[JavaThread], cpu_delta: Option<&HashMap<u64, u64>>, window: Duration, options: &ThreadsOptions) {
+    // 有 /proc 时按采样窗口内的 CPU 使用率排序，否则按转储中的累计 CPU 时间排序
+    let mut ranked: Vec<(&JavaThread, f64)> = match cpu_delta {
+        Some(delta) => {
+            let ticks_per_sec = clock_ticks_per_sec();
+            threads.iter()
+                .filter_map(|t| {
+                    let ticks = *delta.get(&t.nid?)?;
+                    Some((t, ticks as f64 / ticks_per_sec / window.as_secs_f64().max(0.001) * 100.0))
+                })
+                .collect()
+        },
+        None => threads.iter().filter_map(|t| Some((t, t.cpu_ms?))).collect(),
+    };
+    ranked.retain(|(_, value)| *value > 0.0);
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+    if ranked.is_empty() {
+        println!("  最繁忙线程: {}", "无 CPU 占用数据".bright_black());
+        return;
+    }
+
+    match cpu_delta {
+        Some(_) => println!("  最繁忙线程 (采样 {:.1} 秒的 CPU 使用率):", window.as_secs_f64()),
+        None => println!("  最繁忙线程 (累计 CPU 时间):"),
+    }
+    for (n, (t, value)) in ranked.iter().take(options.top).enumerate() {
+        let value_text = match cpu_delta {
+            Some(_) => format!("{:.1}%", value),
+            None => format!("{:.0}ms", value),
+        };
+        let nid = t.nid.map_or_else(String::new, |nid| format!(" nid={}", nid));
+        println!("  {}. \"{}\" {} {}{}", n + 1, t.name.bright_cyan(), value_text.yellow().bold(), color_state(&t.state), nid.bright_black());
+        for frame in t.frames.iter().take(options.depth) {
+            println!("       {}", frame.bright_black());
+        }
+    }
+}
+
+/// 多次转储中状态和栈顶都没有变化的活跃线程（可能卡住）
+fn print_stuck_threads(history: &[Vec<JavaThread>], depth: usize) {
+    let last = &history[history.len() - 1];
+    let stuck: Vec<&JavaThread> = last.iter()
+        .filter(|t| matches!(t.state.as_str(), "RUNNABLE" | "BLOCKED") && !t.frames.is_empty())
+        .filter(|t| history[..history.len() - 1].iter().all(|dump| {
+            dump.iter().any(|other| other.name == t.name && other.state == t.state && other.frames.first() == t.frames.first())
+        }))
+        .collect();
+
+    println!("\n{}", format!("=== {} 次转储中未变化的线程 ===", history.len()).bright_blue().bold());
+    if stuck.is_empty() {
+        println!("  {}", "无".green());
+        return;
+    }
+    for t in stuck {
+        println!("  \"{}\" {}", t.name.bright_cyan(), color_state(&t.state));
+        for frame in t.frames.iter().take(depth) {
+            println!("       {}", frame.bright_black());
+        }
+    }
+}
+
+fn color_state(state: &str) -> String {
+    match state {
+        "RUNNABLE" => state.green().to_string(),
+        "BLOCKED" => state.red().bold().to_string(),
+        "WAITING" | "TIMED_WAITING" => state.yellow().to_string(),
+        _ => state.bright_black().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUMP: &str = r#"Full thread dump OpenJDK 64-Bit Server VM (17.0.15+6 mixed mode, sharing):
+
+"worker-1" #12 prio=5 os_prio=0 cpu=1250.50ms elapsed=30.12s tid=0x00007f nid=0x3039 waiting for monitor entry  [0x00007f]
+   java.lang.Thread.State: BLOCKED (on object monitor)
+	at com.example.Transfer.debit(Transfer.java:20)
+	- waiting to lock <0x00000000a1> (a java.lang.Object)
+	- locked <0x00000000b2> (a java.lang.Object)
+	at com.example.Transfer.run(Transfer.java:10)
+
+"worker-2" #13 prio=5 os_prio=0 cpu=2.10s elapsed=30.12s tid=0x00007f nid=12346 waiting on condition  [0x00007f]
+   java.lang.Thread.State: WAITING (parking)
+	at jdk.internal.misc.Unsafe.park(Native Method)
+	- parking to wait for  <0x00000000b2> (a java.util.concurrent.locks.ReentrantLock$NonfairSync)
+	at com.example.Transfer.credit(Transfer.java:30)
+
+   Locked ownable synchronizers:
+	- <0x00000000a1> (a java.util.concurrent.locks.ReentrantLock$NonfairSync)
+
+"GC Thread#0" os_prio=0 cpu=5.00ms elapsed=30.20s tid=0x00007f nid=0x3000 runnable
+
+JNI global refs: 15, weak refs: 0
+
+Found one Java-level deadlock:
+=============================
+"worker-1":
+  waiting to lock monitor 0x00007f (object 0x00000000a1, a java.lang.Object),
+"#;
+
+    #[test]
+    fn parses_threads_states_and_cpu() {
+        let threads = parse_dump(DUMP);
+        assert_eq!(threads.len(), 3);
+        assert_eq!(threads[0].name, "worker-1");
+        assert_eq!(threads[0].nid, Some(0x3039));
+        assert_eq!(threads[0].cpu_ms, Some(1250.5));
+        assert_eq!(threads[0].state, "BLOCKED");
+        assert_eq!(threads[0].frames, ["at com.example.Transfer.debit(Transfer.java:20)", "at com.example.Transfer.run(Transfer.java:10)"]);
+        assert_eq!(threads[1].nid, Some(12346));
+        assert_eq!(threads[1].cpu_ms, Some(2100.0));
+        assert_eq!(threads[1].state, "WAITING");
+        assert_eq!(threads[2].name, "GC Thread#0");
+        assert_eq!(threads[2].state, "VM");
+    }
+
+    #[test]
+    fn parses_locks_and_finds_deadlock() {
+        let threads = parse_dump(DUMP);
+        assert_eq!(threads[0].locked, ["0x00000000b2"]);
+        assert_eq!(threads[0].waiting_for, Some(("0x00000000a1".to_string(), "java.lang.Object".to_string())));
+        assert_eq!(threads[1].locked, ["0x00000000a1"]);
+        assert_eq!(threads[1].waiting_for.as_ref().map(|(address, _)| address.as_str()), Some("0x00000000b2"));
+
+        let waits = lock_waits(&threads);
+        assert_eq!(waits.get(&0).map(|(owner, _, _)| *owner), Some(1));
+        assert_eq!(waits.get(&1).map(|(owner, _, _)| *owner), Some(0));
+        assert_eq!(find_deadlocks(&waits), [vec![0, 1]]);
+    }
+
+    #[test]
+    fn extracts_dump_with_deadlock_report() {
+        let lines: Vec<String> = ["app output", "Full thread dump OpenJDK", "", "JNI global refs: 1", "", "Found one Java-level deadlock:", "...", "Found 1 deadlock.", "more output"]
+            .iter().map(|l| l.to_string()).collect();
+        let dump = extract_dump(&lines).unwrap();
+        assert!(dump.starts_with("Full thread dump"));
+        assert!(dump.ends_with("Found 1 deadlock."));
+        assert_eq!(extract_dump(&lines[..2]), None);
+    }
+}
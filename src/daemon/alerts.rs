@@ -0,0 +1,405 @@
+// alerts.rs - 日志告警
+// 守护进程每轮增量读取JAR日志的新内容，按告警规则统计匹配次数，触发时执行命令或调用 Webhook
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use regex::Regex;
+use encoding_rs::Encoding;
+use crate::core::config::{AlertRule, GlobalConfig, JarOptions};
+use crate::core::logging;
+
+/// 每次告警携带的去重后样例行数
+const MAX_SAMPLES: usize = 5;
+
+/// 每个文件每轮最多读取的字节数，避免日志暴增时阻塞守护进程
+const MAX_READ_PER_PASS: u64 = 16 * 1024 * 1024;
+
+/// Webhook 请求超时
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 用于识别轮转的文件开头字节数
+const HEAD_BYTES: u64 = 256;
+
+/// 日志文件的读取位置
+struct FileCursor {
+    /// 已读取到的位置
+    offset: u64,
+    /// 文件开头的内容，开头变化说明文件已被轮转替换
+    head: Vec<u8>,
+}
+
+/// 单条规则在单个JAR上的状态
+#[derive(Default)]
+struct RuleState {
+    /// 时间窗口内的匹配时间 (按日志行的时间换算)
+    hits: VecDeque<Instant>,
+    /// 本次统计中去重后的样例行
+    samples: Vec<String>,
+    /// 上次触发时间
+    last_fired: Option<Instant>,
+    /// 冷却期内被抑制的匹配次数
+    suppressed: u32,
+}
+
+/// 告警引擎，状态保存在守护进程内存中
+#[derive(Default)]
+pub struct AlertEngine {
+    /// 日志文件已读取到的位置
+    offsets: HashMap<String, FileCursor>,
+    /// 检查过的JAR，停止运行后仍会读完最后的输出
+    tracked_jars: HashSet<String>,
+    /// (规则名, JAR) -> 状态
+    states: HashMap<(String, String), RuleState>,
+    /// 后台发送的 Webhook 结果，下一轮写入守护进程日志
+    webhook_results: Arc<Mutex<Vec<String>>>,
+}
+
+impl AlertEngine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 检查一轮，返回需要写入守护进程日志的记录
+    pub fn evaluate(&mut self, config: &GlobalConfig, running_jars: &[String]) -> Vec<String> {
+        let mut records: Vec<String> = match self.webhook_results.lock() {
+            Ok(mut results) => std::mem::take(&mut *results),
+            Err(_) => Vec::new(),
+        };
+        let mut rules = Vec::new();
+        for rule in &config.alerts {
+            match Regex::new(&rule.pattern) {
+                Ok(regex) => rules.push((rule, regex)),
+                Err(e) => records.push(format!("告警规则 {} 的正则表达式无效: {}", rule.name, e)),
+            }
+        }
+        if rules.is_empty() {
+            return records;
+        }
+
+        // 运行中的JAR + 规则中指定的JAR + 之前检查过的JAR
+        self.tracked_jars.extend(running_jars.iter().cloned());
+        for (rule, _) in &rules {
+            if rule.jar != "*" {
+                self.tracked_jars.insert(crate::core::registry::app_name(&rule.jar));
+            }
+        }
+        let mut jars: Vec<String> = self.tracked_jars.iter().cloned().collect();
+        jars.sort();
+
+        let now = Instant::now();
+        for jar in &jars {
+            let applicable: Vec<&(&AlertRule, Regex)> = rules.iter().filter(|(rule, _)| rule.applies_to(jar)).collect();
+            if applicable.is_empty() {
+                continue;
+            }
+
+            let encoding = JarOptions::load(jar).encoding();
+            for log_file in config.get_jar_log_files(jar) {
+                for line in self.read_new_lines(&log_file, encoding) {
+                    let body = logging::strip_line_prefix(&line);
+                    let time = line_instant(&line, &config.log.timestamp_format, now);
+                    for (rule, regex) in &applicable {
+                        if regex.is_match(body) {
+                            let state = self.states.entry((rule.name.clone(), jar.clone())).or_default();
+                            state.hits.push_back(time);
+                            if state.samples.len() < MAX_SAMPLES && !state.samples.iter().any(|s| s == body) {
+                                state.samples.push(body.to_string());
+                            }
+                        }
+                    }
+                }
+            }
+
+            for (rule, _) in &applicable {
+                if let Some(state) = self.states.get_mut(&(rule.name.clone(), jar.clone())) {
+                    check_rule(rule, jar, state, now, &self.webhook_results, &mut records);
+                }
+            }
+        }
+        records
+    }
+
+    /// 读取日志文件自上次以来新增的完整行
+    fn read_new_lines(&mut self, path: &str, encoding: &'static Encoding) -> Vec<String> {
+        let len = match std::fs::metadata(path) {
+            Ok(meta) => meta.len(),
+            Err(_) => return Vec::new(),
+        };
+
+        let Ok(head) = read_head(Path::new(path)) else { return Vec::new() };
+
+        // 第一次见到的文件从末尾开始，不对历史内容告警
+        let cursor = self.offsets.entry(path.to_string()).or_insert_with(|| FileCursor { offset: len, head: head.clone() });
+        // 文件变小或开头内容变化说明已被轮转：先读完旧文件在上次之后写入的内容，再从头读取新文件
+        let rotated = len < cursor.offset || !head.starts_with(&cursor.head);
+        let mut lines = Vec::new();
+        let start = if rotated {
+            lines = drain_rotated(path, cursor, encoding);
+            0
+        } else {
+            cursor.offset
+        };
+        if start == len {
+            *cursor = FileCursor { offset: len, head };
+            return lines;
+        }
+
+        let mut buffer = Vec::new();
+        let read = File::open(path).and_then(|mut file| {
+            file.seek(SeekFrom::Start(start))?;
+            file.take((len - start).min(MAX_READ_PER_PASS)).read_to_end(&mut buffer)
+        });
+        if read.is_err() {
+            if rotated {
+                // 旧文件已读完，下一轮从头读取新文件
+                *cursor = FileCursor { offset: 0, head: Vec::new() };
+            }
+            return lines;
+        }
+
+        // 只处理完整的行，未写完的行留到下一轮
+        let complete = match buffer.iter().rposition(|&b| b == b'\n') {
+            Some(pos) => pos + 1,
+            None if buffer.len() as u64 >= MAX_READ_PER_PASS => buffer.len(),
+            None => 0,
+        };
+        self.offsets.insert(path.to_string(), FileCursor { offset: start + complete as u64, head });
+
+        lines.extend(split_lines(&buffer[..complete], encoding));
+        lines
+    }
+}
+
+/// 读取文件开头的若干字节，gzip 文件读取解压后的内容
+fn read_head(path: &Path) -> io::Result<Vec<u8>> {
+    let mut head = Vec::new();
+    logging::open_log_reader(path)?.take(HEAD_BYTES).read_to_end(&mut head)?;
+    Ok(head)
+}
+
+fn split_lines(buffer: &[u8], encoding: &'static Encoding) -> Vec<String> {
+    buffer
+        .split(|b| *b == b'\n')
+        .filter(|line| !line.is_empty())
+        .map(|line| logging::decode_log_line(line, encoding).trim_end_matches('\r').to_string())
+        .collect()
+}
+
+/// 读取轮转走的旧文件中上次读取之后的内容，以及之后轮转出的各代
+/// 旧文件按开头内容在轮转代中查找 (可能已被压缩)，找不到时返回空
+fn drain_rotated(path: &str, cursor: &FileCursor, encoding: &'static Encoding) -> Vec<String> {
+    let generations: Vec<PathBuf> = logging::list_log_generations(path).into_iter()
+        .filter(|generation| !generation.file_name().is_some_and(|name| logging::is_gc_log_file(&name.to_string_lossy())))
+        .collect();
+    let Some(position) = generations.iter().rposition(|generation| read_head(generation).is_ok_and(|head| head.starts_with(&cursor.head))) else {
+        return Vec::new();
+    };
+    let mut lines = Vec::new();
+    for (i, generation) in generations[position..].iter().enumerate() {
+        let offset = if i == 0 { cursor.offset } else { 0 };
+        let mut buffer = Vec::new();
+        let read = logging::open_log_reader(generation).and_then(|mut reader| {
+            io::copy(&mut reader.by_ref().take(offset), &mut io::sink())?;
+            reader.take(MAX_READ_PER_PASS).read_to_end(&mut buffer)
+        });
+        // 已轮转的文件不会再写入，最后一行没有换行符也处理
+        if read.is_ok() {
+            lines.extend(split_lines(&buffer, encoding));
+        }
+    }
+    lines
+}
+
+/// 日志行的时间换算成 Instant；没有时间戳或时间在未来时使用本轮检查的时间
+fn line_instant(line: &str, timestamp_format: &str, now: Instant) -> Instant {
+    let body = logging::strip_line_prefix(line);
+    let timestamp = logging::line_timestamp(line)
+        .and_then(|ts| chrono::NaiveDateTime::parse_from_str(ts, timestamp_format).ok())
+        .or_else(|| body.get(..19).and_then(|ts| chrono::NaiveDateTime::parse_from_str(&ts.replace('T', " "), "%Y-%m-%d %H:%M:%S").ok()))
+        .and_then(|ts| ts.and_local_timezone(chrono::Local).single());
+    timestamp
+        .and_then(|ts| (chrono::Local::now() - ts).to_std().ok())
+        .and_then(|age| now.checked_sub(age))
+        .unwrap_or(now)
+}
+
+/// 判断规则是否达到阈值，达到时发送通知
+fn check_rule(rule: &AlertRule, jar: &str, state: &mut RuleState, now: Instant, webhook_results: &Arc<Mutex<Vec<String>>>, records: &mut Vec<String>) {
+    let window = Duration::from_secs(rule.window_secs);
+    // 多个日志文件的行按文件依次读取，匹配时间不一定有序
+    state.hits.retain(|hit| now.duration_since(*hit) <= window);
+    if state.hits.is_empty() {
+        state.samples.clear();
+    }
+    if (state.hits.len() as u32) < rule.threshold.max(1) {
+        return;
+    }
+
+    let count = state.hits.len() as u32;
+    let cooling = state.last_fired
+        .is_some_and(|fired| now.duration_since(fired) < Duration::from_secs(rule.cooldown_secs));
+    if cooling {
+        // 每个冷却期只记录一次，之后的抑制次数在下次触发时一并报告
+        if state.suppressed == 0 {
+            records.push(format!("告警 {} ({}) 冷却中，抑制 {} 次匹配", rule.name, jar, count));
+        }
+        state.suppressed += count;
+    } else {
+        records.push(format!("告警触发 {} ({}): {} 秒内匹配 {} 次 /{}/",
+                             rule.name, jar, rule.window_secs, count, rule.pattern));
+        let payload = serde_json::json!({
+            "rule": rule.name,
+            "jar": jar,
+            "pattern": rule.pattern,
+            "count": count,
+            "window_secs": rule.window_secs,
+            "suppressed": state.suppressed,
+            "samples": state.samples,
+            "time": chrono::Local::now().to_rfc3339(),
+        });
+        if let Some(command) = &rule.command {
+            records.push(run_alert_command(command, &payload));
+        }
+        if let Some(url) = &rule.webhook {
+            records.push(post_webhook(url, &payload, webhook_results));
+        }
+        state.last_fired = Some(now);
+        state.suppressed = 0;
+    }
+
+    // 已计入的匹配不再重复统计
+    state.hits.clear();
+    state.samples.clear();
+}
+
+/// 在后台执行告警命令，告警信息通过环境变量传入
+fn run_alert_command(command: &str, payload: &serde_json::Value) -> String {
+    let mut cmd = if cfg!(target_os = "windows") {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C").arg(command);
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(command);
+        cmd
+    };
+    let field = |key: &str| match &payload[key] {
+        serde_json::Value::String(s) => s.clone(),
+        value => value.to_string(),
+    };
+    cmd.env("MANAGER_JAR_ALERT_RULE", field("rule"))
+        .env("MANAGER_JAR_ALERT_JAR", field("jar"))
+        .env("MANAGER_JAR_ALERT_COUNT", field("count"))
+        .env("MANAGER_JAR_ALERT_MESSAGE", payload["samples"][0].as_str().unwrap_or(""))
+        .env("MANAGER_JAR_ALERT_PAYLOAD", payload.to_string())
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    match cmd.spawn() {
+        Ok(mut child) => {
+            let pid = child.id();
+            // 回收子进程，不阻塞守护进程
+            std::thread::spawn(move || child.wait().ok());
+            format!("已执行告警命令 (PID: {}): {}", pid, command)
+        },
+        Err(e) => format!("执行告警命令失败: {} ({})", e, command),
+    }
+}
+
+/// 在后台线程以 JSON 形式 POST 告警到 Webhook，不阻塞守护进程的维护任务
+fn post_webhook(url: &str, payload: &serde_json::Value, results: &Arc<Mutex<Vec<String>>>) -> String {
+    let url = url.to_string();
+    let body = payload.to_string();
+    let results = Arc::clone(results);
+    let record = format!("Webhook 发送中: {}", url);
+    std::thread::spawn(move || {
+        let result = ureq::post(&url)
+            .timeout(WEBHOOK_TIMEOUT)
+            .set("Content-Type", "application/json")
+            .send_string(&body);
+        let record = match result {
+            Ok(response) => format!("Webhook 已发送 (HTTP {}): {}", response.status(), url),
+            Err(e) => format!("Webhook 发送失败: {} ({})", e, url),
+        };
+        if let Ok(mut results) = results.lock() {
+            results.push(record);
+        }
+    });
+    record
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn append(path: &Path, content: &str) {
+        std::fs::OpenOptions::new().create(true).append(true).open(path).unwrap().write_all(content.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn reads_lines_written_before_rotation() {
+        let dir = std::env::temp_dir().join(format!("manager_jar_alerts_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let log = dir.join("app.log");
+        let path = log.to_string_lossy().to_string();
+        let encoding = encoding_rs::UTF_8;
+        let mut engine = AlertEngine::new();
+
+        append(&log, "INFO started\n");
+        assert!(engine.read_new_lines(&path, encoding).is_empty());
+
+        // 两轮之间写入后轮转并压缩
+        append(&log, "ERROR first\nERROR second\n");
+        let rotated = dir.join("app.log.20260101-000000");
+        std::fs::rename(&log, &rotated).unwrap();
+        let mut encoder = flate2::write::GzEncoder::new(File::create(dir.join("app.log.20260101-000000.gz")).unwrap(), flate2::Compression::default());
+        encoder.write_all(&std::fs::read(&rotated).unwrap()).unwrap();
+        encoder.finish().unwrap();
+        std::fs::remove_file(&rotated).unwrap();
+        append(&log, "ERROR third\n");
+
+        assert_eq!(engine.read_new_lines(&path, encoding), vec!["ERROR first", "ERROR second", "ERROR third"]);
+
+        // 未压缩的轮转文件，最后一行没有换行符
+        append(&log, "ERROR fourth");
+        std::fs::rename(&log, dir.join("app.log.20260101-000001")).unwrap();
+        append(&log, "INFO restarted\n");
+        assert_eq!(engine.read_new_lines(&path, encoding), vec!["ERROR fourth", "INFO restarted"]);
+        assert!(engine.read_new_lines(&path, encoding).is_empty());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn reports_cooldown_once() {
+        let rule = AlertRule {
+            name: "errors".to_string(),
+            jar: "*".to_string(),
+            pattern: "ERROR".to_string(),
+            threshold: 1,
+            window_secs: 60,
+            cooldown_secs: 300,
+            command: None,
+            webhook: None,
+        };
+        let results = Arc::new(Mutex::new(Vec::new()));
+        let mut state = RuleState::default();
+        let mut records = Vec::new();
+        let now = Instant::now();
+        for _ in 0..3 {
+            state.hits.push_back(now);
+            check_rule(&rule, "app.jar", &mut state, now, &results, &mut records);
+        }
+        assert_eq!(records.len(), 2);
+        assert!(records[0].starts_with("告警触发"));
+        assert!(records[1].contains("冷却中"));
+        assert_eq!(state.suppressed, 2);
+    }
+}
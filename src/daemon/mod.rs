@@ -3,6 +3,8 @@
 
 pub mod thread_daemon;
 pub mod system_daemon;
+pub mod alerts;
+pub mod shipper;
 
 // 重新导出主要功能（当前暂未使用，保留供未来扩展）
 #[allow(unused_imports)]
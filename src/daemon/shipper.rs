@@ -0,0 +1,498 @@
+// shipper.rs - 日志投递
+// 守护进程每轮将各JAR日志的新增内容分批推送到 HTTP 端点（Loki push API 或 Elasticsearch _bulk）
+// 读取位置和序号持久化到数据目录，守护进程重启后从上次位置继续；发送失败时不前进并按退避时间重试
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::io::{self, BufRead, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use crate::core::config::{GlobalConfig, JarOptions, ShipperConfig};
+use crate::core::logging;
+
+/// 投递状态文件
+const SHIPPER_STATE_FILE: &str = ".Manager_Jar/data/shipper_state.json";
+
+/// 失败后的最长退避时间
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// 单个日志文件的读取位置
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+struct FileState {
+    /// 已投递到的字节位置 (正在补发轮转代时为该轮转代中的位置)
+    offset: u64,
+    /// 文件创建时间（纳秒），变化说明文件已被轮转并重建
+    #[serde(default)]
+    created: Option<u128>,
+    /// 正在补发的轮转代文件名 (不含 .gz)，None 表示读取活动日志
+    #[serde(default)]
+    generation: Option<String>,
+    /// 读取活动日志时已存在的最新轮转代，轮转后比它新的轮转代都需要补发 (空字符串表示当时没有轮转代)
+    #[serde(default)]
+    newest_generation: Option<String>,
+}
+
+/// 持久化的投递状态
+#[derive(Serialize, Deserialize, Default)]
+struct ShipperState {
+    /// 日志文件路径 -> 读取位置
+    files: BTreeMap<String, FileState>,
+    /// JAR -> 已分配的最后一个序号
+    sequences: BTreeMap<String, u64>,
+}
+
+/// 一行待投递的日志
+struct Entry {
+    timestamp_ns: i64,
+    stream: String,
+    /// 每个JAR递增的行序号
+    sequence: u64,
+    message: String,
+}
+
+/// 日志投递器，状态在守护进程中保留并持久化
+pub struct LogShipper {
+    state: ShipperState,
+    hostname: String,
+    /// 连续失败次数和下次允许重试的时间
+    failures: u32,
+    retry_at: Option<Instant>,
+}
+
+impl Default for LogShipper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LogShipper {
+    /// 加载上次保存的投递状态
+    pub fn new() -> Self {
+        let state = fs::read_to_string(SHIPPER_STATE_FILE)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self {
+            state,
+            hostname: crate::utils::system::local_hostname(),
+            failures: 0,
+            retry_at: None,
+        }
+    }
+
+    /// 投递一轮，返回需要写入守护进程日志的记录
+    pub fn ship(&mut self, config: &GlobalConfig) -> Vec<String> {
+        let mut records = Vec::new();
+        let shipper = &config.shipper;
+        if self.retry_at.is_some_and(|at| Instant::now() < at) {
+            return records;
+        }
+
+        let mut shipped = 0;
+        let mut batches = 0;
+        'files: for (jar, stream, path) in managed_log_files(&config.log.log_dir) {
+            let encoding = JarOptions::load(&jar).encoding();
+            loop {
+                // 背压: 每轮最多发送指定批数，其余留到下一轮
+                if batches >= shipper.max_batches_per_pass.max(1) {
+                    break 'files;
+                }
+                let (entries, next) = match self.read_batch(&jar, &stream, &path, encoding, config) {
+                    Ok(batch) => batch,
+                    Err(e) => {
+                        records.push(format!("日志投递读取失败 {}: {}", path.display(), e));
+                        continue 'files;
+                    }
+                };
+                if entries.is_empty() {
+                    // 只有位置变化（如文件被轮转）
+                    self.commit(&jar, &path, next, None);
+                    continue 'files;
+                }
+
+                batches += 1;
+                match self.send(shipper, &jar, &entries) {
+                    Ok(()) => {
+                        shipped += entries.len();
+                        let last_sequence = entries.last().map(|e| e.sequence);
+                        self.commit(&jar, &path, next, last_sequence);
+                        self.failures = 0;
+                        self.retry_at = None;
+                    },
+                    Err(e) => {
+                        // 发送失败不前进读取位置，下次重新发送同一批
+                        self.failures += 1;
+                        let backoff = Duration::from_secs(1 << self.failures.min(8)).min(MAX_BACKOFF);
+                        self.retry_at = Some(Instant::now() + backoff);
+                        records.push(format!("日志投递失败 ({} 秒后重试): {}", backoff.as_secs(), e));
+                        break 'files;
+                    },
+                }
+            }
+        }
+
+        if shipped > 0 {
+            records.push(format!("日志投递 {} 行 ({} 批) 到 {}", shipped, batches, shipper.url));
+        }
+        if let Err(e) = self.save() {
+            records.push(format!("保存日志投递状态失败: {}", e));
+        }
+        records
+    }
+
+    /// 读取一批新增的完整行，返回日志条目和读取后的位置
+    fn read_batch(
+        &self,
+        jar: &str,
+        stream: &str,
+        path: &Path,
+        encoding: &'static encoding_rs::Encoding,
+        config: &GlobalConfig,
+    ) -> io::Result<(Vec<Entry>, FileState)> {
+        let key = path.to_string_lossy().to_string();
+        let metadata = fs::metadata(path)?;
+        let created = file_created(&metadata);
+        let mut next_sequence = self.state.sequences.get(jar).copied().unwrap_or(0);
+
+        // 先列出轮转代再检查活动日志，列出的轮转代一定早于此时的活动日志
+        let generations = logging::list_log_generations(&key);
+        let mut state = match self.state.files.get(&key) {
+            Some(known) => known.clone(),
+            // 第一次见到的文件从头投递
+            None => FileState { offset: 0, created, generation: None, newest_generation: None },
+        };
+
+        // 文件被轮转: 按从旧到新的顺序补发之后的所有轮转代，再从新文件开头读取
+        // 文件变小、创建时间变化或出现了新的轮转代都说明文件已被轮转
+        let rotated = metadata.len() < state.offset
+            || (state.created.is_some() && created.is_some() && state.created != created)
+            || (state.newest_generation.is_some() && !newer_generations(&generations, state.newest_generation.as_deref()).is_empty());
+        if state.generation.is_none() && rotated {
+            let newer = newer_generations(&generations, state.newest_generation.as_deref());
+            state.generation = newer.first().map(|path| generation_name(path));
+            if state.generation.is_none() {
+                state.offset = 0;
+            }
+        }
+
+        while let Some(name) = state.generation.clone() {
+            if let Some(file) = generations.iter().find(|path| generation_name(path) == name) {
+                let (lines, offset) = read_lines(file, state.offset, config, true)?;
+                if !lines.is_empty() {
+                    let entries = self.to_entries(&lines, stream, encoding, config, &mut next_sequence);
+                    return Ok((entries, FileState { offset, generation: Some(name), ..state }));
+                }
+            }
+            // 这一代已读完 (或已被清理)，继续下一代，没有更新的轮转代时回到活动日志开头
+            state.generation = newer_generations(&generations, Some(&name)).first().map(|path| generation_name(path));
+            state.offset = 0;
+            if state.generation.is_none() {
+                state.created = created;
+                state.newest_generation = Some(name);
+            }
+        }
+
+        let (lines, offset) = read_lines(path, state.offset, config, false)?;
+        let entries = self.to_entries(&lines, stream, encoding, config, &mut next_sequence);
+        let newest_generation = Some(generations.last().map(|path| generation_name(path)).unwrap_or_default());
+        Ok((entries, FileState { offset, created, generation: None, newest_generation }))
+    }
+
+    fn to_entries(
+        &self,
+        lines: &[Vec<u8>],
+        stream: &str,
+        encoding: &'static encoding_rs::Encoding,
+        config: &GlobalConfig,
+        next_sequence: &mut u64,
+    ) -> Vec<Entry> {
+        let now_ns = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_nanos() as i64);
+        lines.iter().map(|bytes| {
+            let line = logging::decode_log_line(bytes, encoding);
+            let line = line.trim_end_matches('\r');
+            let (tag, body) = logging::split_line_prefix(line);
+            let timestamp_ns = logging::line_timestamp(line)
+                .and_then(|ts| chrono::NaiveDateTime::parse_from_str(ts, &config.log.timestamp_format).ok())
+                .and_then(|ts| ts.and_local_timezone(chrono::Local).single())
+                .and_then(|ts| ts.timestamp_nanos_opt())
+                .unwrap_or(now_ns);
+            *next_sequence += 1;
+            Entry {
+                timestamp_ns,
+                stream: tag.unwrap_or(stream).to_string(),
+                sequence: *next_sequence,
+                message: body.to_string(),
+            }
+        }).collect()
+    }
+
+    /// 记录投递成功后的位置和序号
+    fn commit(&mut self, jar: &str, path: &Path, next: FileState, last_sequence: Option<u64>) {
+        self.state.files.insert(path.to_string_lossy().to_string(), next);
+        if let Some(sequence) = last_sequence {
+            self.state.sequences.insert(jar.to_string(), sequence);
+        }
+    }
+
+    fn save(&self) -> Result<(), String> {
+        if let Some(parent) = Path::new(SHIPPER_STATE_FILE).parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let content = serde_json::to_string_pretty(&self.state).map_err(|e| e.to_string())?;
+        // 先写临时文件再改名，避免中途退出留下损坏的状态
+        let temp = format!("{}.tmp", SHIPPER_STATE_FILE);
+        fs::write(&temp, content).map_err(|e| e.to_string())?;
+        fs::rename(&temp, SHIPPER_STATE_FILE).map_err(|e| e.to_string())
+    }
+
+    /// 按配置的格式发送一批日志
+    fn send(&self, shipper: &ShipperConfig, jar: &str, entries: &[Entry]) -> Result<(), String> {
+        let sequence = crate::core::registry::sequences_of(jar).join(",");
+        let (body, content_type) = match shipper.format.as_str() {
+            "elasticsearch" => (self.elasticsearch_body(shipper, jar, &sequence, entries), "application/x-ndjson"),
+            _ => (self.loki_body(jar, &sequence, entries), "application/json"),
+        };
+
+        let mut request = ureq::post(&shipper.url)
+            .timeout(Duration::from_secs(shipper.timeout_secs.max(1)))
+            .set("Content-Type", content_type);
+        if !shipper.auth_header.is_empty() {
+            request = request.set("Authorization", &shipper.auth_header);
+        }
+
+        match request.send_string(&body) {
+            Ok(response) if shipper.format == "elasticsearch" => {
+                // _bulk 部分失败时整体返回 200，需要检查 errors 字段
+                let text = response.into_string().map_err(|e| format!("读取响应失败: {}", e))?;
+                let result: serde_json::Value = serde_json::from_str(&text).map_err(|e| format!("解析响应失败: {}", e))?;
+                if result["errors"].as_bool() == Some(true) {
+                    return Err("Elasticsearch 返回部分写入失败".to_string());
+                }
+                Ok(())
+            },
+            Ok(_) => Ok(()),
+            Err(ureq::Error::Status(code, response)) => {
+                let detail = response.into_string().unwrap_or_default();
+                Err(format!("HTTP {}: {}", code, detail.chars().take(200).collect::<String>()))
+            },
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Loki push API: 按 jar/host/sequence/stream 标签分组 (sequence 为JAR所属的启动序列，不属于任何序列时省略)
+    /// 行序号取值太多，不能作为标签，只作为结构化元数据
+    fn loki_body(&self, jar: &str, sequence: &str, entries: &[Entry]) -> String {
+        let mut streams: BTreeMap<&str, Vec<serde_json::Value>> = BTreeMap::new();
+        for entry in entries {
+            streams.entry(entry.stream.as_str()).or_default().push(json!([
+                entry.timestamp_ns.to_string(),
+                entry.message,
+                { "line_sequence": entry.sequence.to_string() },
+            ]));
+        }
+        let streams: Vec<serde_json::Value> = streams.into_iter().map(|(stream, values)| {
+            let mut labels = json!({ "jar": jar, "host": self.hostname, "stream": stream });
+            if !sequence.is_empty() {
+                labels["sequence"] = json!(sequence);
+            }
+            json!({ "stream": labels, "values": values })
+        }).collect();
+        json!({ "streams": streams }).to_string()
+    }
+
+    /// Elasticsearch _bulk: 以 host/jar/行序号作为文档 ID，重发同一批不会产生重复文档
+    fn elasticsearch_body(&self, shipper: &ShipperConfig, jar: &str, sequence: &str, entries: &[Entry]) -> String {
+        let mut body = String::new();
+        for entry in entries {
+            let timestamp = chrono::DateTime::from_timestamp_nanos(entry.timestamp_ns)
+                .to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+            let action = json!({ "index": {
+                "_index": shipper.index,
+                "_id": format!("{}-{}-{}", self.hostname, jar, entry.sequence),
+            }});
+            let mut document = json!({
+                "@timestamp": timestamp,
+                "jar": jar,
+                "host": self.hostname,
+                "stream": entry.stream,
+                "message": entry.message,
+            });
+            if !sequence.is_empty() {
+                document["sequence"] = json!(sequence);
+            }
+            body.push_str(&action.to_string());
+            body.push('\n');
+            body.push_str(&document.to_string());
+            body.push('\n');
+        }
+        body
+    }
+}
+
+/// 日志目录中所有JAR的活动日志: (JAR, 流, 路径)
+fn managed_log_files(log_dir: &str) -> Vec<(String, String, PathBuf)> {
+    let mut files = Vec::new();
+    if let Ok(entries) = fs::read_dir(log_dir) {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            // GC 日志不是应用输出，不投递
+            let base = match name.strip_suffix(".log") {
+                Some(base) if base != "daemon" && !logging::is_gc_log_file(&name) && entry.path().is_file() => base,
+                _ => continue,
+            };
+            let (jar, stream) = match (base.strip_suffix(".out"), base.strip_suffix(".err")) {
+                (Some(jar), _) => (jar, "out"),
+                (_, Some(jar)) => (jar, "err"),
+                _ => (base, "out"),
+            };
+            files.push((crate::core::registry::app_for_key(jar), stream.to_string(), entry.path()));
+        }
+    }
+    files.sort();
+    files
+}
+
+/// 启用日志投递时尚未投递完的轮转代文件名 (含 .gz)，按配额清理时需要保留
+pub fn unshipped_generations(config: &GlobalConfig) -> HashSet<String> {
+    let mut names = HashSet::new();
+    if !config.shipper.enabled {
+        return names;
+    }
+    let state: ShipperState = fs::read_to_string(SHIPPER_STATE_FILE)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+    for (_, _, path) in managed_log_files(&config.log.log_dir) {
+        let key = path.to_string_lossy().to_string();
+        let generations = logging::list_log_generations(&key);
+        // 正在补发的一代及之后的轮转代；读取活动日志时为之后新出现的轮转代
+        let pending = match state.files.get(&key) {
+            Some(FileState { generation: Some(name), .. }) => generations.iter().filter(|path| generation_name(path) >= *name).collect(),
+            Some(known) => newer_generations(&generations, known.newest_generation.as_deref()),
+            None => Vec::new(),
+        };
+        names.extend(pending.into_iter().filter_map(|path| path.file_name()).map(|name| name.to_string_lossy().to_string()));
+    }
+    names
+}
+
+/// 轮转代的文件名，压缩前后相同 (不含 .gz)
+fn generation_name(path: &Path) -> String {
+    let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    name.strip_suffix(".gz").map(str::to_string).unwrap_or(name)
+}
+
+/// 比指定轮转代新的轮转代 (从旧到新)；旧版本的状态没有记录，只取最新的一代
+fn newer_generations<'a>(generations: &'a [PathBuf], after: Option<&str>) -> Vec<&'a PathBuf> {
+    match after {
+        Some(after) => generations.iter().filter(|path| generation_name(path).as_str() > after).collect(),
+        None => generations.last().into_iter().collect(),
+    }
+}
+
+/// 从 offset 开始读取一批行 (按 batch_lines/batch_bytes 限制)，返回行和读取后的位置
+/// 活动日志中未写完的行留到下一轮；已轮转的文件不会再写入，最后一行没有换行符也读取
+fn read_lines(path: &Path, offset: u64, config: &GlobalConfig, finished: bool) -> io::Result<(Vec<Vec<u8>>, u64)> {
+    let mut reader: Box<dyn BufRead> = if logging::is_gzip_file(path) {
+        let mut reader = logging::open_log_reader(path)?;
+        io::copy(&mut reader.by_ref().take(offset), &mut io::sink())?;
+        reader
+    } else {
+        let mut file = fs::File::open(path)?;
+        file.seek(SeekFrom::Start(offset))?;
+        Box::new(io::BufReader::new(file))
+    };
+
+    let mut lines = Vec::new();
+    let mut offset = offset;
+    let mut bytes = 0;
+    while lines.len() < config.shipper.batch_lines.max(1) && bytes < config.shipper.batch_bytes.max(1) {
+        let mut line = Vec::new();
+        let read = reader.read_until(b'\n', &mut line)?;
+        if read == 0 || (!finished && line.last() != Some(&b'\n')) {
+            break;
+        }
+        offset += read as u64;
+        bytes += read;
+        if line.last() == Some(&b'\n') {
+            line.pop();
+        }
+        lines.push(line);
+    }
+    Ok((lines, offset))
+}
+
+fn file_created(metadata: &fs::Metadata) -> Option<u128> {
+    metadata.created().ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_nanos())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 读完所有可投递的行，每批后提交位置
+    fn drain(shipper: &mut LogShipper, path: &Path, config: &GlobalConfig) -> Vec<String> {
+        let mut messages = Vec::new();
+        loop {
+            let (entries, next) = shipper.read_batch("app.jar", "out", path, encoding_rs::UTF_8, config).unwrap();
+            let last_sequence = entries.last().map(|e| e.sequence);
+            let done = entries.is_empty() && next.generation.is_none();
+            shipper.commit("app.jar", path, next, last_sequence);
+            messages.extend(entries.into_iter().map(|e| e.message));
+            if done {
+                return messages;
+            }
+        }
+    }
+
+    #[test]
+    fn ships_every_generation_rotated_since_last_pass() {
+        let dir = std::env::temp_dir().join(format!("manager_jar_shipper_test_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("app.log");
+        let mut config = GlobalConfig::default();
+        config.shipper.batch_lines = 2;
+        let mut shipper = LogShipper { state: ShipperState::default(), hostname: "test".to_string(), failures: 0, retry_at: None };
+
+        fs::write(&path, "a1\na2\n").unwrap();
+        assert_eq!(drain(&mut shipper, &path, &config), ["a1", "a2"]);
+
+        // 两次轮转之间没有投递，旧文件还有未投递的行
+        fs::write(&path, "a1\na2\na3\na4\na5\n").unwrap();
+        fs::rename(&path, dir.join("app.log.20240101-000001")).unwrap();
+        fs::write(&path, "b1\nb2\nb3").unwrap();
+        fs::rename(&path, dir.join("app.log.20240101-000002")).unwrap();
+        fs::write(&path, "c1\nc2\npartial").unwrap();
+
+        assert_eq!(drain(&mut shipper, &path, &config), ["a3", "a4", "a5", "b1", "b2", "b3", "c1", "c2"]);
+        fs::write(&path, "c1\nc2\npartial line\n").unwrap();
+        assert_eq!(drain(&mut shipper, &path, &config), ["partial line"]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn labels_streams_with_launch_sequence_not_line_counter() {
+        let shipper = LogShipper { state: ShipperState::default(), hostname: "host1".to_string(), failures: 0, retry_at: None };
+        let entries = vec![
+            Entry { timestamp_ns: 1, stream: "out".to_string(), sequence: 7, message: "hello".to_string() },
+            Entry { timestamp_ns: 2, stream: "err".to_string(), sequence: 8, message: "oops".to_string() },
+        ];
+        let body: serde_json::Value = serde_json::from_str(&shipper.loki_body("api", "backend", &entries)).unwrap();
+        let streams = body["streams"].as_array().unwrap();
+        assert_eq!(streams.len(), 2);
+        assert_eq!(streams[1]["stream"], json!({ "jar": "api", "host": "host1", "sequence": "backend", "stream": "out" }));
+        assert_eq!(streams[1]["values"][0], json!(["1", "hello", { "line_sequence": "7" }]));
+
+        let body: serde_json::Value = serde_json::from_str(&shipper.loki_body("api", "", &entries)).unwrap();
+        assert!(body["streams"][0]["stream"].get("sequence").is_none());
+
+        let bulk = shipper.elasticsearch_body(&GlobalConfig::default().shipper, "api", "backend", &entries);
+        let lines: Vec<serde_json::Value> = bulk.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines[0]["index"]["_id"], "host1-api-7");
+        assert_eq!(lines[1]["sequence"], "backend");
+    }
+}
@@ -5,6 +5,8 @@ use std::path::PathBuf;
 use colored::Colorize;
 use crate::core::config::GlobalConfig;
 use crate::core::process;
+use crate::daemon::alerts::AlertEngine;
+use crate::daemon::shipper::LogShipper;
 
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
@@ -64,7 +66,10 @@ impl SystemDaemon {
         println!("  ✅ 自动健康检查");
         println!("  ✅ 自动日志轮转");
         println!("  ✅ 自动过期日志清理");
+        println!("  ✅ 自动压缩轮转日志 (需启用 enable_compression)");
         println!("  ✅ 自动僵尸进程清理");
+        println!("  ✅ 日志告警 (global-config 中的 [[alerts]] 规则)");
+        println!("  ✅ HTTP 日志投递 (需启用 [shipper])");
         println!("  📝 守护进程日志: {}", DAEMON_LOG_FILE.cyan());
 
         Ok(())
@@ -128,6 +133,10 @@ impl SystemDaemon {
                 println!("  • 日志轮转: {}", "启用".green());
                 println!("  • 过期日志清理: {}", "启用".green());
                 println!("  • 僵尸PID清理: {}", "启用".green());
+                let config = GlobalConfig::load();
+                println!("  • 旧日志压缩: {}", if config.log.enable_compression { "启用".green() } else { "禁用".red() });
+                println!("  • 日志告警: {} 条规则", config.alerts.len().to_string().yellow());
+                println!("  • 日志投递: {}", if config.shipper.enabled { config.shipper.url.green() } else { "禁用".red() });
                 
             } else {
                 println!("状态: {}", "已停止".bright_red());
@@ -237,13 +246,18 @@ impl SystemDaemon {
         
         writeln!(daemon_log, "[{}] 守护进程启动", chrono::Local::now().format("%Y-%m-%d %H:%M:%S")).ok();
         
+        // 告警状态（文件读取位置、窗口计数、冷却）保存在内存中
+        let mut alerts = AlertEngine::new();
+        // 日志投递的读取位置持久化在数据目录，重启后继续
+        let mut shipper = LogShipper::new();
+
         // 主循环
         loop {
             // 加载配置
             let config = GlobalConfig::load();
             
             // 执行定期任务
-            Self::perform_maintenance_tasks(&config, &mut daemon_log);
+            Self::perform_maintenance_tasks(&config, &mut daemon_log, &mut alerts, &mut shipper);
             
             // 等待下次检查
             std::thread::sleep(std::time::Duration::from_secs(config.process.health_check_interval as u64));
@@ -251,7 +265,7 @@ impl SystemDaemon {
     }
 
     /// 执行维护任务
-    fn perform_maintenance_tasks(config: &GlobalConfig, log_file: &mut fs::File) {
+    fn perform_maintenance_tasks(config: &GlobalConfig, log_file: &mut fs::File, alerts: &mut AlertEngine, shipper: &mut LogShipper) {
         use std::io::Write;
         
         let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S");
@@ -265,21 +279,48 @@ impl SystemDaemon {
             }
         }
 
-        // 2. 日志轮转
+        // 2. 日志告警（在轮转前检查，避免漏掉被轮转走的内容）
+        let jar_names: Vec<String> = running_jars.iter().map(|(jar_name, _)| jar_name.clone()).collect();
+        for record in alerts.evaluate(config, &jar_names) {
+            writeln!(log_file, "[{}] {}", timestamp, record).ok();
+        }
+
+        // 3. 日志投递（同样在轮转前，轮转后的剩余内容从旧文件补发）
+        if config.shipper.enabled {
+            for record in shipper.ship(config) {
+                writeln!(log_file, "[{}] {}", timestamp, record).ok();
+            }
+        }
+
+        // 4. 日志轮转
         if config.log.enable_rotation {
             for (jar_name, _) in &running_jars {
-                let log_path = config.get_log_file_path(jar_name);
-                if config.should_rotate_log(&log_path) {
-                    if let Err(e) = config.rotate_log(jar_name) {
-                        writeln!(log_file, "[{}] 日志轮转失败 {}: {}", timestamp, jar_name, e).ok();
-                    } else {
+                match config.rotate_jar_logs(jar_name) {
+                    Ok(0) => {},
+                    Ok(_) => {
                         writeln!(log_file, "[{}] 日志轮转成功: {}", timestamp, jar_name).ok();
-                    }
+                    },
+                    Err(e) => {
+                        writeln!(log_file, "[{}] 日志轮转失败 {}: {}", timestamp, jar_name, e).ok();
+                    },
                 }
             }
         }
 
-        // 3. 清理过期日志
+        // 5. 压缩轮转后的旧日志
+        if config.log.enable_compression {
+            match config.compress_rotated_logs() {
+                Ok(compressed) if compressed > 0 => {
+                    writeln!(log_file, "[{}] 压缩了 {} 个轮转日志文件", timestamp, compressed).ok();
+                },
+                Ok(_) => {},
+                Err(e) => {
+                    writeln!(log_file, "[{}] 压缩轮转日志失败: {}", timestamp, e).ok();
+                },
+            }
+        }
+
+        // 6. 清理过期日志
         if config.log.retention_days > 0 {
             if let Ok(cleaned) = config.cleanup_old_logs() {
                 if cleaned > 0 {
@@ -288,6 +329,17 @@ impl SystemDaemon {
             }
         }
 
+        // 7. 按磁盘配额删除最旧的轮转日志
+        match config.enforce_log_quota(&crate::daemon::shipper::unshipped_generations(&config)) {
+            Ok(0) => {},
+            Ok(removed) => {
+                writeln!(log_file, "[{}] 按磁盘配额删除了 {} 个旧日志文件", timestamp, removed).ok();
+            },
+            Err(e) => {
+                writeln!(log_file, "[{}] 磁盘配额检查失败: {}", timestamp, e).ok();
+            },
+        }
+
         if config.system.verbose {
             writeln!(log_file, "[{}] 维护任务完成", timestamp).ok();
         }
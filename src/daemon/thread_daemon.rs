@@ -94,7 +94,19 @@ impl ManagerDaemon {
                 Self::check_log_rotation(&cfg);
             }
 
-            // 3. 清理过期日志
+            // 3. 压缩轮转后的旧日志
+            if cfg.log.enable_compression {
+                if verbose {
+                    println!("🗜️ 压缩轮转日志...");
+                }
+                if let Err(e) = cfg.compress_rotated_logs() {
+                    if verbose {
+                        println!("压缩轮转日志失败: {}", e);
+                    }
+                }
+            }
+
+            // 4. 清理过期日志
             if cfg.log.retention_days > 0 {
                 if verbose {
                     println!("🧹 清理过期日志...");
@@ -106,7 +118,7 @@ impl ManagerDaemon {
                 }
             }
 
-            // 4. 清理僵尸PID文件
+            // 5. 清理僵尸PID文件
             if cfg.system.auto_cleanup_pid {
                 if verbose {
                     println!("🧹 清理僵尸PID文件...");
@@ -137,12 +149,9 @@ impl ManagerDaemon {
         let running_jars = process::get_running_jars();
         
         for (jar_name, _) in &running_jars {
-            let log_file = config.get_log_file_path(jar_name);
-            if config.should_rotate_log(&log_file) {
-                if let Err(e) = config.rotate_log(jar_name) {
-                    if config.system.verbose {
-                        print_warn!("日志轮转失败 {}: {}", jar_name, e);
-                    }
+            if let Err(e) = config.rotate_jar_logs(jar_name) {
+                if config.system.verbose {
+                    print_warn!("日志轮转失败 {}: {}", jar_name, e);
                 }
             }
         }
@@ -28,9 +28,18 @@ fn main() {
         return;
     }
     
+    // 检查是否以日志管道模式启动
+    if args.len() > 1 && args[1] == core::pipeline::PIPELINE_FLAG {
+        core::pipeline::run_pipeline_mode(&args[2..]);
+        return;
+    }
+    
     // 初始化目录结构
     utils::files::init_directories();
     
+    // 等待日志轮转触发的后台压缩完成 (包括提前返回的情况)
+    let _compressions = core::logging::BackgroundCompressionGuard;
+    
     let cli = Cli::parse();
     match &cli.command {
         Some(Commands::Completions { shell }) => {
@@ -41,62 +50,103 @@ fn main() {
                 _ => print_warn!("不支持的shell类型: {}，支持 bash/zsh/powershell", shell),
             }
         },
-        Some(Commands::Start { jar, args }) => {
-            if !utils::files::validate_jar_file(jar.as_str()) { return; }
-            if let Err(e) = core::process::start_jar(jar.as_str(), args.as_slice()) {
+        Some(Commands::Start { jar, instance, scale, args }) => {
+            let jar = instance.as_ref().map_or_else(|| jar.clone(), |name| core::instance::instance_id(jar, name));
+            let Some(jar) = core::registry::resolve_app(&jar) else { return };
+            let result = match scale {
+                Some(count) => core::instance::scale(jar.as_str(), *count, args.as_slice()),
+                None => core::process::start_jar(jar.as_str(), args.as_slice()),
+            };
+            if let Err(e) = result {
                 print_error!("{}", e);
             }
         },
-        Some(Commands::Stop { jar }) => {
-            if !utils::files::validate_jar_file(jar.as_str()) { return; }
+        Some(Commands::Stop { jar, instance }) => {
+            let jar = instance.as_ref().map_or_else(|| jar.clone(), |name| core::instance::instance_id(jar, name));
+            let Some(jar) = core::registry::resolve_app(&jar) else { return };
             if let Err(e) = core::process::stop_jar(jar.as_str()) {
                 print_error!("{}", e);
             }
         },
-        Some(Commands::Restart { jar, args }) => {
-            if !utils::files::validate_jar_file(jar.as_str()) { return; }
+        Some(Commands::Restart { jar, instance, args }) => {
+            let jar = instance.as_ref().map_or_else(|| jar.clone(), |name| core::instance::instance_id(jar, name));
+            let Some(jar) = core::registry::resolve_app(&jar) else { return };
             if let Err(e) = core::process::restart_jar(jar.as_str(), args.as_slice()) {
                 print_error!("{}", e);
             }
         },
         Some(Commands::Status { jar }) => {
-            if let Some(jar) = jar {
-                if !utils::files::validate_jar_file(jar.as_str()) { return; }
-            }
+            let jar = match jar {
+                Some(jar) => match core::registry::resolve_app(jar) {
+                    Some(app) => Some(app),
+                    None => return,
+                },
+                None => None,
+            };
             core::process::show_jar_status(jar.as_deref());
         },
-        Some(Commands::List) => {
+        Some(Commands::List { details }) => {
             let jars = utils::files::list_available_jars();
             if jars.is_empty() {
-                print_warn!("当前目录下无可用 JAR 文件");
+                print_warn!("查找目录中无可用 JAR 文件");
             } else {
                 print_success!("发现 {} 个可用 JAR 文件:", jars.len());
                 for jar in jars { 
-                    let status = if core::process::is_jar_running(&jar) { "运行中".green() } else { "已停止".red() };
-                    println!("  {} [{}]", jar.cyan(), status); 
+                    // 属于注册应用的JAR，只有应用当前使用的那个显示为运行中
+                    let app = core::registry::app_name(&jar);
+                    let current = app == jar || core::registry::jar_path(&app) == std::path::Path::new(&jar);
+                    let status = if current && core::process::is_jar_running(&app) { "运行中".green() } else { "已停止".red() };
+                    let label = if app == jar { jar.clone() } else { format!("{} ({})", jar, app) };
+                    let instances = if current { core::instance::running_instances(&app).len() } else { 0 };
+                    let status = if instances > 0 { format!("{} +{} 个实例", status, instances) } else { status.to_string() };
+                    if *details {
+                        let manifest = core::inspect::read_manifest(std::path::Path::new(&jar)).unwrap_or_default();
+                        let attribute = |key: &str| manifest.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone()).unwrap_or_else(|| "-".to_string());
+                        println!("  {} [{}] {} {}", format!("{:<30}", label).cyan(), status,
+                            format!("{:<12}", attribute("Implementation-Version")).yellow(), attribute("Implementation-Title").bright_black());
+                    } else {
+                        println!("  {} [{}]", label.cyan(), status); 
+                    }
                 }
             }
             println!();
             core::process::list_running_jars();
         },
-        Some(Commands::Log { jar, lines }) => {
-            if !utils::files::validate_jar_file(jar.as_str()) { return; }
-            core::logging::show_jar_log(jar.as_str(), *lines)
+        Some(Commands::Log { jar, lines, stderr, session, previous, follow, fields, filters }) => {
+            let Some(jar) = core::registry::resolve_app(jar) else { return };
+            let view = match core::jsonlog::JsonView::new(fields, filters) {
+                Ok(view) => view,
+                Err(e) => { print_error!("{}", e); return; }
+            };
+            use crate::core::logging::SessionSelector;
+            let selector = match (session, previous) {
+                (Some(index), _) => Some(SessionSelector::Index(*index)),
+                (None, true) => Some(SessionSelector::Previous),
+                (None, false) => None,
+            };
+            match selector {
+                Some(selector) => {
+                    if let Err(e) = core::logging::show_session_log(jar.as_str(), selector, *lines, *stderr, &view) {
+                        print_error!("{}", e);
+                    }
+                },
+                None => core::logging::show_jar_log(jar.as_str(), *lines, *stderr, *follow, &view),
+            }
         },
         Some(Commands::Kill { jar }) => {
-            if !utils::files::validate_jar_file(jar.as_str()) { return; }
+            let Some(jar) = core::registry::resolve_app(jar) else { return };
             if let Err(e) = core::process::kill_jar(jar.as_str()) {
                 print_error!("{}", e);
             }
         },
         Some(Commands::Config { jar, args }) => {
-            if !utils::files::validate_jar_file(jar.as_str()) { return; }
+            let Some(jar) = core::registry::resolve_app(jar) else { return };
             if let Err(e) = core::config::save_jar_config(jar.as_str(), args.as_slice()) {
                 print_error!("{}", e);
             }
         },
         Some(Commands::Quick { jar }) => {
-            if !utils::files::validate_jar_file(jar.as_str()) { return; }
+            let Some(jar) = core::registry::resolve_app(jar) else { return };
             if let Err(e) = core::config::quick_start_jar(jar.as_str()) {
                 print_error!("{}", e);
             }
@@ -113,8 +163,8 @@ fn main() {
             // 验证所有JAR文件
             let mut valid_jars = Vec::new();
             for jar in jars {
-                if utils::files::validate_jar_file(jar) {
-                    valid_jars.push(jar.clone());
+                if let Some(jar) = core::registry::resolve_app(jar) {
+                    valid_jars.push(jar);
                 }
             }
             if valid_jars.is_empty() {
@@ -140,6 +190,34 @@ fn main() {
                         print_error!("{}", e);
                     }
                 },
+                LogsAction::Sessions { jar } => {
+                    let Some(jar) = core::registry::resolve_app(jar) else { return };
+                    if let Err(e) = core::logging::list_sessions(jar.as_str()) {
+                        print_error!("{}", e);
+                    }
+                },
+                LogsAction::Grep { jar, pattern, ignore_case, fields, filters } => {
+                    let Some(jar) = core::registry::resolve_app(jar) else { return };
+                    let view = match core::jsonlog::JsonView::new(fields, filters) {
+                        Ok(view) => view,
+                        Err(e) => { print_error!("{}", e); return; }
+                    };
+                    if let Err(e) = core::logging::grep_logs(jar.as_str(), pattern.as_str(), *ignore_case, &view) {
+                        print_error!("{}", e);
+                    }
+                },
+                LogsAction::SyslogTest { jar } => {
+                    let Some(jar) = core::registry::resolve_app(jar) else { return };
+                    if let Err(e) = core::syslog::send_test_message(jar.as_str()) {
+                        print_error!("{}", e);
+                    }
+                },
+                LogsAction::Errors { jar, top } => {
+                    let Some(jar) = core::registry::resolve_app(jar) else { return };
+                    if let Err(e) = core::exceptions::show_error_report(jar.as_str(), *top) {
+                        print_error!("{}", e);
+                    }
+                },
             }
         },
         Some(Commands::Configs { action }) => {
@@ -148,17 +226,23 @@ fn main() {
                     core::config::list_configs();
                 },
                 ConfigsAction::Show { jar } => {
-                    if !utils::files::validate_jar_file(jar.as_str()) { return; }
+                    let Some(jar) = core::registry::resolve_app(jar) else { return };
                     if let Err(e) = core::config::show_config(jar.as_str()) {
                         print_error!("{}", e);
                     }
                 },
                 ConfigsAction::Delete { jar } => {
-                    if !utils::files::validate_jar_file(jar.as_str()) { return; }
+                    let Some(jar) = core::registry::resolve_app(jar) else { return };
                     if let Err(e) = core::config::delete_config(jar.as_str()) {
                         print_error!("{}", e);
                     }
                 },
+                ConfigsAction::Set { jar, key, value } => {
+                    let Some(jar) = core::registry::resolve_app(jar) else { return };
+                    if let Err(e) = core::config::set_jar_option(jar.as_str(), key.as_str(), value.as_str()) {
+                        print_error!("{}", e);
+                    }
+                },
             }
         },
         Some(Commands::GlobalConfig { action }) => {
@@ -193,6 +277,9 @@ fn main() {
                         },
                         Err(e) => print_error!("清理过期日志失败: {}", e),
                     }
+                    if let Err(e) = config.enforce_log_quota(&daemon::shipper::unshipped_generations(&config)) {
+                        print_error!("按磁盘配额清理日志失败: {}", e);
+                    }
                 },
                 GlobalConfigAction::SetLogDir { path } => {
                     let mut config = GlobalConfig::load();
@@ -212,6 +299,17 @@ fn main() {
                         print_success!("日志保存天数已设置为: {} 天", days.to_string().yellow());
                     }
                 },
+                GlobalConfigAction::SetLogQuota { size_mb } => {
+                    let mut config = GlobalConfig::load();
+                    config.log.quota_mb = *size_mb;
+                    if let Err(e) = config.save() {
+                        print_error!("保存配置失败: {}", e);
+                    } else if *size_mb > 0 {
+                        print_success!("日志目录磁盘配额已设置为: {} MB，由守护进程执行", size_mb.to_string().yellow());
+                    } else {
+                        print_success!("日志目录磁盘配额已{}", "取消".yellow());
+                    }
+                },
                 GlobalConfigAction::SetMaxLogSize { size_mb } => {
                     let mut config = GlobalConfig::load();
                     config.log.max_file_size_mb = *size_mb;
@@ -221,6 +319,86 @@ fn main() {
                         print_success!("日志文件最大大小已设置为: {} MB", size_mb.to_string().yellow());
                     }
                 },
+                GlobalConfigAction::SetLogPipeline { enable } => {
+                    let mut config = GlobalConfig::load();
+                    config.log.enable_pipeline = *enable;
+                    if let Err(e) = config.save() {
+                        print_error!("保存配置失败: {}", e);
+                    } else {
+                        let status = if *enable { "启用".green() } else { "禁用".red() };
+                        print_success!("日志管道已{}，将在下次启动JAR时生效", status);
+                    }
+                },
+                GlobalConfigAction::SetSearchDirs { dirs, depth } => {
+                    if let Some(dir) = dirs.iter().find(|dir| !std::path::Path::new(dir).is_dir()) {
+                        print_error!("目录不存在: {}", dir);
+                        return;
+                    }
+                    let mut config = GlobalConfig::load();
+                    config.discovery.search_dirs = dirs.clone();
+                    if let Some(depth) = depth {
+                        config.discovery.max_depth = (*depth).max(1);
+                    }
+                    if let Err(e) = config.save() {
+                        print_error!("保存配置失败: {}", e);
+                    } else {
+                        print_success!("查找目录已设置为: {} (深度 {})", dirs.join(", ").cyan(), config.discovery.max_depth.to_string().yellow());
+                    }
+                },
+                GlobalConfigAction::SetPortRange { range } => {
+                    let parsed = range.split_once('-')
+                        .and_then(|(start, end)| Some((start.trim().parse::<u16>().ok()?, end.trim().parse::<u16>().ok()?)))
+                        .filter(|(start, end)| *start > 0 && start <= end);
+                    let Some((start, end)) = parsed else {
+                        print_error!("无效的端口范围: {} (格式: 20000-29999)", range);
+                        return;
+                    };
+                    let mut config = GlobalConfig::load();
+                    config.ports.range_start = start;
+                    config.ports.range_end = end;
+                    if let Err(e) = config.save() {
+                        print_error!("保存配置失败: {}", e);
+                    } else {
+                        print_success!("自动端口范围已设置为: {}", format!("{}-{}", start, end).yellow());
+                    }
+                },
+                GlobalConfigAction::SetJavaHome { value } => {
+                    let mut config = GlobalConfig::load();
+                    if value == "off" {
+                        config.process.java_home.clear();
+                    } else if let Err(e) = core::jdk::resolve_home(value) {
+                        print_error!("{}", e);
+                        return;
+                    } else {
+                        config.process.java_home = value.clone();
+                    }
+                    if let Err(e) = config.save() {
+                        print_error!("保存配置失败: {}", e);
+                    } else if config.process.java_home.is_empty() {
+                        print_success!("默认 Java 已恢复为 PATH 中的 java");
+                    } else {
+                        print_success!("默认 Java 已设置为: {}，将在下次启动JAR时生效", value.cyan());
+                    }
+                },
+                GlobalConfigAction::SetSyslog { address } => {
+                    let mut config = GlobalConfig::load();
+                    if address == "off" {
+                        config.syslog.enabled = false;
+                    } else if let Err(e) = core::syslog::SyslogTarget::parse(address) {
+                        print_error!("{}", e);
+                        return;
+                    } else {
+                        config.syslog.enabled = true;
+                        config.syslog.address = address.clone();
+                    }
+                    if let Err(e) = config.save() {
+                        print_error!("保存配置失败: {}", e);
+                    } else if config.syslog.enabled {
+                        print_success!("syslog 转发已启用: {}，将在下次启动JAR时生效", address.cyan());
+                    } else {
+                        print_success!("syslog 转发已{}", "禁用".red());
+                    }
+                },
                 GlobalConfigAction::SetLogRotation { enable } => {
                     let mut config = GlobalConfig::load();
                     config.log.enable_rotation = *enable;
@@ -255,6 +433,122 @@ fn main() {
                 },
             }
         },
+        Some(Commands::Threads { jar, count, interval, top, depth }) => {
+            let Some(jar) = core::registry::resolve_app(jar) else { return };
+            let options = core::threads::ThreadsOptions {
+                count: *count,
+                interval: *interval,
+                top: *top,
+                depth: *depth,
+            };
+            if let Err(e) = core::threads::analyze_threads(jar.as_str(), &options) {
+                print_error!("{}", e);
+            }
+        },
+        Some(Commands::Heap { action }) => {
+            let result = match action {
+                HeapAction::Histo { jar, all, top } => {
+                    let Some(jar) = core::registry::resolve_app(jar) else { return };
+                    core::heap::capture_histogram(jar.as_str(), *all, *top)
+                },
+                HeapAction::Diff { jar, from, to, top } => core::heap::diff_histograms(&core::registry::app_name(jar), from, to, *top),
+                HeapAction::Dump { jar, all } => {
+                    let Some(jar) = core::registry::resolve_app(jar) else { return };
+                    core::heap::dump_heap(jar.as_str(), *all)
+                },
+                HeapAction::List { jar } => {
+                    core::heap::list_snapshots(&core::registry::app_name(jar));
+                    Ok(())
+                },
+            };
+            if let Err(e) = result {
+                print_error!("{}", e);
+            }
+        },
+        Some(Commands::Gc { jar, top }) => {
+            if let Err(e) = core::gc::show_gc_summary(&core::registry::app_name(jar), *top) {
+                print_error!("{}", e);
+            }
+        },
+        Some(Commands::Inspect { jar, manifest }) => {
+            let Some(jar) = core::registry::resolve_app(jar) else { return };
+            if let Err(e) = core::inspect::inspect_jar(jar.as_str(), *manifest) {
+                print_error!("{}", e);
+            }
+        },
+        Some(Commands::Deploy { app, artifact }) => {
+            if let Err(e) = core::deploy::deploy(&core::registry::app_name(app), artifact.as_str()) {
+                print_error!("{}", e);
+            }
+        },
+        Some(Commands::Releases { app }) => {
+            core::deploy::list_releases(&core::registry::app_name(app));
+        },
+        Some(Commands::Rollback { app, version }) => {
+            if let Err(e) = core::deploy::rollback(&core::registry::app_name(app), version.as_deref()) {
+                print_error!("{}", e);
+            }
+        },
+        Some(Commands::Apps { action }) => {
+            let result = match action {
+                AppsAction::List => {
+                    core::registry::list_apps();
+                    Ok(())
+                },
+                AppsAction::Add { name, jar } => core::registry::add_app(name, jar),
+                AppsAction::Remove { name } => core::registry::remove_app(name),
+                AppsAction::Alias { alias, app } => core::registry::add_alias(alias, app),
+                AppsAction::Unalias { alias } => core::registry::remove_alias(alias),
+            };
+            if let Err(e) = result {
+                print_error!("{}", e);
+            }
+        },
+        Some(Commands::Jdks) => {
+            core::jdk::show_jdks();
+        },
+        Some(Commands::Ports { managed }) => {
+            if let Err(e) = core::ports::show_ports(*managed) {
+                print_error!("{}", e);
+            }
+        },
+        Some(Commands::Jfr { action }) => {
+            let app = |jar: &String| core::registry::app_name(jar);
+            let result = match action {
+                JfrAction::Start { jar, name, duration, settings } => core::jfr::start_recording(&app(jar), name, duration.as_deref(), settings),
+                JfrAction::Dump { jar, name } => core::jfr::dump_recording(&app(jar), name.as_deref()),
+                JfrAction::Stop { jar, name, discard } => core::jfr::stop_recording(&app(jar), name, *discard),
+                JfrAction::List { jar } => {
+                    core::jfr::list_recordings(&app(jar));
+                    Ok(())
+                },
+            };
+            if let Err(e) = result {
+                print_error!("{}", e);
+            }
+        },
+        Some(Commands::Bundle { jar, all, threads, lines, rotated, output }) => {
+            let (jars, label) = match jar {
+                Some(jar) if !*all => {
+                    let Some(jar) = core::registry::resolve_app(jar) else { return };
+                    (vec![jar.clone()], core::registry::state_key(&jar))
+                },
+                _ => (utils::files::list_available_jars(), "all".to_string()),
+            };
+            let options = core::bundle::BundleOptions {
+                lines: *lines,
+                rotated: *rotated,
+                threads: *threads,
+                output: output.clone(),
+            };
+            match core::bundle::create_bundle(&jars, &label, &options) {
+                Ok(path) => {
+                    let size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+                    print_success!("诊断包已生成: {} ({})", path.display().to_string().cyan(), utils::files::format_file_size(size));
+                },
+                Err(e) => print_error!("{}", e),
+            }
+        },
         Some(Commands::Version) => show_version(),
         None => {
             print_usage();
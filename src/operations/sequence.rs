@@ -7,7 +7,7 @@ use std::thread;
 use std::time::Duration;
 
 use crate::core::process::{start_jar_simple, stop_jar, is_jar_running};
-use crate::utils::files::validate_jar_file;
+use crate::core::registry::{app_name, resolve_app};
 
 const SEQUENCE_DIR: &str = ".Manager_Jar/sequences";
 
@@ -48,12 +48,12 @@ fn create_sequence(args: &[String]) -> Result<(), String> {
     }
 
     let sequence_name = &args[0];
-    let jars = &args[1..];
-
-    // 验证所有JAR文件是否存在
-    for jar in jars {
-        if !validate_jar_file(jar) {
-            return Err(format!("JAR文件不存在: {}", jar));
+    // 验证所有JAR文件是否存在，别名解析为应用名保存
+    let mut jars = Vec::new();
+    for jar in &args[1..] {
+        match resolve_app(jar) {
+            Some(app) => jars.push(app),
+            None => return Err(format!("JAR文件不存在: {}", jar)),
         }
     }
 
@@ -313,10 +313,12 @@ fn load_sequence(sequence_name: &str) -> Result<Vec<String>, String> {
     }
 
     let content = fs::read_to_string(&sequence_file).map_err(|e| format!("读取序列失败: {}", e))?;
+    // 注册为应用之前保存的JAR文件名按应用名处理
     let jars: Vec<String> = content
         .lines()
-        .map(|s| s.trim().to_string())
+        .map(|s| s.trim())
         .filter(|s| !s.is_empty())
+        .map(app_name)
         .collect();
 
     if jars.is_empty() {
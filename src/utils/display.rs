@@ -38,8 +38,20 @@ pub fn print_usage() {
     println!("  {}           强制终止应用", "kill".bright_yellow());
     println!("  {}         显示应用状态", "status".bright_yellow());
     println!("  {}           列出运行中的应用", "list".bright_yellow());
+    println!("  {}         部署新版本", "deploy".bright_yellow());
+    println!("  {}       查看已部署的版本", "releases".bright_yellow());
+    println!("  {}       回滚版本", "rollback".bright_yellow());
+    println!("  {}           应用注册表和别名", "apps".bright_yellow());
     println!();
     println!("{}", "工具命令:".bright_green());
+    println!("  {}        检查JAR内容", "inspect".bright_yellow());
+    println!("  {}        线程转储分析", "threads".bright_yellow());
+    println!("  {}           堆直方图/堆转储", "heap".bright_yellow());
+    println!("  {}             GC 日志汇总", "gc".bright_yellow());
+    println!("  {}            JFR 录制", "jfr".bright_yellow());
+    println!("  {}           已安装的 JDK", "jdks".bright_yellow());
+    println!("  {}          监听端口和所属应用", "ports".bright_yellow());
+    println!("  {}         导出诊断包", "bundle".bright_yellow());
     println!("  {}        显示版本信息", "version".bright_yellow());
     println!("  {}           显示帮助", "help".bright_yellow());
     println!("  {}   生成补全脚本", "completions".bright_yellow());
@@ -38,24 +38,35 @@ pub fn validate_jar_file(jar_file: &str) -> bool {
     valid
 }
 
-/// 列出当前目录的JAR文件
+/// 列出查找目录 (全局配置 discovery) 中的JAR文件
+/// 当前目录下的JAR用文件名表示，排在前面；其余用完整路径表示
 pub fn list_available_jars() -> Vec<String> {
+    let discovery = crate::core::config::GlobalConfig::load().discovery;
     let mut jars = Vec::new();
     
-    if let Ok(entries) = fs::read_dir(".") {
-        for entry in entries.flatten() {
-            if let Some(file_name) = entry.file_name().to_str() {
-                if file_name.ends_with(".jar") && entry.path().is_file() {
-                    jars.push(file_name.to_string());
-                }
-            }
-        }
+    for dir in &discovery.search_dirs {
+        collect_jars(Path::new(dir), discovery.max_depth.max(1), &mut jars);
     }
     
-    jars.sort();
+    jars.sort_by(|a, b| (Path::new(a).is_absolute(), a).cmp(&(Path::new(b).is_absolute(), b)));
+    jars.dedup();
     jars
 }
 
+/// 递归查找目录中的JAR，depth 为剩余深度；跳过隐藏目录 (如 .Manager_Jar)
+fn collect_jars(dir: &Path, depth: usize, jars: &mut Vec<String>) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        if path.is_file() && name.ends_with(".jar") {
+            jars.push(crate::core::registry::normalize_path(&path.display().to_string()));
+        } else if depth > 1 && path.is_dir() && !name.starts_with('.') {
+            collect_jars(&path, depth - 1, jars);
+        }
+    }
+}
+
 /// 格式化文件大小
 #[allow(dead_code)]
 pub fn format_file_size(size: u64) -> String {
@@ -4,6 +4,7 @@
 pub mod macros;
 pub mod files;
 pub mod display;
+pub mod system;
 
 // 重新导出常用功能
 // Individual functions can be imported as needed